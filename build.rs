@@ -0,0 +1,18 @@
+use std::{env, fs, path::Path};
+
+// the migrations are embedded with `sqlx::migrate!`, rebuild when they change
+fn main() {
+    println!("cargo:rerun-if-changed=migrations");
+    geoip();
+}
+
+/// bundle `data/ip2asn-v4.tsv` into the binary, see the `geoip` script
+fn geoip() {
+    let src = "data/ip2asn-v4.tsv";
+    println!("cargo:rerun-if-changed={src}");
+    let out = Path::new(&env::var("OUT_DIR").unwrap()).join("ip2asn-v4.tsv");
+    if fs::copy(src, &out).is_err() {
+        println!("cargo:warning={src} is missing, run ./geoip to bundle it");
+        fs::write(&out, "").unwrap();
+    }
+}
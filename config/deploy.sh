@@ -42,10 +42,11 @@ if check_diff "config/*.service"; then
     echo $SPACER
 fi
 
-if check_diff "src/*"; then
+if check_diff "src/*" || check_diff "migrations/*"; then
+    [[ -f data/ip2asn-v4.tsv ]] || ./geoip
     echo "$EG cargo build bot"
     send_message "building bot"
-    cargo build -r
+    SQLX_OFFLINE=true cargo build -r
     [[ $? = 0 ]] && e="✅" || e="❌"
     send_message "bot build status: $e"
     echo $SPACER
@@ -0,0 +1 @@
+alter table settings add column fj_fail_closed boolean not null default false;
@@ -0,0 +1,13 @@
+alter table channels add column url text not null default '';
+alter table channels add column claw_days integer not null default 3;
+alter table channels add column joins integer not null default 0;
+
+create table if not exists channel_joins (
+    id integer primary key not null,
+    channel integer not null references channels(id) on delete cascade,
+    karbar integer not null references karbars(tid) on delete cascade,
+    points integer not null,
+    created_at integer not null,
+    clawed boolean not null default false,
+    unique (channel, karbar)
+);
@@ -0,0 +1,19 @@
+alter table flyers add column starts_at integer not null default 0;
+alter table flyers add column ends_at integer not null default 0;
+alter table flyers add column daily_max_views integer not null default -1;
+alter table flyers add column day integer not null default 0;
+alter table flyers add column day_views integer not null default 0;
+alter table flyers add column user_cap integer not null default -1;
+alter table flyers add column weight integer not null default 1;
+alter table flyers add column audience integer not null default 0;
+alter table flyers add column lang text;
+
+alter table karbars add column language_code text;
+
+create table if not exists flyer_views (
+    flyer integer not null references flyers(id) on delete cascade,
+    karbar integer not null references karbars(tid) on delete cascade,
+    day integer not null,
+    views integer not null default 0,
+    primary key (flyer, karbar, day)
+);
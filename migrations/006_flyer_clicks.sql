@@ -0,0 +1,9 @@
+alter table flyers add column clicks integer not null default 0;
+
+create table if not exists flyer_clicks (
+    id integer primary key not null,
+    flyer integer not null references flyers(id) on delete cascade,
+    karbar integer not null references karbars(tid) on delete cascade,
+    created_at integer not null
+);
+create index if not exists flyer_clicks_flyer on flyer_clicks(flyer);
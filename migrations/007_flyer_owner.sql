@@ -0,0 +1,6 @@
+alter table flyers add column owner integer references karbars(tid) on delete set null;
+alter table flyers add column status integer not null default 0;
+alter table flyers add column reason text;
+alter table flyers add column charge_id text;
+
+alter table settings add column ad_star_price integer not null default 50;
@@ -0,0 +1,2 @@
+-- null means follow the telegram language_code
+alter table karbars add column lang text;
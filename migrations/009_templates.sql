@@ -0,0 +1,7 @@
+create table if not exists templates (
+    name text not null,
+    lang text not null,
+    body text not null,
+    updated_at integer not null default 0,
+    primary key (name, lang)
+);
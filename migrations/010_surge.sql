@@ -0,0 +1,16 @@
+-- extra percent of the cost for every stacked request
+alter table settings add column proxy_surge text not null default '0,0.1,3,10,30,60,110,170,300,1000';
+alter table settings add column v2ray_surge text not null default '0,0.1,3,10,30,60,110,170,300,1000';
+alter table settings add column vip_surge text not null default '0,0.1,3,10,30,60,110,170,300,1000';
+-- seconds for a stack to drop by one
+alter table settings add column surge_decay integer not null default 21600;
+
+alter table karbars add column proxy_stack integer not null default 0;
+alter table karbars add column proxy_stack_at integer not null default 0;
+alter table karbars add column v2ray_stack integer not null default 0;
+alter table karbars add column v2ray_stack_at integer not null default 0;
+alter table karbars add column vip_stack integer not null default 0;
+alter table karbars add column vip_stack_at integer not null default 0;
+
+alter table karbars drop column price_stack;
+alter table karbars drop column last_request;
@@ -0,0 +1 @@
+alter table karbars add column skip_confirm boolean not null default false;
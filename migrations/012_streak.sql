@@ -0,0 +1,7 @@
+-- extra percent of free_points for each day of a claim streak
+alter table settings add column streak_bonus text not null default '0,10,20,30,50,75,100';
+-- seconds after the next claim is available before the streak is lost
+alter table settings add column streak_window integer not null default 86400;
+
+alter table karbars add column streak integer not null default 0;
+alter table karbars add column streak_reminded boolean not null default false;
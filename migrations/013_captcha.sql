@@ -0,0 +1,20 @@
+alter table settings add column captcha_enabled boolean not null default true;
+-- free point claims between two captchas, 0 only checks new karbars
+alter table settings add column captcha_every integer not null default 5;
+alter table settings add column captcha_tries integer not null default 3;
+-- seconds a karbar is locked out after too many wrong answers
+alter table settings add column captcha_lockout integer not null default 3600;
+
+alter table karbars add column verified boolean not null default false;
+alter table karbars add column invited_by integer;
+alter table karbars add column captcha_fails integer not null default 0;
+alter table karbars add column captcha_locked_until integer not null default 0;
+alter table karbars add column captcha_claims integer not null default 0;
+update karbars set verified = true;
+
+create table if not exists captchas (
+    tid integer primary key not null references karbars(tid) on delete cascade,
+    answer integer not null,
+    purpose text not null,
+    created_at integer not null
+);
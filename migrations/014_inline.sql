@@ -0,0 +1,3 @@
+-- inline shares in the current rate limit window
+alter table karbars add column inline_shares integer not null default 0;
+alter table karbars add column inline_window_at integer not null default 0;
@@ -0,0 +1,10 @@
+-- from the offline geoip database, null when unknown
+alter table proxies add column country text;
+alter table proxies add column asn integer;
+
+-- preferred proxy country, null means any
+alter table karbars add column region text;
+
+alter table settings add column bundle_size integer not null default 3;
+-- percent off the price of the proxies in a bundle
+alter table settings add column bundle_discount integer not null default 20;
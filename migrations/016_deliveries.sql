@@ -0,0 +1,14 @@
+-- last time a karbar received an item, recent ones are not handed out again
+create table if not exists proxy_deliveries (
+    karbar integer not null references karbars(tid) on delete cascade,
+    proxy integer not null references proxies(id) on delete cascade,
+    at integer not null,
+    primary key (karbar, proxy)
+);
+
+create table if not exists v2rays_deliveries (
+    karbar integer not null references karbars(tid) on delete cascade,
+    v2ray integer not null references v2rays(id) on delete cascade,
+    at integer not null,
+    primary key (karbar, v2ray)
+);
@@ -0,0 +1,7 @@
+-- vip messages a karbar paid for, see settings.vip_msg
+create table if not exists vip_deliveries (
+    karbar integer not null references karbars(tid) on delete cascade,
+    msg integer not null,
+    at integer not null,
+    primary key (karbar, msg)
+);
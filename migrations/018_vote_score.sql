@@ -0,0 +1,17 @@
+alter table proxy_votes add column at integer not null default 0;
+alter table proxy_votes add column weight real not null default 1;
+alter table v2rays_votes add column at integer not null default 0;
+alter table v2rays_votes add column weight real not null default 1;
+update proxy_votes set at = cast(strftime('%s', 'now') as integer);
+update v2rays_votes set at = cast(strftime('%s', 'now') as integer);
+
+-- wilson lower bound of the decayed votes, 0.5 without enough votes
+alter table proxies add column score real not null default 0.5;
+alter table v2rays add column score real not null default 0.5;
+
+-- seconds for the weight of a vote to halve
+alter table settings add column vote_half_life integer not null default 259200;
+-- decayed vote weight needed before auto disable
+alter table settings add column vote_min_weight integer not null default 10;
+-- auto disable when the down vote lower bound reaches this percent
+alter table settings add column vote_disable_pct integer not null default 60;
@@ -0,0 +1,2 @@
+-- seconds after a delivery that the karbar can vote or change its vote
+alter table settings add column vote_window integer not null default 604800;
@@ -0,0 +1,23 @@
+create table if not exists submissions (
+    id integer primary key not null,
+    karbar integer not null references karbars(tid) on delete cascade,
+    server text not null,
+    port text not null,
+    secret text not null,
+    -- see Submission::STATUS_PENDING
+    status integer not null default 0,
+    -- milliseconds to connect when submitted
+    ping integer not null default 0,
+    -- the proxy added on approval
+    proxy integer references proxies(id) on delete set null,
+    -- the good votes reward was paid
+    vote_rewarded boolean not null default false,
+    created_at integer not null
+);
+create index if not exists submissions_karbar on submissions(karbar);
+create index if not exists submissions_status on submissions(status);
+
+-- points for an approved submission
+alter table settings add column submit_reward integer not null default 50;
+-- points when a submitted proxy gets enough good votes
+alter table settings add column submit_vote_reward integer not null default 100;
@@ -0,0 +1,2 @@
+-- last v2ray auto update, read by the bot but never created before
+alter table settings add column v2ray_last_auto_update integer not null default 0;
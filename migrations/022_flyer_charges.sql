@@ -0,0 +1,15 @@
+-- every stars payment of a flyer, a flyer can be topped up many times
+create table if not exists flyer_charges (
+    id integer primary key not null,
+    flyer integer not null references flyers(id) on delete cascade,
+    charge_id text not null unique,
+    stars integer not null default 0,
+    views integer not null default 0,
+    refunded boolean not null default false,
+    created_at integer not null
+);
+create index if not exists flyer_charges_flyer on flyer_charges(flyer);
+
+insert or ignore into flyer_charges(flyer, charge_id, created_at)
+    select id, charge_id, 0 from flyers where charge_id is not NULL;
+alter table flyers drop column charge_id;
@@ -0,0 +1,119 @@
+-- the tables behind crate::db::Repo, same columns as the sqlite schema
+
+create table if not exists karbars (
+    tid bigint primary key not null,
+    fullname text not null,
+    username text,
+    banned boolean not null default false,
+    created_at bigint not null,
+    updated_at bigint not null,
+    points bigint not null default 0,
+    last_free_point_at bigint not null default 0,
+    invite_code text not null unique,
+    blocked boolean not null default false,
+    language_code text,
+    lang text,
+    proxy_stack bigint not null default 0,
+    proxy_stack_at bigint not null default 0,
+    v2ray_stack bigint not null default 0,
+    v2ray_stack_at bigint not null default 0,
+    vip_stack bigint not null default 0,
+    vip_stack_at bigint not null default 0,
+    skip_confirm boolean not null default false,
+    streak bigint not null default 0,
+    streak_reminded boolean not null default false,
+    verified boolean not null default false,
+    invited_by bigint,
+    captcha_fails bigint not null default 0,
+    captcha_locked_until bigint not null default 0,
+    captcha_claims bigint not null default 0,
+    inline_shares bigint not null default 0,
+    inline_window_at bigint not null default 0,
+    region text
+);
+
+create table if not exists settings (
+    id bigint primary key not null,
+    invite_points bigint not null default 100,
+    free_points bigint not null default 100,
+    proxy_cost bigint not null default 100,
+    v2ray_cost bigint not null default 100,
+    vip_cost bigint not null default 200,
+    vip_views bigint not null default 0,
+    vip_max_views bigint not null default 100,
+    vip_msg bigint,
+    donate_msg bigint,
+    ch_last_sent bigint not null default 0,
+    star_point_price bigint not null default 2,
+    free_point_delay bigint not null default 43200,
+    total_stars bigint not null default 0,
+    fj_fail_closed boolean not null default false,
+    ad_star_price bigint not null default 50,
+    proxy_surge text not null default '0,0.1,3,10,30,60,110,170,300,1000',
+    v2ray_surge text not null default '0,0.1,3,10,30,60,110,170,300,1000',
+    vip_surge text not null default '0,0.1,3,10,30,60,110,170,300,1000',
+    surge_decay bigint not null default 21600,
+    streak_bonus text not null default '0,10,20,30,50,75,100',
+    streak_window bigint not null default 86400,
+    captcha_enabled boolean not null default true,
+    captcha_every bigint not null default 5,
+    captcha_tries bigint not null default 3,
+    captcha_lockout bigint not null default 3600,
+    bundle_size bigint not null default 3,
+    bundle_discount bigint not null default 20,
+    vote_half_life bigint not null default 259200,
+    vote_min_weight bigint not null default 10,
+    vote_disable_pct bigint not null default 60,
+    vote_window bigint not null default 604800,
+    submit_reward bigint not null default 50,
+    submit_vote_reward bigint not null default 100,
+    v2ray_last_auto_update bigint not null default 0
+);
+
+create table if not exists proxies (
+    id bigint generated by default as identity primary key,
+    server text not null,
+    port text not null,
+    secret text not null,
+    up_votes bigint not null default 0,
+    dn_votes bigint not null default 0,
+    disabled boolean not null default false,
+    country text,
+    asn bigint,
+    score double precision not null default 0.5,
+    unique (server, port, secret)
+);
+
+create table if not exists v2rays (
+    id bigint generated by default as identity primary key,
+    label text not null,
+    link text not null unique,
+    up_votes bigint not null default 0,
+    dn_votes bigint not null default 0,
+    disabled boolean not null default false,
+    score double precision not null default 0.5
+);
+
+create table if not exists flyers (
+    id bigint generated by default as identity primary key,
+    label text not null,
+    link text,
+    mid bigint not null,
+    views bigint not null default 0,
+    max_views bigint not null default -1,
+    disabled boolean not null default false,
+    starts_at bigint not null default 0,
+    ends_at bigint not null default 0,
+    daily_max_views bigint not null default -1,
+    day bigint not null default 0,
+    day_views bigint not null default 0,
+    user_cap bigint not null default -1,
+    weight bigint not null default 1,
+    audience bigint not null default 0,
+    lang text,
+    clicks bigint not null default 0,
+    owner bigint references karbars(tid) on delete set null,
+    status bigint not null default 0,
+    reason text,
+    charge_id text
+);
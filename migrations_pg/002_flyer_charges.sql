@@ -0,0 +1,2 @@
+-- the stars payments of a flyer live in flyer_charges on sqlite
+alter table flyers drop column if exists charge_id;
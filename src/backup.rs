@@ -0,0 +1,152 @@
+//! consistent database backups with `VACUUM INTO`, rotated on disk.
+//! restores are checked, staged next to the database and swapped in on
+//! the next start
+
+use crate::{
+    Ctx, TB,
+    config::Config,
+    db::{integrity_check, schema_version},
+    error::AppErr,
+    utils,
+};
+use sqlx::{
+    Connection, SqliteConnection, SqlitePool, sqlite::SqliteConnectOptions,
+};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use teloxide::prelude::*;
+
+/// copies of both databases from one run
+pub struct Backup {
+    pub at: i64,
+    pub main: PathBuf,
+    pub storage: PathBuf,
+}
+
+fn path_str(path: &Path) -> String {
+    path.to_string_lossy().into_owned()
+}
+
+/// the uploaded database waiting to replace the main one
+pub fn staged() -> PathBuf {
+    PathBuf::from(format!("{}.restore", Config::get().db_path))
+}
+
+/// where an uploaded database waits for the owner to confirm
+pub fn upload() -> PathBuf {
+    Config::get().backup_dir.join("upload.db")
+}
+
+/// copy both databases into a new backup and drop the oldest ones
+pub async fn create(ctx: &Ctx) -> Result<Backup, AppErr> {
+    let conf = Config::get();
+    let at = utils::now();
+    let dir = conf.backup_dir.join(format!("tonel-{at}"));
+    tokio::fs::create_dir_all(&dir).await?;
+
+    let main = dir.join("main.db");
+    sqlx::query("VACUUM INTO ?").bind(path_str(&main)).execute(&ctx.db).await?;
+
+    let storage = dir.join("storage.db");
+    let uri = format!("sqlite://{}", conf.bot_storage);
+    let mut conn =
+        SqliteConnection::connect_with(&SqliteConnectOptions::from_str(&uri)?)
+            .await?;
+    sqlx::query("VACUUM INTO ?")
+        .bind(path_str(&storage))
+        .execute(&mut conn)
+        .await?;
+    conn.close().await?;
+
+    rotate(&conf.backup_dir, conf.backup_keep).await?;
+    Ok(Backup { at, main, storage })
+}
+
+/// keep the newest `keep` backups in `dir`
+async fn rotate(dir: &Path, keep: usize) -> Result<(), AppErr> {
+    let mut all = Vec::new();
+    let mut rd = tokio::fs::read_dir(dir).await?;
+    while let Some(entry) = rd.next_entry().await? {
+        let name = entry.file_name().to_string_lossy().into_owned();
+        let at =
+            name.strip_prefix("tonel-").and_then(|v| v.parse::<i64>().ok());
+        if let Some(at) = at {
+            all.push((at, entry.path()));
+        }
+    }
+
+    all.sort_unstable_by_key(|(at, _)| std::cmp::Reverse(*at));
+    for (_, path) in all.into_iter().skip(keep) {
+        tokio::fs::remove_dir_all(path).await?;
+    }
+    Ok(())
+}
+
+pub async fn schedule(bot: TB, ctx: Ctx) {
+    let conf = Config::get();
+    loop {
+        tokio::time::sleep(conf.backup_every).await;
+        if let Err(e) = create(&ctx).await {
+            log::error!("backup failed: {e:?}");
+            let m = format!("backup failed ❌\n\n{e:#?}");
+            let _ = bot.send_message(conf.dev, m).await;
+        }
+    }
+}
+
+/// check that `file` is a healthy database with the same schema version
+/// as the main one. the error is a message for the owner
+pub async fn check(ctx: &Ctx, file: &Path) -> Result<i64, String> {
+    let uri = format!("sqlite://{}", path_str(file));
+    let opts = SqliteConnectOptions::from_str(&uri)
+        .map_err(|e| format!("مسیر نامعتبر: {e}"))?
+        .read_only(true);
+    let db = SqlitePool::connect_with(opts)
+        .await
+        .map_err(|e| format!("فایل sqlite نیست: {e}"))?;
+
+    let problems = integrity_check(&db).await;
+    let version = schema_version(&db).await;
+    db.close().await;
+
+    match problems {
+        Ok(v) if v.is_empty() => {}
+        Ok(v) => return Err(format!("فایل خراب است: {}", v.join("\n"))),
+        Err(e) => return Err(format!("فایل sqlite نیست: {}", e.debug)),
+    }
+
+    let Some(version) = version else {
+        return Err(String::from("این فایل دیتابیس تونل نیست"));
+    };
+    let current = schema_version(&ctx.db).await.unwrap_or_default();
+    if version != current {
+        return Err(format!(
+            "نسخه دیتابیس {version} با نسخه ربات {current} یکی نیست"
+        ));
+    }
+
+    Ok(version)
+}
+
+/// swap in a staged restore before the database is opened. the current
+/// database is moved into the backup dir
+pub fn apply_staged() -> std::io::Result<bool> {
+    let conf = Config::get();
+    let staged = staged();
+    if !staged.exists() {
+        return Ok(false);
+    }
+
+    std::fs::create_dir_all(&conf.backup_dir)?;
+    let old = conf.backup_dir.join(format!("pre-restore-{}", utils::now()));
+    std::fs::create_dir_all(&old)?;
+    for suffix in ["", "-wal", "-shm", "-journal"] {
+        let path = PathBuf::from(format!("{}{suffix}", conf.db_path));
+        if path.exists() {
+            std::fs::rename(&path, old.join(format!("main.db{suffix}")))?;
+        }
+    }
+    std::fs::rename(&staged, &conf.db_path)?;
+
+    Ok(true)
+}
@@ -2,6 +2,7 @@ use std::fmt::Display;
 
 use teloxide::types::{InlineKeyboardButton, InlineKeyboardMarkup};
 
+use crate::i18n::Lang;
 use crate::state::KeyData;
 
 pub trait BookItem: Display {
@@ -70,7 +71,7 @@ impl<T: BookItem> Book<T> {
         }
 
         layout.push(vec![
-            KeyData::main_menu_btn(),
+            KeyData::main_menu_btn(Lang::Fa),
             InlineKeyboardButton::callback(
                 "📛 حذف همه 📛",
                 KeyData::BookDeleteAll,
@@ -0,0 +1,756 @@
+//! bulk import and export of proxies and v2rays. imports take plain
+//! lines, csv, json, base64 subscriptions or clash yaml and are checked
+//! in a dry run before anything is added
+
+use crate::Ctx;
+use crate::db::{Proxy, V2ray};
+use crate::utils::{b64_decode, b64_encode};
+use std::collections::{HashMap, HashSet};
+
+#[derive(
+    Debug,
+    serde::Serialize,
+    serde::Deserialize,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq
+)]
+#[serde(rename_all = "snake_case")]
+pub enum Pool {
+    Proxy,
+    V2ray,
+}
+
+#[derive(
+    Debug,
+    serde::Serialize,
+    serde::Deserialize,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq
+)]
+#[serde(rename_all = "snake_case")]
+pub enum Format {
+    Text,
+    Csv,
+    Json,
+    Base64,
+    /// import only
+    Clash,
+}
+
+impl Format {
+    pub const EXPORT: [Self; 4] =
+        [Self::Text, Self::Csv, Self::Json, Self::Base64];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Text => "txt",
+            Self::Csv => "csv",
+            Self::Json => "json",
+            Self::Base64 => "base64",
+            Self::Clash => "clash",
+        }
+    }
+}
+
+#[derive(
+    Debug,
+    serde::Serialize,
+    serde::Deserialize,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq
+)]
+#[serde(rename_all = "snake_case")]
+/// which items of the pool to export
+pub enum Filter {
+    All,
+    Active,
+    /// active with a score of at least [`crate::vote::neutral`]
+    Good,
+}
+
+impl Filter {
+    pub const ALL: [Self; 3] = [Self::All, Self::Active, Self::Good];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::All => "همه",
+            Self::Active => "فعال",
+            Self::Good => "خوب",
+        }
+    }
+}
+
+/// result of parsing an import
+pub struct Report {
+    pub format: Format,
+    /// normalized links without repeats, in input order
+    pub links: Vec<String>,
+    /// links repeated in the input
+    pub repeated: usize,
+    /// entry number and reason
+    pub invalid: Vec<(usize, String)>,
+}
+
+type Entries = Vec<(usize, Result<String, String>)>;
+
+/// detect the format of `data` and parse it into links of `pool`
+pub fn parse(pool: Pool, data: &str) -> Report {
+    let data = data.trim_start_matches('\u{feff}').trim();
+    let (format, entries) = if data.starts_with('[') || data.starts_with('{') {
+        (Format::Json, json_entries(pool, data))
+    } else if data.lines().any(|l| l.trim_end() == "proxies:") {
+        (Format::Clash, clash_entries(pool, data))
+    } else if let Some(text) = b64_text(data) {
+        (Format::Base64, line_entries(&text))
+    } else if is_csv(data) {
+        (Format::Csv, csv_entries(pool, data))
+    } else {
+        (Format::Text, line_entries(data))
+    };
+
+    let mut report =
+        Report { format, links: Vec::new(), repeated: 0, invalid: Vec::new() };
+    let mut seen = HashSet::new();
+    for (n, entry) in entries {
+        let link = entry.and_then(|link| normalize(pool, &link));
+        match link {
+            Ok(link) if seen.insert(link.clone()) => report.links.push(link),
+            Ok(_) => report.repeated += 1,
+            Err(reason) => report.invalid.push((n, reason)),
+        }
+    }
+
+    report
+}
+
+/// add the checked links of an import, returns how many were added
+pub async fn commit(ctx: &Ctx, pool: Pool, links: &[String]) -> usize {
+    let mut added = 0;
+    for link in links {
+        let ok = match pool {
+            Pool::Proxy => match Proxy::from_link(link) {
+                Some(mut px) => px.add(ctx).await.is_ok(),
+                None => false,
+            },
+            Pool::V2ray => match V2ray::from_link(link) {
+                Some(mut v2) => v2.add(ctx).await.is_ok(),
+                None => false,
+            },
+        };
+        if ok {
+            added += 1;
+        }
+    }
+    added
+}
+
+fn normalize(pool: Pool, link: &str) -> Result<String, String> {
+    match pool {
+        Pool::Proxy => Proxy::from_link(link)
+            .map(|px| px.url())
+            .ok_or(String::from("لینک پروکسی نامعتبر")),
+        Pool::V2ray => {
+            if !link.contains("://") {
+                return Err(String::from("لینک v2ray نامعتبر"));
+            }
+            V2ray::from_link(link)
+                .map(|v2| v2.link)
+                .ok_or(String::from("لینک v2ray نامعتبر"))
+        }
+    }
+}
+
+fn line_entries(data: &str) -> Entries {
+    data.lines()
+        .enumerate()
+        .map(|(i, l)| (i + 1, l.trim()))
+        .filter(|(_, l)| !l.is_empty() && !l.starts_with('#'))
+        .map(|(n, l)| (n, Ok(l.to_string())))
+        .collect()
+}
+
+/// the decoded text of a base64 subscription
+fn b64_text(data: &str) -> Option<String> {
+    if data.contains("://") {
+        return None;
+    }
+    let text = String::from_utf8(b64_decode(data)?).ok()?;
+    text.contains("://").then_some(text)
+}
+
+fn is_csv(data: &str) -> bool {
+    let first = data.lines().next().unwrap_or_default();
+    first.contains(',') && !first.contains("://")
+}
+
+/// split a csv line, double quotes may wrap a field
+fn csv_split(line: &str) -> Vec<String> {
+    let mut out = vec![String::new()];
+    let mut quoted = false;
+    let mut it = line.chars().peekable();
+    while let Some(c) = it.next() {
+        match c {
+            '"' if quoted && it.peek() == Some(&'"') => {
+                it.next();
+                out.last_mut().unwrap().push('"');
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => out.push(String::new()),
+            c => out.last_mut().unwrap().push(c),
+        }
+    }
+    out.into_iter().map(|f| f.trim().to_string()).collect()
+}
+
+fn csv_escape(field: &str) -> String {
+    if field.contains([',', '"', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// columns by header name, or `server,port,secret` and a link column
+/// without a header
+fn csv_entries(pool: Pool, data: &str) -> Entries {
+    let mut lines = data.lines().enumerate();
+    let Some((_, first)) = lines.next() else { return Vec::new() };
+    let head = csv_split(&first.to_lowercase());
+    let col = |name: &str| head.iter().position(|h| h == name);
+    let link = col("link").or(col("url"));
+    let spp = (col("server"), col("port"), col("secret"));
+    let header = link.is_some() || spp.0.is_some();
+
+    let rows = std::iter::once((0, first))
+        .filter(|_| !header)
+        .chain(lines)
+        .filter(|(_, l)| !l.trim().is_empty());
+
+    let mut out = Vec::new();
+    for (i, line) in rows {
+        let f = csv_split(line);
+        let get = |i: Option<usize>| i.and_then(|i| f.get(i)).cloned();
+        let entry = match (pool, header) {
+            (_, true) if link.is_some() => get(link),
+            (Pool::Proxy, true) => match spp {
+                (Some(s), Some(p), Some(k)) => {
+                    proxy_link(get(Some(s)), get(Some(p)), get(Some(k)))
+                }
+                _ => None,
+            },
+            (Pool::Proxy, false) if f.len() >= 3 => proxy_link(
+                f.first().cloned(),
+                f.get(1).cloned(),
+                f.get(2).cloned(),
+            ),
+            _ => f.iter().find(|v| v.contains("://")).cloned(),
+        };
+        out.push((i + 1, entry.ok_or(String::from("ستون لینک پیدا نشد"))));
+    }
+    out
+}
+
+fn proxy_link(
+    server: Option<String>, port: Option<String>, secret: Option<String>,
+) -> Option<String> {
+    let (server, port, secret) = (server?, port?, secret?);
+    Some(format!(
+        "https://t.me/proxy?server={server}&port={port}&secret={secret}"
+    ))
+}
+
+/// an array of links or objects, or an object holding such an array
+fn json_entries(pool: Pool, data: &str) -> Entries {
+    let value = match serde_json::from_str::<serde_json::Value>(data) {
+        Ok(v) => v,
+        Err(e) => return vec![(e.line(), Err(format!("json نامعتبر: {e}")))],
+    };
+    let items = match value {
+        serde_json::Value::Array(items) => items,
+        serde_json::Value::Object(mut obj) => {
+            let key = ["proxies", "v2rays", "items", "links"]
+                .into_iter()
+                .find(|k| obj.get(*k).is_some_and(|v| v.is_array()));
+            match key.and_then(|k| obj.remove(k)) {
+                Some(serde_json::Value::Array(items)) => items,
+                _ => vec![serde_json::Value::Object(obj)],
+            }
+        }
+        _ => return vec![(1, Err(String::from("json باید آرایه باشد")))],
+    };
+
+    let text = |v: Option<&serde_json::Value>| match v? {
+        serde_json::Value::String(s) => Some(s.clone()),
+        serde_json::Value::Number(n) => Some(n.to_string()),
+        _ => None,
+    };
+    items
+        .iter()
+        .enumerate()
+        .map(|(i, item)| {
+            let link = match item {
+                serde_json::Value::String(s) => Some(s.clone()),
+                serde_json::Value::Object(o) => {
+                    let link = text(o.get("link")).or(text(o.get("url")));
+                    match pool {
+                        Pool::V2ray => link,
+                        Pool::Proxy => link.or(proxy_link(
+                            text(o.get("server")),
+                            text(o.get("port")),
+                            text(o.get("secret")),
+                        )),
+                    }
+                }
+                _ => None,
+            };
+            (i + 1, link.ok_or(String::from("فیلد link پیدا نشد")))
+        })
+        .collect()
+}
+
+/// the `proxies` list of a clash config, converted to share links
+fn clash_entries(pool: Pool, data: &str) -> Entries {
+    if pool == Pool::Proxy {
+        return vec![(1, Err(String::from("clash پروکسی MTProto ندارد")))];
+    }
+
+    match clash_proxies(data) {
+        Ok(items) => items
+            .iter()
+            .enumerate()
+            .map(|(i, item)| (i + 1, clash_link(item)))
+            .collect(),
+        Err(e) => vec![(1, Err(e))],
+    }
+}
+
+/// flatten a yaml map into `key` and `parent.key` strings.
+/// lists inside an item like alpn are not needed
+fn yaml_flat(
+    value: &serde_yaml::Value, prefix: &str, out: &mut HashMap<String, String>,
+) {
+    let serde_yaml::Value::Mapping(map) = value else { return };
+    for (k, v) in map {
+        let Some(k) = yaml_text(k) else { continue };
+        let key = format!("{prefix}{k}");
+        match v {
+            serde_yaml::Value::Mapping(_) => {
+                yaml_flat(v, &format!("{key}."), out)
+            }
+            _ => {
+                if let Some(v) = yaml_text(v) {
+                    out.insert(key, v);
+                }
+            }
+        }
+    }
+}
+
+fn yaml_text(value: &serde_yaml::Value) -> Option<String> {
+    match value {
+        serde_yaml::Value::String(s) => Some(s.clone()),
+        serde_yaml::Value::Number(n) => Some(n.to_string()),
+        serde_yaml::Value::Bool(b) => Some(b.to_string()),
+        _ => None,
+    }
+}
+
+/// items of the top level `proxies:` list, nested keys joined with `.`
+fn clash_proxies(data: &str) -> Result<Vec<HashMap<String, String>>, String> {
+    let value = serde_yaml::from_str::<serde_yaml::Value>(data)
+        .map_err(|e| format!("yaml نامعتبر: {e}"))?;
+    let Some(serde_yaml::Value::Sequence(items)) = value.get("proxies") else {
+        return Err(String::from("لیست proxies پیدا نشد"));
+    };
+    Ok(items
+        .iter()
+        .map(|item| {
+            let mut out = HashMap::new();
+            yaml_flat(item, "", &mut out);
+            out
+        })
+        .collect())
+}
+
+/// share link of a clash proxy
+fn clash_link(m: &HashMap<String, String>) -> Result<String, String> {
+    let g = |k: &str| m.get(k).map(String::as_str).unwrap_or_default();
+    let (name, server, port) = (g("name"), g("server"), g("port"));
+    if server.is_empty() || port.is_empty() {
+        return Err(String::from("server یا port ندارد"));
+    }
+    let host = if server.contains(':') {
+        format!("[{server}]:{port}")
+    } else {
+        format!("{server}:{port}")
+    };
+    let net = match g("network") {
+        "" => "tcp",
+        n => n,
+    };
+    let tls = g("tls") == "true";
+    let sni = match g("servername") {
+        "" => g("sni"),
+        v => v,
+    };
+    let path = match g("ws-opts.path") {
+        "" => g("grpc-opts.grpc-service-name"),
+        v => v,
+    };
+    let ws_host = g("ws-opts.headers.Host");
+
+    let url = |scheme: &str,
+               user: &str,
+               params: &[(&str, &str)]|
+     -> Result<String, String> {
+        let base = format!("{scheme}://{user}@{host}");
+        let params = params.iter().filter(|(_, v)| !v.is_empty());
+        let mut url = reqwest::Url::parse_with_params(&base, params)
+            .map_err(|e| format!("لینک نامعتبر: {e}"))?;
+        if url.query() == Some("") {
+            url.set_query(None);
+        }
+        if !name.is_empty() {
+            url.set_fragment(Some(name));
+        }
+        Ok(url.to_string())
+    };
+
+    match g("type") {
+        "vmess" => {
+            let conf = serde_json::json!({
+                "v": "2",
+                "ps": name,
+                "add": server,
+                "port": port,
+                "id": g("uuid"),
+                "aid": match g("alterId") { "" => "0", v => v },
+                "scy": match g("cipher") { "" => "auto", v => v },
+                "net": net,
+                "type": "none",
+                "host": ws_host,
+                "path": path,
+                "tls": if tls { "tls" } else { "" },
+                "sni": sni,
+            });
+            Ok(format!("vmess://{}", b64_encode(conf.to_string().as_bytes())))
+        }
+        "vless" => {
+            let security = match (g("reality-opts.public-key"), tls) {
+                ("", true) => "tls",
+                ("", false) => "none",
+                _ => "reality",
+            };
+            url(
+                "vless",
+                g("uuid"),
+                &[
+                    ("encryption", "none"),
+                    ("security", security),
+                    ("type", net),
+                    ("flow", g("flow")),
+                    ("sni", sni),
+                    ("fp", g("client-fingerprint")),
+                    ("pbk", g("reality-opts.public-key")),
+                    ("sid", g("reality-opts.short-id")),
+                    ("path", path),
+                    ("host", ws_host),
+                ],
+            )
+        }
+        "trojan" => url(
+            "trojan",
+            g("password"),
+            &[
+                ("security", "tls"),
+                ("type", net),
+                ("sni", sni),
+                ("path", path),
+                ("host", ws_host),
+            ],
+        ),
+        "ss" => {
+            let user = format!("{}:{}", g("cipher"), g("password"));
+            let user = b64_encode(user.as_bytes());
+            url("ss", user.trim_end_matches('='), &[])
+        }
+        t => Err(format!("نوع {t} پشتیبانی نمی شود")),
+    }
+}
+
+pub fn export_proxies(items: &[Proxy], format: Format) -> String {
+    match format {
+        Format::Csv => {
+            let mut out = String::from(
+                "id,server,port,secret,up_votes,dn_votes,score,disabled\n",
+            );
+            for px in items {
+                out += &format!(
+                    "{},{},{},{},{},{},{:.3},{}\n",
+                    px.id,
+                    csv_escape(&px.server),
+                    csv_escape(&px.port),
+                    csv_escape(&px.secret),
+                    px.up_votes,
+                    px.dn_votes,
+                    px.score,
+                    px.disabled
+                );
+            }
+            out
+        }
+        Format::Json => {
+            let items = items
+                .iter()
+                .map(|px| {
+                    serde_json::json!({
+                        "id": px.id,
+                        "server": px.server,
+                        "port": px.port,
+                        "secret": px.secret,
+                        "link": px.url(),
+                        "up_votes": px.up_votes,
+                        "dn_votes": px.dn_votes,
+                        "score": px.score,
+                        "disabled": px.disabled,
+                    })
+                })
+                .collect::<Vec<_>>();
+            serde_json::to_string_pretty(&items).unwrap_or_default()
+        }
+        Format::Text | Format::Base64 | Format::Clash => {
+            let links = items.iter().map(|px| px.url()).collect::<Vec<_>>();
+            lines(links, format)
+        }
+    }
+}
+
+pub fn export_v2rays(items: &[V2ray], format: Format) -> String {
+    match format {
+        Format::Csv => {
+            let mut out = String::from(
+                "id,label,link,up_votes,dn_votes,score,disabled\n",
+            );
+            for v2 in items {
+                out += &format!(
+                    "{},{},{},{},{},{:.3},{}\n",
+                    v2.id,
+                    csv_escape(&v2.label),
+                    csv_escape(&v2.link),
+                    v2.up_votes,
+                    v2.dn_votes,
+                    v2.score,
+                    v2.disabled
+                );
+            }
+            out
+        }
+        Format::Json => {
+            let items = items
+                .iter()
+                .map(|v2| {
+                    serde_json::json!({
+                        "id": v2.id,
+                        "label": v2.label,
+                        "link": v2.link,
+                        "up_votes": v2.up_votes,
+                        "dn_votes": v2.dn_votes,
+                        "score": v2.score,
+                        "disabled": v2.disabled,
+                    })
+                })
+                .collect::<Vec<_>>();
+            serde_json::to_string_pretty(&items).unwrap_or_default()
+        }
+        Format::Text | Format::Base64 | Format::Clash => {
+            let links = items.iter().map(|v2| v2.link.clone()).collect();
+            lines(links, format)
+        }
+    }
+}
+
+fn lines(links: Vec<String>, format: Format) -> String {
+    let text = links.join("\n");
+    match format {
+        Format::Base64 => b64_encode(text.as_bytes()),
+        _ => text,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PX: &str = "https://t.me/proxy?server=1.2.3.4&port=443&secret=ee11";
+    const V2: &str = "vless://id@example.com:443?security=tls#a";
+
+    fn proxy(id: i64, server: &str) -> Proxy {
+        let mut px = Proxy::from_link(PX).unwrap();
+        px.id = id;
+        px.server = server.to_string();
+        px
+    }
+
+    #[test]
+    fn detect() {
+        let b64 = b64_encode(format!("{V2}\n").as_bytes());
+        let csv = "server,port,secret\n1.2.3.4,443,ee11";
+        let json = format!("[\"{PX}\"]");
+        let clash = "proxies:\n  - {name: a, type: ss, server: h, port: 1}";
+        assert_eq!(parse(Pool::Proxy, PX).format, Format::Text);
+        assert_eq!(parse(Pool::Proxy, csv).format, Format::Csv);
+        assert_eq!(parse(Pool::Proxy, &json).format, Format::Json);
+        assert_eq!(parse(Pool::V2ray, &b64).format, Format::Base64);
+        assert_eq!(parse(Pool::V2ray, clash).format, Format::Clash);
+        assert_eq!(parse(Pool::V2ray, &b64).links, [V2]);
+        assert_eq!(parse(Pool::Proxy, csv).links, [PX]);
+        assert_eq!(parse(Pool::Proxy, &json).links, [PX]);
+    }
+
+    #[test]
+    fn normalize_links() {
+        let data = indoc::indoc! {"
+            # comment
+            tg://t.me/proxy?secret=ee11&port=443&server=1.2.3.4
+            https://t.me/proxy?server=1.2.3.4&port=443&secret=ee11
+
+            https://t.me/proxy?server=5.6.7.8&port=80&secret=ee22&x=1
+        "};
+        let report = parse(Pool::Proxy, data);
+        assert_eq!(
+            report.links,
+            [PX, "https://t.me/proxy?server=5.6.7.8&port=80&secret=ee22"]
+        );
+        assert_eq!(report.repeated, 1);
+        assert!(report.invalid.is_empty());
+
+        let report = parse(Pool::V2ray, &format!("  {V2}  \n{V2}"));
+        assert_eq!(report.links, [V2]);
+        assert_eq!(report.repeated, 1);
+    }
+
+    #[test]
+    fn invalid_reasons() {
+        let reason = |pool, data: &str| parse(pool, data).invalid;
+        let data = format!("{PX}\nhttps://t.me/proxy?server=1.2.3.4");
+        assert_eq!(
+            reason(Pool::Proxy, &data),
+            [(2, String::from("لینک پروکسی نامعتبر"))]
+        );
+        assert_eq!(
+            reason(Pool::V2ray, "not a link"),
+            [(1, String::from("لینک v2ray نامعتبر"))]
+        );
+        assert_eq!(
+            reason(Pool::Proxy, "[1, {\"a\": 2}]"),
+            [
+                (1, String::from("فیلد link پیدا نشد")),
+                (2, String::from("فیلد link پیدا نشد"))
+            ]
+        );
+        assert_eq!(
+            reason(Pool::Proxy, "server,port\n1.2.3.4,443"),
+            [(2, String::from("ستون لینک پیدا نشد"))]
+        );
+        assert!(reason(Pool::Proxy, "[1,")[0].1.starts_with("json نامعتبر"));
+
+        let clash = indoc::indoc! {"
+            proxies:
+              - {name: a, type: hysteria2, server: h, port: 1}
+              - {name: b, type: vmess, server: h}
+        "};
+        assert_eq!(
+            reason(Pool::V2ray, clash),
+            [
+                (1, String::from("نوع hysteria2 پشتیبانی نمی شود")),
+                (2, String::from("server یا port ندارد"))
+            ]
+        );
+        assert_eq!(
+            reason(Pool::Proxy, clash),
+            [(1, String::from("clash پروکسی MTProto ندارد"))]
+        );
+        assert_eq!(
+            reason(Pool::V2ray, "proxies:\n  a: [")[0].1.split(':').next(),
+            Some("yaml نامعتبر")
+        );
+    }
+
+    #[test]
+    fn clash() {
+        let data = indoc::indoc! {"
+            port: 7890
+            proxies:
+              - name: tr
+                type: trojan
+                server: example.com
+                port: 443
+                password: pass
+                sni: example.org
+                alpn:
+                  - h2
+              - name: \"ws vless\"
+                type: vless
+                server: 1.2.3.4
+                port: 8443
+                uuid: id
+                tls: true
+                network: ws
+                ws-opts:
+                  path: /ws
+                  headers:
+                    Host: cdn.example.com
+              - {name: ss, type: ss, server: h, port: 1, cipher: aes-128-gcm, password: p}
+            proxy-groups:
+              - name: auto
+        "};
+        let report = parse(Pool::V2ray, data);
+        assert!(report.invalid.is_empty());
+        assert_eq!(
+            report.links,
+            [
+                "trojan://pass@example.com:443?security=tls&type=tcp\
+                &sni=example.org#tr",
+                "vless://id@1.2.3.4:8443?encryption=none&security=tls\
+                &type=ws&path=%2Fws&host=cdn.example.com#ws%20vless",
+                "ss://YWVzLTEyOC1nY206cA@h:1#ss",
+            ]
+        );
+
+        let data = "proxies:\n  - {name: v, type: vmess, server: h, port: 1}";
+        let link = parse(Pool::V2ray, data).links.remove(0);
+        let conf = b64_decode(link.strip_prefix("vmess://").unwrap()).unwrap();
+        let conf = serde_json::from_slice::<serde_json::Value>(&conf).unwrap();
+        assert_eq!(conf["add"], "h");
+        assert_eq!(conf["port"], "1");
+        assert_eq!(conf["net"], "tcp");
+    }
+
+    #[test]
+    fn export_round_trip() {
+        let pxs = [proxy(1, "1.2.3.4"), proxy(2, "a,\"b\"")];
+        let links = pxs.iter().map(|px| px.url()).collect::<Vec<_>>();
+        for format in Format::EXPORT {
+            let data = export_proxies(&pxs, format);
+            let report = parse(Pool::Proxy, &data);
+            assert_eq!(report.format, format);
+            assert_eq!(report.links, links, "{}", format.name());
+        }
+
+        let v2s = ["vless://id@h:1?a=b#x,y", "trojan://p@h:2"]
+            .map(|l| V2ray::from_link(l).unwrap());
+        let links = v2s.iter().map(|v2| v2.link.clone()).collect::<Vec<_>>();
+        for format in Format::EXPORT {
+            let data = export_v2rays(&v2s, format);
+            let report = parse(Pool::V2ray, &data);
+            assert_eq!(report.format, format);
+            assert_eq!(report.links, links, "{}", format.name());
+        }
+    }
+}
@@ -0,0 +1,60 @@
+use crate::config::Config;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use teloxide::types::{ChatId, ChatMemberKind, Restricted, UserId};
+
+/// still in the chat, kicked and banned members are not
+pub fn is_member(kind: &ChatMemberKind) -> bool {
+    match kind {
+        ChatMemberKind::Owner(_)
+        | ChatMemberKind::Administrator(_)
+        | ChatMemberKind::Member(_) => true,
+        ChatMemberKind::Restricted(Restricted { is_member, .. }) => *is_member,
+        ChatMemberKind::Left | ChatMemberKind::Banned(_) => false,
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Entry {
+    joined: bool,
+    at: i64,
+}
+
+type Entries = HashMap<(ChatId, UserId), Entry>;
+
+#[derive(Debug, Clone, Default)]
+/// force join membership cache, keyed by (channel, user)
+pub struct MemberCache {
+    inner: Arc<Mutex<Entries>>,
+}
+
+impl MemberCache {
+    /// cached membership if it is not older than [`Config::FORCE_JOIN_TTL`]
+    pub fn get(&self, cid: ChatId, uid: UserId, now: i64) -> Option<bool> {
+        let map = self.inner.lock().ok()?;
+        let e = map.get(&(cid, uid))?;
+        if e.at + Config::FORCE_JOIN_TTL < now {
+            return None;
+        }
+        Some(e.joined)
+    }
+
+    /// only force join channels are cached
+    pub fn set(&self, cid: ChatId, uid: UserId, joined: bool, now: i64) {
+        if !Config::get().force_join.iter().any(|(c, _, _)| *c == cid) {
+            return;
+        }
+        let Ok(mut map) = self.inner.lock() else { return };
+        if map.len() >= Config::FORCE_JOIN_CACHE_MAX {
+            map.retain(|_, e| e.at + Config::FORCE_JOIN_TTL >= now);
+        }
+        if map.len() >= Config::FORCE_JOIN_CACHE_MAX {
+            // everything is still fresh, drop the oldest tenth
+            let mut ats = map.values().map(|e| e.at).collect::<Vec<_>>();
+            let n = ats.len() / 10;
+            let cut = *ats.select_nth_unstable(n).1;
+            map.retain(|_, e| e.at > cut);
+        }
+        map.insert((cid, uid), Entry { joined, at: now });
+    }
+}
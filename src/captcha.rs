@@ -0,0 +1,100 @@
+use crate::i18n::{Lang, t};
+use rand::Rng;
+use rand::seq::SliceRandom;
+
+const DIGITS: [&str; 10] =
+    ["0️⃣", "1️⃣", "2️⃣", "3️⃣", "4️⃣", "5️⃣", "6️⃣", "7️⃣", "8️⃣", "9️⃣"];
+const EMOJIS: [&str; 6] = ["🍅", "🍌", "🍏", "🍓", "🍇", "🥕"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// what to continue with after the captcha is solved
+pub enum Purpose {
+    Start,
+    FreePoint,
+}
+
+impl Purpose {
+    pub const fn code(self) -> &'static str {
+        match self {
+            Self::Start => "start",
+            Self::FreePoint => "free_point",
+        }
+    }
+
+    pub fn from_code(code: &str) -> Option<Self> {
+        match code {
+            "start" => Some(Self::Start),
+            "free_point" => Some(Self::FreePoint),
+            _ => None,
+        }
+    }
+}
+
+/// options of a challenge, a random guess is right 1 in 9 times and
+/// passes the default 3 `captcha_tries` about 30% of the time
+pub const OPTIONS: usize = 9;
+
+/// a question with [`OPTIONS`] options, `options[answer]` is the right one
+pub struct Challenge {
+    pub question: String,
+    pub options: Vec<String>,
+    pub answer: u8,
+}
+
+/// an arithmetic or an emoji counting challenge
+pub fn challenge(lang: Lang) -> Challenge {
+    let mut rng = rand::rng();
+    if rng.random_bool(0.5) {
+        arithmetic(lang, &mut rng)
+    } else {
+        count(lang, &mut rng)
+    }
+}
+
+fn keycap(n: i64) -> String {
+    n.to_string()
+        .chars()
+        .filter_map(|c| c.to_digit(10))
+        .map(|d| DIGITS[d as usize])
+        .collect()
+}
+
+fn arithmetic(lang: Lang, rng: &mut impl Rng) -> Challenge {
+    let a = rng.random_range(1..10);
+    let b = rng.random_range(1..10);
+    let (op, value) =
+        if rng.random_bool(0.5) { ("➕", a + b) } else { ("✖️", a * b) };
+    let q = t!(lang, "captcha_math", a = keycap(a), op = op, b = keycap(b));
+    options(q, value, rng)
+}
+
+fn count(lang: Lang, rng: &mut impl Rng) -> Challenge {
+    let mut pool = EMOJIS;
+    pool.shuffle(rng);
+    let target = pool[0];
+    let n = rng.random_range(2..=6);
+    let mut items = vec![target; n];
+    for _ in 0..rng.random_range(4..=8) {
+        items.push(pool[rng.random_range(1..pool.len())]);
+    }
+    items.shuffle(rng);
+    let q = t!(lang, "captcha_count", emoji = target, items = items.concat());
+    options(q, n as i64, rng)
+}
+
+fn options(question: String, value: i64, rng: &mut impl Rng) -> Challenge {
+    let mut opts = vec![value];
+    while opts.len() < OPTIONS {
+        let v = value + rng.random_range(-8..=8);
+        if v >= 0 && !opts.contains(&v) {
+            opts.push(v);
+        }
+    }
+    opts.shuffle(rng);
+    let answer = opts.iter().position(|v| *v == value).unwrap_or_default();
+    Challenge {
+        question,
+        options: opts.iter().map(|v| v.to_string()).collect(),
+        answer: answer as u8,
+    }
+}
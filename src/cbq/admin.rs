@@ -5,17 +5,25 @@ use super::*;
 impl super::Cbq {
     pub async fn handle_admin(&mut self, ag: Ag) -> Result<bool, AppErr> {
         match ag {
-            Ag::ForceJoinList => {
-                let mut kyb = Vec::with_capacity(self.s.conf.force_join.len());
-                for (_, title, url) in self.s.conf.force_join.iter() {
-                    kyb.push([InlineKeyboardButton::url(title, url.clone())]);
-                }
-
-                self.s
-                    .bot
-                    .send_message(self.s.cid, "admin force join list")
-                    .reply_markup(InlineKeyboardMarkup::new(kyb))
-                    .await?;
+            Ag::ForceJoinList => self.admin_force_join_list().await?,
+            Ag::ForceJoinFailToggle => {
+                self.s.settings.fj_fail_closed =
+                    !self.s.settings.fj_fail_closed;
+                self.s.settings.set(&self.s.ctx.db).await?;
+                self.admin_force_join_list().await?;
+            }
+            Ag::VoteClusters => self.s.admin_vote_clusters().await?,
+            Ag::Export(pool, format, filter) => {
+                self.s.admin_export(pool, format, filter).await?
+            }
+            Ag::ImportCommit => {
+                let State::AdminImportConfirm { pool, links } =
+                    self.s.store.get_or_default().await?
+                else {
+                    self.s.notify("پیش نویسی پیدا نشد 🤡").await?;
+                    return Ok(true);
+                };
+                self.s.admin_import_commit(pool, &links).await?;
             }
             Ag::KarbarFind => {
                 let m = concat!(
@@ -103,7 +111,7 @@ impl super::Cbq {
                 let kyb3 = [
                     sbtn!(SetDonateMsg, "پیام حمایت مالی"),
                     sbtn!(SetVipMaxViews, "بازدید VIP: {}", vip_max_views),
-                    KeyData::main_menu_btn(),
+                    KeyData::main_menu_btn(Lang::Fa),
                 ];
                 let kyb4 = [
                     sbtn!(
@@ -116,9 +124,64 @@ impl super::Cbq {
                         "پاداش برای یک استار {}",
                         star_point_price
                     ),
-                    KeyData::main_menu_btn(),
+                    KeyData::main_menu_btn(Lang::Fa),
+                ];
+                let kyb5 = [
+                    sbtn!(
+                        SetAdStarPrice,
+                        "قیمت 1000 بازدید تبلیغ {} ⭐",
+                        ad_star_price
+                    ),
+                    sbtn!(Surge, "افزایش قیمت 📈"),
+                    KeyData::main_menu_btn(Lang::Fa),
                 ];
-                let kb = InlineKeyboardMarkup::new([kyb1, kyb2, kyb3, kyb4]);
+                let kyb6 = [
+                    sbtn!(SetStreakBonus, "پاداش پیاپی 🔥"),
+                    sbtn!(SetStreakWindow, "مهلت پیاپی: {}", streak_window),
+                    sbtn!(Captcha, "کپچا 🤖"),
+                ];
+                let kb = InlineKeyboardMarkup::new([kyb1, kyb2, kyb3, kyb4])
+                    .append_row(kyb5)
+                    .append_row(kyb6)
+                    .append_row([
+                        sbtn!(SetBundleSize, "بسته پروکسی: {}", bundle_size),
+                        sbtn!(
+                            SetBundleDiscount,
+                            "تخفیف بسته: {}%",
+                            bundle_discount
+                        ),
+                        KeyData::main_menu_btn(Lang::Fa),
+                    ])
+                    .append_row([
+                        sbtn!(
+                            SetVoteHalfLife,
+                            "نیمه عمر رای: {}",
+                            vote_half_life
+                        ),
+                        sbtn!(
+                            SetVoteMinWeight,
+                            "حداقل وزن رای: {}",
+                            vote_min_weight
+                        ),
+                        sbtn!(
+                            SetVoteDisablePct,
+                            "غیرفعال با {}% منفی",
+                            vote_disable_pct
+                        ),
+                    ])
+                    .append_row([
+                        sbtn!(SetVoteWindow, "مهلت رای: {}", vote_window),
+                        sbtn!(
+                            SetSubmitReward,
+                            "پاداش ارسال پروکسی: {}",
+                            submit_reward
+                        ),
+                        sbtn!(
+                            SetSubmitVoteReward,
+                            "پاداش رای خوب: {}",
+                            submit_vote_reward
+                        ),
+                    ]);
 
                 let m = indoc::formatdoc!(
                     "تنظیمات ⚙️
@@ -153,7 +216,7 @@ impl super::Cbq {
                         self.s
                             .bot
                             .send_message(self.s.cid, m)
-                            .reply_markup(KeyData::main_menu())
+                            .reply_markup(KeyData::main_menu(Lang::Fa))
                             .await?
                     }
                     Err(e) => {
@@ -163,7 +226,7 @@ impl super::Cbq {
                         self.s
                             .bot
                             .send_message(self.s.cid, m)
-                            .reply_markup(KeyData::main_menu())
+                            .reply_markup(KeyData::main_menu(Lang::Fa))
                             .await?
                     }
                 };
@@ -202,6 +265,176 @@ impl super::Cbq {
                 self.s.notify(msg).await?;
                 self.s.store.update(State::AdminFlyerSetLabel(id)).await?;
             }
+            Ag::FlyerSetSchedule(_page, id) => {
+                let msg = concat!(
+                    "تاریخ شروع و پایان تبلیغ را ارسال کنید 📅\n\n",
+                    "مثال: 2025-01-20 2025-02-01\n",
+                    "برای بدون محدودیت از - استفاده کنید. مثال: - 2025-02-01"
+                );
+                self.s.notify(msg).await?;
+                let st = State::AdminFlyerSetSchedule(id);
+                self.s.store.update(st).await?;
+            }
+            Ag::FlyerSetDailyMax(_page, id) => {
+                let msg = concat!(
+                    "حداکثر بازدید روزانه را به صورت عدد ارسال کنید\n",
+                    "عداد زیر -1 به معنی بی نهایت تفسیر می شوند"
+                );
+                self.s.notify(msg).await?;
+                let st = State::AdminFlyerSetDailyMax(id);
+                self.s.store.update(st).await?;
+            }
+            Ag::FlyerSetUserCap(_page, id) => {
+                let msg = concat!(
+                    "حداکثر بازدید روزانه هر کاربر را به صورت عدد ارسال کنید\n",
+                    "عداد زیر -1 به معنی بی نهایت تفسیر می شوند"
+                );
+                self.s.notify(msg).await?;
+                self.s.store.update(State::AdminFlyerSetUserCap(id)).await?;
+            }
+            Ag::FlyerSetWeight(_page, id) => {
+                let msg = concat!(
+                    "وزن تبلیغ را به صورت عدد ارسال کنید ⚖\n",
+                    "تبلیغ با وزن 3 سه برابر تبلیغ با وزن 1 نمایش داده می شود"
+                );
+                self.s.notify(msg).await?;
+                self.s.store.update(State::AdminFlyerSetWeight(id)).await?;
+            }
+            Ag::FlyerSetLang(_page, id) => {
+                let msg = concat!(
+                    "کد زبان کاربران هدف را ارسال کنید 🌐\n",
+                    "مثال: fa یا en\n",
+                    "برای نمایش به همه زبان ها - ارسال کنید"
+                );
+                self.s.notify(msg).await?;
+                self.s.store.update(State::AdminFlyerSetLang(id)).await?;
+            }
+            Ag::FlyerAudience(page, id) => {
+                let mut flyer = Flyer::get(&self.s.ctx, id).await?;
+                flyer.audience = match flyer.audience {
+                    Flyer::AUDIENCE_ALL => Flyer::AUDIENCE_NEW,
+                    Flyer::AUDIENCE_NEW => Flyer::AUDIENCE_OLD,
+                    _ => Flyer::AUDIENCE_ALL,
+                };
+                flyer.set(&self.s.ctx).await?;
+                self.s.notify(flyer.audience_str()).await?;
+                self.admin_flyer_list(page).await?;
+            }
+            Ag::FlyerReport(page, id) => {
+                let flyer = Flyer::get(&self.s.ctx, id).await?;
+                let m = self.s.flyer_report(&flyer).await?;
+                let kb = InlineKeyboardMarkup::new([[
+                    InlineKeyboardButton::callback(
+                        "بازگشت ⬅️",
+                        KeyData::BookPagination(page),
+                    ),
+                    KeyData::main_menu_btn(Lang::Fa),
+                ]]);
+                self.s.bot.send_message(self.s.cid, m).reply_markup(kb).await?;
+            }
+            Ag::Surge => self.s.admin_surge().await?,
+            Ag::SurgeSet(product) => {
+                let m = format!(
+                    concat!(
+                        "منحنی افزایش قیمت {} را ارسال کنید 📈\n\n",
+                        "درصد اضافه برای هر پله، جدا شده با ویرگول:\n",
+                        "<code>{}</code>"
+                    ),
+                    product.name(),
+                    Settings::SURGE
+                );
+                self.s
+                    .bot
+                    .send_message(self.s.cid, m)
+                    .parse_mode(ParseMode::Html)
+                    .reply_markup(KeyData::main_menu(Lang::Fa))
+                    .await?;
+                self.s.store.update(State::AdminSurgeSet(product)).await?;
+            }
+            Ag::SetSurgeDecay => {
+                let m = "زمان کاهش یک پله را به ثانیه ارسال کنید ⏳";
+                self.s.notify(m).await?;
+                self.s.store.update(State::AdminSetSurgeDecay).await?;
+            }
+            Ag::TemplateList => self.admin_template_list().await?,
+            Ag::Template(idx, lang) => self.admin_template(idx, lang).await?,
+            Ag::TemplateEdit(idx, lang) => {
+                let m = concat!(
+                    "متن جدید قالب را ارسال کنید ✍️\n\n",
+                    "از تگ های HTML تلگرام استفاده کنید. متغیر ها:\n",
+                    "{points} {cost} {link} {username}"
+                );
+                self.s.notify(m).await?;
+                let st = State::AdminTemplateEdit(idx, lang);
+                self.s.store.update(st).await?;
+            }
+            Ag::TemplateReset(idx, lang) => {
+                if let Some(name) = Template::name(idx) {
+                    Template::del(&self.s.ctx, name, lang).await?;
+                }
+                self.s.notify("قالب به متن پیشفرض برگشت ♻️").await?;
+                self.admin_template(idx, lang).await?;
+            }
+            Ag::TemplateSave => {
+                let State::AdminTemplateConfirm { idx, lang, body } =
+                    self.s.store.get_or_default().await?
+                else {
+                    self.s.notify("پیش نویسی پیدا نشد 🤡").await?;
+                    return Ok(true);
+                };
+                let Some(name) = Template::name(idx) else {
+                    self.s.notify("قالبی پیدا نشد 🤡").await?;
+                    return Ok(true);
+                };
+
+                let ctx = &self.s.ctx;
+                Template::set(ctx, name, lang, &body, self.s.now).await?;
+                self.s.store.update(State::Menu).await?;
+                self.s.notify("قالب ذخیره شد ✅").await?;
+            }
+            Ag::FlyerQueue => {
+                let flyers = Flyer::queue(&self.s.ctx).await?;
+                if flyers.is_empty() {
+                    self.s.notify("صف بررسی تبلیغات خالی است 🍃").await?;
+                    return Ok(true);
+                }
+                for flyer in flyers.iter() {
+                    self.s.ad_review(self.s.karbar.uid(), flyer).await?;
+                }
+            }
+            Ag::FlyerApprove(id) => {
+                let mut flyer = Flyer::get(&self.s.ctx, id).await?;
+                if flyer.status != Flyer::STATUS_PENDING {
+                    let m =
+                        format!("وضعیت تبلیغ: {}", flyer.status_str(Lang::Fa));
+                    self.s.notify(&m).await?;
+                    return Ok(true);
+                }
+                flyer.status = Flyer::STATUS_ACTIVE;
+                flyer.set(&self.s.ctx).await?;
+
+                if let Some(owner) = flyer.owner {
+                    let lang = Karbar::find_with_tid(&self.s.ctx, owner)
+                        .await
+                        .map(|k| k.lang())
+                        .unwrap_or_default();
+                    let m = t!(lang, "ad_approved", label = flyer.label);
+                    let _ = self.s.bot.send_message(ChatId(owner), m).await;
+                }
+                self.s.notify("تبلیغ تایید شد ✅").await?;
+            }
+            Ag::FlyerReject(id) => {
+                let flyer = Flyer::get(&self.s.ctx, id).await?;
+                if flyer.status != Flyer::STATUS_PENDING {
+                    let m =
+                        format!("وضعیت تبلیغ: {}", flyer.status_str(Lang::Fa));
+                    self.s.notify(&m).await?;
+                    return Ok(true);
+                }
+                let m = "دلیل رد تبلیغ را ارسال کنید ✍️";
+                self.s.notify(m).await?;
+                self.s.store.update(State::AdminFlyerReject(id)).await?;
+            }
             Ag::FlyerDelLink(page, id) => {
                 let mut flyer = Flyer::get(&self.s.ctx, id).await?;
                 flyer.link = None;
@@ -231,6 +464,45 @@ impl super::Cbq {
                 Flyer::del_all(&self.s.ctx).await?;
                 self.s.send_menu().await?;
             }
+            Ag::ChannelList => self.admin_channel_list(0).await?,
+            Ag::ChannelDel(page, id) => {
+                Channel::del(&self.s.ctx, id).await?;
+                self.admin_channel_list(page).await?;
+            }
+            Ag::ChannelToggle(page, id) => {
+                let mut ch = Channel::get(&self.s.ctx, id).await?;
+                ch.enabled = !ch.enabled;
+                ch.set(&self.s.ctx).await?;
+                self.admin_channel_list(page).await?;
+            }
+            Ag::ChannelSetAmount(_page, id) => {
+                let msg = "پاداش عضویت را به صورت عدد ارسال کنید 🍅";
+                self.s.notify(msg).await?;
+                self.s.store.update(State::AdminChannelSetAmount(id)).await?;
+            }
+            Ag::ChannelSetMaxSub(_page, id) => {
+                let msg = concat!(
+                    "حداکثر تعداد عضو با پاداش را به صورت عدد ارسال کنید\n",
+                    "عداد زیر -1 به معنی بی نهایت تفسیر می شوند"
+                );
+                self.s.notify(msg).await?;
+                self.s.store.update(State::AdminChannelSetMaxSub(id)).await?;
+            }
+            Ag::ChannelSetClawDays(_page, id) => {
+                let msg = concat!(
+                    "تعداد روزی که کاربر باید در کانال بماند را ارسال کنید\n",
+                    "اگر زودتر کانال را ترک کند، پاداش از او کسر می شود\n\n",
+                    "⚠ عضویت هر ۶ ساعت بررسی می شود و ربات باید در کانال ",
+                    "ادمین باشد"
+                );
+                self.s.notify(msg).await?;
+                let st = State::AdminChannelSetClawDays(id);
+                self.s.store.update(st).await?;
+            }
+            Ag::ChannelDeleteAllConfirm => {
+                Channel::del_all(&self.s.ctx).await?;
+                self.s.send_menu().await?;
+            }
             Ag::SetVipMaxViews => {
                 let msg = indoc::formatdoc!(
                     "حداکثر بازدید پیام VIP: {}
@@ -242,6 +514,15 @@ impl super::Cbq {
                 self.set_settings(msg, State::AdminSetVipMaxViews).await?;
             }
 
+            Ag::SetAdStarPrice => {
+                let msg = indoc::formatdoc!(
+                    "قیمت فعلی هر 1000 بازدید تبلیغ: {} استار ⭐
+
+                    قیمت جدید را به صورت عدد ارسال کنید:",
+                    self.s.settings.ad_star_price
+                );
+                self.set_settings(msg, State::AdminSetAdStarPrice).await?;
+            }
             Ag::SetVipCost => {
                 let msg = indoc::formatdoc!(
                     "هزینه فعلی پیام VIP: {}
@@ -299,6 +580,160 @@ impl super::Cbq {
                 );
                 self.set_settings(msg, State::AdminSetFreePtDelay).await?;
             }
+            Ag::SetStreakBonus => {
+                let msg = indoc::formatdoc!(
+                    "پاداش پیاپی فعلی: {}
+
+                    درصد اضافه پاداش رایگان برای هر روز پیاپی را
+                    جدا شده با ویرگول ارسال کنید:
+
+                    مثال: {}",
+                    self.s.settings.streak_bonus,
+                    Settings::STREAK
+                );
+                self.set_settings(msg, State::AdminSetStreakBonus).await?;
+            }
+            Ag::SetStreakWindow => {
+                let msg = indoc::formatdoc!(
+                    "مهلت پیاپی فعلی: {}
+
+                    اگر کاربر تا این مدت بعد از آماده شدن پاداش رایگان
+                    آن را دریافت نکند، روز های پیاپی از نو شروع می شود.
+
+                    مهلت جدید را به صورت ثانیه ارسال کنید:",
+                    self.s.settings.streak_window
+                );
+                self.set_settings(msg, State::AdminSetStreakWindow).await?;
+            }
+            Ag::Captcha => self.s.admin_captcha().await?,
+            Ag::CaptchaToggle => {
+                self.s.settings.captcha_enabled =
+                    !self.s.settings.captcha_enabled;
+                self.s.settings.set(&self.s.ctx.db).await?;
+                self.s.admin_captcha().await?;
+            }
+            Ag::SetCaptchaEvery => {
+                let msg = indoc::formatdoc!(
+                    "کپچا هر {} پاداش رایگان
+
+                    تعداد پاداش رایگان بین دو کپچا را ارسال کنید:
+                    (0 یعنی فقط کاربران جدید)",
+                    self.s.settings.captcha_every
+                );
+                self.set_settings(msg, State::AdminSetCaptchaEvery).await?;
+            }
+            Ag::SetCaptchaTries => {
+                let msg = indoc::formatdoc!(
+                    "تعداد خطای مجاز فعلی: {}
+
+                    تعداد جدید را به صورت عدد ارسال کنید:",
+                    self.s.settings.captcha_tries
+                );
+                self.set_settings(msg, State::AdminSetCaptchaTries).await?;
+            }
+            Ag::SetCaptchaLockout => {
+                let msg = indoc::formatdoc!(
+                    "زمان قفل فعلی: {}
+
+                    زمان قفل بعد از خطا های پیاپی را به صورت ثانیه ارسال کنید:",
+                    self.s.settings.captcha_lockout
+                );
+                self.set_settings(msg, State::AdminSetCaptchaLockout).await?;
+            }
+            Ag::SetBundleSize => {
+                let msg = indoc::formatdoc!(
+                    "تعداد پروکسی در هر بسته: {}
+
+                    تعداد جدید را به صورت عدد ارسال کنید (3 تا 5):",
+                    self.s.settings.bundle_size
+                );
+                self.set_settings(msg, State::AdminSetBundleSize).await?;
+            }
+            Ag::SetBundleDiscount => {
+                let msg = indoc::formatdoc!(
+                    "تخفیف بسته پروکسی: {}%
+
+                    درصد تخفیف جدید را به صورت عدد ارسال کنید:",
+                    self.s.settings.bundle_discount
+                );
+                self.set_settings(msg, State::AdminSetBundleDiscount).await?;
+            }
+            Ag::SetVoteHalfLife => {
+                let msg = indoc::formatdoc!(
+                    "نیمه عمر رای فعلی: {}
+
+                    بعد از این مدت وزن هر رای نصف می شود.
+                    زمان جدید را به صورت ثانیه ارسال کنید:",
+                    self.s.settings.vote_half_life
+                );
+                self.set_settings(msg, State::AdminSetVoteHalfLife).await?;
+            }
+            Ag::SetVoteMinWeight => {
+                let msg = indoc::formatdoc!(
+                    "حداقل وزن رای فعلی: {}
+
+                    پروکسی و v2ray تا این وزن رای غیرفعال نمی شوند.
+                    وزن جدید را به صورت عدد ارسال کنید:",
+                    self.s.settings.vote_min_weight
+                );
+                self.set_settings(msg, State::AdminSetVoteMinWeight).await?;
+            }
+            Ag::SetVoteDisablePct => {
+                let msg = indoc::formatdoc!(
+                    "آستانه غیرفعال سازی فعلی: {}%
+
+                    وقتی با اطمینان 95% بیش از این درصد رای ها منفی باشد
+                    پروکسی یا v2ray غیرفعال و به شما اطلاع داده می شود.
+                    درصد جدید را به صورت عدد ارسال کنید:",
+                    self.s.settings.vote_disable_pct
+                );
+                self.set_settings(msg, State::AdminSetVoteDisablePct).await?;
+            }
+            Ag::SetVoteWindow => {
+                let msg = indoc::formatdoc!(
+                    "مهلت رای فعلی: {}
+
+                    کاربران تا این مدت بعد از دریافت پروکسی یا v2ray
+                    می توانند رای بدهند یا رای خود را تغییر دهند.
+                    زمان جدید را به صورت ثانیه ارسال کنید:",
+                    self.s.settings.vote_window
+                );
+                self.set_settings(msg, State::AdminSetVoteWindow).await?;
+            }
+            Ag::SetSubmitReward => {
+                let msg = indoc::formatdoc!(
+                    "پاداش ارسال پروکسی فعلی: {}
+
+                    این امتیاز بعد از تایید پروکسی ارسالی به کاربر داده می شود.
+                    پاداش جدید را به صورت عدد ارسال کنید:",
+                    self.s.settings.submit_reward
+                );
+                self.set_settings(msg, State::AdminSetSubmitReward).await?;
+            }
+            Ag::SetSubmitVoteReward => {
+                let msg = indoc::formatdoc!(
+                    "پاداش رای خوب فعلی: {}
+
+                    وقتی پروکسی ارسالی از دیگران رای مثبت به وزن {} بگیرد و
+                    امتیاز خوبی داشته باشد این امتیاز به کاربر داده می شود.
+                    پاداش جدید را به صورت عدد ارسال کنید:",
+                    self.s.settings.submit_vote_reward,
+                    Config::SUBMIT_GOOD_VOTES
+                );
+                self.set_settings(msg, State::AdminSetSubmitVoteReward).await?;
+            }
+            Ag::SubmitQueue => {
+                let subs = Submission::queue(&self.s.ctx).await?;
+                if subs.is_empty() {
+                    self.s.notify("صف پروکسی های ارسالی خالی است 🍃").await?;
+                    return Ok(true);
+                }
+                for sub in subs.iter() {
+                    self.s.submit_review(self.s.karbar.uid(), sub).await?;
+                }
+            }
+            Ag::SubmitApprove(id) => self.s.submit_decide(id, true).await?,
+            Ag::SubmitReject(id) => self.s.submit_decide(id, false).await?,
             Ag::SetFreePt => {
                 let msg = indoc::formatdoc!(
                     "پاداش رایگان فعلی: {}
@@ -399,7 +834,7 @@ impl super::Cbq {
                             );
                             let _ = bot
                                 .send_message(bcid, m)
-                                .reply_markup(KeyData::main_menu())
+                                .reply_markup(KeyData::main_menu(Lang::Fa))
                                 .await;
                         }
 
@@ -412,7 +847,7 @@ impl super::Cbq {
                     let m = format!("پیام همگانی به {count} کاربر ارسال شد ✅");
                     let _ = bot
                         .send_message(bcid, m)
-                        .reply_markup(KeyData::main_menu())
+                        .reply_markup(KeyData::main_menu(Lang::Fa))
                         .await;
                 });
             }
@@ -420,4 +855,32 @@ impl super::Cbq {
 
         Ok(true)
     }
+
+    async fn admin_force_join_list(&self) -> HR {
+        let mut kyb = Vec::with_capacity(self.s.conf.force_join.len() + 1);
+        for (_, title, url) in self.s.conf.force_join.iter() {
+            kyb.push(vec![InlineKeyboardButton::url(title, url.clone())]);
+        }
+
+        let policy = if self.s.settings.fj_fail_closed {
+            "در صورت خطا: عضو نیست 🔒"
+        } else {
+            "در صورت خطا: عضو است 🔓"
+        };
+        kyb.push(vec![
+            InlineKeyboardButton::callback(
+                policy,
+                kd!(gg, ForceJoinFailToggle),
+            ),
+            KeyData::main_menu_btn(Lang::Fa),
+        ]);
+
+        self.s
+            .bot
+            .send_message(self.s.cid, "admin force join list")
+            .reply_markup(InlineKeyboardMarkup::new(kyb))
+            .await?;
+
+        Ok(())
+    }
 }
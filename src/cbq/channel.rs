@@ -0,0 +1,134 @@
+use teloxide::sugar::request::RequestLinkPreviewExt;
+use teloxide::utils::html::escape;
+
+use super::*;
+
+impl super::Cbq {
+    pub async fn admin_channel_list(&self, page: u32) -> HR {
+        let channels = Channel::list(&self.s.ctx, page).await?;
+        let count = Channel::count(&self.s.ctx).await?;
+        let bk = Book::new(channels, page, count / 32);
+        let msg = format!(
+            "کانال های اسپانسر\npage: {page} | total: {count}\n\n{}",
+            &bk.message()
+        );
+
+        self.s
+            .bot
+            .send_message(self.s.cid, msg)
+            .parse_mode(ParseMode::Html)
+            .disable_link_preview(true)
+            .reply_markup(bk.keyboard())
+            .await?;
+        self.s.store.update(State::AdminChannelList).await?;
+        self.del_msg().await?;
+
+        Ok(())
+    }
+
+    pub async fn handle_admin_channel(&self) -> Result<bool, AppErr> {
+        match self.key {
+            KeyData::BookAdd => {
+                let m = concat!(
+                    "ایدی عددی کانال و یا ایدی آن را با @ ارسال کنید\n\n",
+                    "⚠ ربات باید در کانال ادمین باشد تا عضویت را بررسی کند"
+                );
+                self.s.store.update(State::AdminChannelAdd).await?;
+                self.s.notify(m).await?;
+            }
+            KeyData::BookItem(page, id) => {
+                let ch = Channel::get(&self.s.ctx, id).await?;
+                let msg = indoc::formatdoc!(
+                    r#"<b>{}</b>
+
+                    id: <code>{}</code>
+                    link: {}
+                    پاداش: {} 🍅
+                    عضو با پاداش: {}
+                    حداکثر عضو: {}
+                    حداقل روز ماندن: {}
+                    فعال: {}"#,
+                    escape(&ch.name),
+                    ch.id,
+                    escape(&ch.url),
+                    ch.amount,
+                    ch.joins,
+                    ch.max_sub,
+                    ch.claw_days,
+                    if ch.enabled { "✅" } else { "❌" },
+                );
+
+                let kyb1 = vec![
+                    InlineKeyboardButton::callback(
+                        if ch.enabled {
+                            "غیرفعال کن"
+                        } else {
+                            "فعال کن"
+                        },
+                        kd!(ag, Ag::ChannelToggle(page, ch.id)),
+                    ),
+                    InlineKeyboardButton::callback(
+                        "حذف کن ⭕",
+                        kd!(ag, Ag::ChannelDel(page, ch.id)),
+                    ),
+                ];
+
+                let kyb2 = vec![
+                    InlineKeyboardButton::callback(
+                        "پاداش 🍅",
+                        kd!(ag, Ag::ChannelSetAmount(page, ch.id)),
+                    ),
+                    InlineKeyboardButton::callback(
+                        "حداکثر عضو 🐝",
+                        kd!(ag, Ag::ChannelSetMaxSub(page, ch.id)),
+                    ),
+                    InlineKeyboardButton::callback(
+                        "روز ماندن ⏳",
+                        kd!(ag, Ag::ChannelSetClawDays(page, ch.id)),
+                    ),
+                ];
+
+                let kyb3 = vec![
+                    InlineKeyboardButton::callback(
+                        "بازگشت ⬅️",
+                        KeyData::BookPagination(page),
+                    ),
+                    KeyData::main_menu_btn(Lang::Fa),
+                ];
+
+                let kb = InlineKeyboardMarkup::new([kyb1, kyb2, kyb3]);
+                self.s
+                    .bot
+                    .send_message(self.s.cid, msg)
+                    .parse_mode(ParseMode::Html)
+                    .disable_link_preview(true)
+                    .reply_markup(kb)
+                    .await?;
+            }
+            KeyData::BookPagination(page) => {
+                self.admin_channel_list(page).await?;
+            }
+            KeyData::BookDeleteAll => {
+                let m = concat!(
+                    "آیا از حذف تمامی کانال ها اتمینان کامل دارید ❓❓❓\n\n",
+                    "این عملیات غیر قابل بازگشت است ⚠⚠⚠"
+                );
+
+                let kyb = InlineKeyboardMarkup::new([[
+                    KeyData::main_menu_btn(Lang::Fa),
+                    InlineKeyboardButton::callback(
+                        "⭕ حذف همه ⭕",
+                        kd!(ag, Ag::ChannelDeleteAllConfirm),
+                    ),
+                    KeyData::main_menu_btn(Lang::Fa),
+                ]]);
+
+                let cid = self.s.cid;
+                self.s.bot.send_message(cid, m).reply_markup(kyb).await?;
+            }
+            _ => return Ok(false),
+        }
+
+        Ok(true)
+    }
+}
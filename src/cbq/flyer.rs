@@ -1,5 +1,7 @@
 use teloxide::sugar::request::RequestLinkPreviewExt;
 
+use crate::utils::fmt_date;
+
 use super::*;
 
 impl super::Cbq {
@@ -36,15 +38,40 @@ impl super::Cbq {
             }
             KeyData::BookItem(page, id) => {
                 let flyer = Flyer::get(&self.s.ctx, id).await?;
+                let date = |ts: i64| {
+                    if ts > 0 { fmt_date(ts) } else { String::from("---") }
+                };
                 let msg = indoc::formatdoc!(
                     r#"{} 👆👆👆
                     بازدید: {}
+                    کلیک: {} ({:.2}%)
                     حداکثر بازدید: {}
+                    بازدید امروز: {} / {}
+                    سقف روزانه هر کاربر: {}
+                    وزن: {}
+                    شروع: {}
+                    پایان: {}
+                    مخاطب: {}
+                    زبان: {}
                     فعال: {}
                     link: {}"#,
                     flyer.label,
                     flyer.views,
+                    flyer.clicks,
+                    flyer.ctr(),
                     flyer.max_views,
+                    if flyer.day == self.s.now / 86400 {
+                        flyer.day_views
+                    } else {
+                        0
+                    },
+                    flyer.daily_max_views,
+                    flyer.user_cap,
+                    flyer.weight,
+                    date(flyer.starts_at),
+                    date(flyer.ends_at),
+                    flyer.audience_str(),
+                    flyer.lang.as_deref().unwrap_or("---"),
                     if flyer.disabled { "❌" } else { "✅" },
                     flyer.link.as_deref().unwrap_or("---")
                 );
@@ -83,12 +110,46 @@ impl super::Cbq {
                     ),
                 ];
 
+                let kyb4 = vec![
+                    InlineKeyboardButton::callback(
+                        "زمان بندی 📅",
+                        kd!(ag, Ag::FlyerSetSchedule(page, flyer.id)),
+                    ),
+                    InlineKeyboardButton::callback(
+                        "سقف روزانه 📊",
+                        kd!(ag, Ag::FlyerSetDailyMax(page, flyer.id)),
+                    ),
+                    InlineKeyboardButton::callback(
+                        "سقف هر کاربر 👤",
+                        kd!(ag, Ag::FlyerSetUserCap(page, flyer.id)),
+                    ),
+                ];
+
+                let kyb5 = vec![
+                    InlineKeyboardButton::callback(
+                        "وزن ⚖",
+                        kd!(ag, Ag::FlyerSetWeight(page, flyer.id)),
+                    ),
+                    InlineKeyboardButton::callback(
+                        "مخاطب 🎯",
+                        kd!(ag, Ag::FlyerAudience(page, flyer.id)),
+                    ),
+                    InlineKeyboardButton::callback(
+                        "زبان 🌐",
+                        kd!(ag, Ag::FlyerSetLang(page, flyer.id)),
+                    ),
+                ];
+
                 let mut kyb3 = vec![
                     InlineKeyboardButton::callback(
                         "بازگشت ⬅️",
                         KeyData::BookPagination(page),
                     ),
-                    KeyData::main_menu_btn(),
+                    KeyData::main_menu_btn(Lang::Fa),
+                    InlineKeyboardButton::callback(
+                        "گزارش 📈",
+                        kd!(ag, Ag::FlyerReport(page, flyer.id)),
+                    ),
                 ];
 
                 if flyer.link.is_some() {
@@ -98,7 +159,8 @@ impl super::Cbq {
                     ));
                 }
 
-                let kb = InlineKeyboardMarkup::new([kyb1, kyb2, kyb3]);
+                let kb =
+                    InlineKeyboardMarkup::new([kyb1, kyb2, kyb4, kyb5, kyb3]);
 
                 let (cid, dev) = (self.s.cid, self.s.conf.dev);
                 let mid = MessageId(flyer.mid as i32);
@@ -120,12 +182,12 @@ impl super::Cbq {
                 );
 
                 let kyb = InlineKeyboardMarkup::new([[
-                    KeyData::main_menu_btn(),
+                    KeyData::main_menu_btn(Lang::Fa),
                     InlineKeyboardButton::callback(
                         "⭕ حذف همه ⭕",
                         kd!(ag, Ag::FlyerDeleteAllConfirm),
                     ),
-                    KeyData::main_menu_btn(),
+                    KeyData::main_menu_btn(Lang::Fa),
                 ]]);
 
                 let cid = self.s.cid;
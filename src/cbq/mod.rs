@@ -1,24 +1,32 @@
 use crate::{
     Ctx, HR, TB,
     book::Book,
+    bulk::Pool,
     config::Config,
-    db::{Flyer, Karbar, KarbarStats, Proxy, Settings, V2ray},
+    db::{
+        Channel, Flyer, Karbar, KarbarStats, Proxy, Settings, Submission,
+        Template, V2ray,
+    },
     error::AppErr,
+    i18n::{Lang, t, tr},
     session::Session,
     state::{AdminGlobal as Ag, KeyData, State, Store, kd},
+    vote,
 };
 use teloxide::{
     payloads::{SendInvoiceSetters, SendMessageSetters},
     prelude::Requester,
     types::{
-        CallbackQuery, InlineKeyboardButton, InlineKeyboardMarkup,
+        CallbackQuery, ChatId, InlineKeyboardButton, InlineKeyboardMarkup,
         LabeledPrice, MessageId, ParseMode,
     },
 };
 
 mod admin;
+mod channel;
 mod flyer;
 mod proxy;
+mod template;
 mod v2ray;
 
 pub struct Cbq {
@@ -36,21 +44,44 @@ impl Cbq {
     pub async fn handle_global(&mut self) -> Result<bool, AppErr> {
         match self.key {
             KeyData::Menu => self.s.send_menu().await?,
+            KeyData::RestoreConfirm => self.s.owner_restore_confirm().await?,
             KeyData::Donate => self.s.donate().await?,
-            KeyData::GetProxy => self.s.get_proxy().await?,
-            KeyData::GetVip => self.s.get_vip().await?,
-            KeyData::GetV2ray => self.s.get_v2ray().await?,
+            KeyData::GetProxy => self.s.get_proxy(None).await?,
+            KeyData::GetVip => self.s.get_vip(None).await?,
+            KeyData::GetV2ray => self.s.get_v2ray(None).await?,
+            KeyData::GetProxyBundle => self.s.get_proxy_bundle(None).await?,
+            KeyData::Prefs => self.s.prefs().await?,
+            KeyData::MyConfigs => self.s.my_configs().await?,
+            KeyData::ResendProxy(id) => self.s.resend_proxy(id).await?,
+            KeyData::ResendV2ray(id) => self.s.resend_v2ray(id).await?,
+            KeyData::ResendVip => self.s.resend_vip().await?,
+            KeyData::Region => self.s.region().await?,
+            KeyData::SetRegion(region) => self.s.set_region(region).await?,
+            KeyData::Buy(product, cost) => {
+                self.s.buy(product, Some(cost)).await?
+            }
+            KeyData::BuyAlways(product, cost) => {
+                self.s.karbar.skip_confirm = true;
+                self.s.karbar.set(&self.s.ctx).await?;
+                self.s.buy(product, Some(cost)).await?
+            }
+            KeyData::Captcha(idx) => self.s.captcha_answer(idx).await?,
+            KeyData::SkipConfirmToggle => self.s.skip_confirm_toggle().await?,
             KeyData::MyInviteLinks => self.s.get_invite().await?,
             KeyData::GetFreePoints => self.s.get_free_point().await?,
             KeyData::BuyStarPoints(star) => {
                 let points = star * self.s.settings.star_point_price as u32;
+                let lang = self.s.lang();
                 self.s
                     .bot
                     .send_invoice(
                         self.s.cid,
-                        format!("{points} امتیاز 🍅"),
-                        format!(
-                            "خرید {points} امتیاز 🍅 با {star} استار ⭐ تلگرام "
+                        t!(lang, "star_invoice_title", points = points),
+                        t!(
+                            lang,
+                            "star_invoice_desc",
+                            points = points,
+                            stars = star
                         ),
                         points.to_string(),
                         "XTR",
@@ -61,36 +92,70 @@ impl Cbq {
             }
             KeyData::StarPrices => self.s.buy_star_point().await?,
             KeyData::GetRealFreePoints => self.s.get_real_free_point().await?,
+            KeyData::Tasks => self.s.tasks().await?,
+            KeyData::TaskCheck(id) => self.s.task_check(id).await?,
+            KeyData::FlyerClick(id) => self.s.flyer_click(id).await?,
+            KeyData::MyAds => self.s.my_ads().await?,
+            KeyData::MyAd(id) => self.s.my_ad(id).await?,
+            KeyData::AdNew => self.s.ad_new().await?,
+            KeyData::AdBuy(id, pkg) => self.s.ad_buy(id, pkg).await?,
+            KeyData::SubmitProxy => self.s.submit_new().await?,
+            KeyData::Language => self.s.language().await?,
+            KeyData::SetLang(lang) => self.s.set_lang(lang).await?,
             KeyData::ProxyVote(id, vote) => {
                 self.del_msg().await?;
-                let kid = self.s.karbar.tid;
-                let vr = Proxy::vote_add(&self.s.ctx, kid, id, vote).await;
-                let msg = if vr.is_ok() {
-                    "رای شما ثبت شد 🍌"
-                } else {
-                    "شما قبلا رای داده بودید 🍏"
+                let (kid, now) = (self.s.karbar.tid, self.s.now);
+                let lang = self.s.lang();
+                let rep = vote::reputation(&self.s.ctx, &self.s.karbar, now);
+                let Some(w) = rep.await? else {
+                    self.s.notify(t!(lang, "vote_too_new")).await?;
+                    return Ok(true);
                 };
+                let (ctx, st) = (&self.s.ctx, &self.s.settings);
+                let win = st.vote_window;
+                let cast =
+                    Proxy::vote_add(ctx, kid, id, vote, w, now, win).await?;
+                if cast.counted() {
+                    let r = Proxy::rescore(ctx, id, st, now).await?;
+                    if let Some(px) = r {
+                        vote::notify_disabled(&self.s.bot, px.to_string())
+                            .await;
+                    }
+                }
+                let msg = tr(lang, cast.key());
 
                 self.s
                     .bot
                     .send_message(self.s.cid, msg)
-                    .reply_markup(KeyData::main_menu())
+                    .reply_markup(KeyData::main_menu(lang))
                     .await?;
             }
             KeyData::V2rayVote(id, vote) => {
                 self.del_msg().await?;
-                let kid = self.s.karbar.tid;
-                let vr = V2ray::vote_add(&self.s.ctx, kid, id, vote).await;
-                let msg = if vr.is_ok() {
-                    "رای شما ثبت شد 🍌"
-                } else {
-                    "شما قبلا رای داده بودید 🍏"
+                let (kid, now) = (self.s.karbar.tid, self.s.now);
+                let lang = self.s.lang();
+                let rep = vote::reputation(&self.s.ctx, &self.s.karbar, now);
+                let Some(w) = rep.await? else {
+                    self.s.notify(t!(lang, "vote_too_new")).await?;
+                    return Ok(true);
                 };
+                let (ctx, st) = (&self.s.ctx, &self.s.settings);
+                let win = st.vote_window;
+                let cast =
+                    V2ray::vote_add(ctx, kid, id, vote, w, now, win).await?;
+                if cast.counted() {
+                    let r = V2ray::rescore(ctx, id, st, now).await?;
+                    if let Some(v2) = r {
+                        vote::notify_disabled(&self.s.bot, v2.to_string())
+                            .await;
+                    }
+                }
+                let msg = tr(lang, cast.key());
 
                 self.s
                     .bot
                     .send_message(self.s.cid, msg)
-                    .reply_markup(KeyData::main_menu())
+                    .reply_markup(KeyData::main_menu(lang))
                     .await?;
             }
             _ => return Ok(false),
@@ -103,7 +168,7 @@ impl Cbq {
         self.s
             .bot
             .send_message(self.s.cid, msg)
-            .reply_markup(KeyData::main_menu())
+            .reply_markup(KeyData::main_menu(Lang::Fa))
             .await?;
         self.s.store.update(state).await?;
         Ok(())
@@ -153,33 +218,26 @@ impl Cbq {
             return Ok(());
         }
 
-        if let KeyData::Ag(ag) = key {
-            if is_admin && cbq.handle_admin(ag).await? {
-                return Ok(());
-            }
+        if !is_admin {
+            return Ok(());
         }
 
-        if is_admin {
-            match state {
-                State::AdminProxyList => {
-                    if cbq.handle_admin_proxy().await? {
-                        return Ok(());
-                    }
-                }
-                State::AdminV2rayList => {
-                    if cbq.handle_admin_v2ray().await? {
-                        return Ok(());
-                    }
-                }
-                State::AdminFlyerList => {
-                    if cbq.handle_admin_flyer().await? {
-                        return Ok(());
-                    }
-                }
-                _ => {}
-            }
+        let done = match key {
+            KeyData::Ag(ag) => cbq.handle_admin(ag).await?,
+            _ => false,
+        };
+        if done {
+            return Ok(());
         }
 
+        match state {
+            State::AdminProxyList => cbq.handle_admin_proxy().await?,
+            State::AdminV2rayList => cbq.handle_admin_v2ray().await?,
+            State::AdminFlyerList => cbq.handle_admin_flyer().await?,
+            State::AdminChannelList => cbq.handle_admin_channel().await?,
+            _ => false,
+        };
+
         Ok(())
     }
 }
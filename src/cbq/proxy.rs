@@ -14,7 +14,9 @@ impl Cbq {
             .bot
             .send_message(self.s.cid, msg)
             .parse_mode(ParseMode::Html)
-            .reply_markup(bk.keyboard())
+            .reply_markup(bk.keyboard().append_row([
+                InlineKeyboardButton::callback("خروجی ⬇️", KeyData::BookExport),
+            ]))
             .await?;
         self.s.store.update(State::AdminProxyList).await?;
         self.del_msg().await?;
@@ -32,10 +34,11 @@ impl Cbq {
                         concat!(
                             "send a proxy links. each link must be on ",
                             "a different line. like:\n\nproxy 1\nproxy 2\n",
-                            "proxy 3.\n\n send in a message or a .txt file"
+                            "proxy 3.\n\n send in a message or a .txt file\n",
+                            "csv, json and base64 files are accepted too"
                         ),
                     )
-                    .reply_markup(KeyData::main_menu())
+                    .reply_markup(KeyData::main_menu(Lang::Fa))
                     .await?;
                 self.s.store.update(State::AdminProxyAdd).await?;
             }
@@ -54,6 +57,7 @@ impl Cbq {
 
                     up votes: {upp}% ({}) 👍
                     down votes: {dnp}% ({}) 👎
+                    score: {:.3}
                     فعال: {}
                 "#,
                     px.server,
@@ -62,6 +66,7 @@ impl Cbq {
                     px.url(),
                     px.up_votes,
                     px.dn_votes,
+                    px.score,
                     if px.disabled { "❌" } else { "✅" },
                 );
 
@@ -93,7 +98,7 @@ impl Cbq {
                                 "<- بازگشت",
                                 KeyData::BookPagination(page),
                             ),
-                            KeyData::main_menu_btn(),
+                            KeyData::main_menu_btn(Lang::Fa),
                         ],
                     ]))
                     .await?;
@@ -101,6 +106,9 @@ impl Cbq {
             KeyData::BookPagination(page) => {
                 self.admin_proxy_list(page).await?;
             }
+            KeyData::BookExport => {
+                self.s.admin_export_menu(Pool::Proxy).await?
+            }
             KeyData::BookDeleteAll => {
                 let m = concat!(
                     "آیا از حذف تمامی پروکسی ها اتمینان کامل دارید ❓❓❓\n\n",
@@ -108,12 +116,12 @@ impl Cbq {
                 );
 
                 let kyb = InlineKeyboardMarkup::new([[
-                    KeyData::main_menu_btn(),
+                    KeyData::main_menu_btn(Lang::Fa),
                     InlineKeyboardButton::callback(
                         "⭕ حذف همه ⭕",
                         kd!(ag, Ag::ProxyDeleteAllConfirm),
                     ),
-                    KeyData::main_menu_btn(),
+                    KeyData::main_menu_btn(Lang::Fa),
                 ]]);
 
                 let cid = self.s.cid;
@@ -0,0 +1,92 @@
+use crate::{i18n::tr, utils::fmt_date};
+use teloxide::utils::html::escape;
+
+use super::*;
+
+impl super::Cbq {
+    pub async fn admin_template_list(&self) -> HR {
+        let edited = Template::list(&self.s.ctx).await?;
+        let m = concat!(
+            "قالب پیام ها 📝\n\n",
+            "متغیر ها: {points} {cost} {link} {username}\n",
+            "✏️ یعنی قالب ویرایش شده است"
+        );
+
+        let mut kyb = Vec::with_capacity(Template::NAMES.len() + 1);
+        for (idx, name) in Template::NAMES.iter().enumerate() {
+            let row = Lang::ALL
+                .iter()
+                .map(|lang| {
+                    let e = edited
+                        .iter()
+                        .any(|t| t.name == *name && t.lang == lang.code());
+                    InlineKeyboardButton::callback(
+                        format!(
+                            "{name} {}{}",
+                            lang.code(),
+                            if e { " ✏️" } else { "" }
+                        ),
+                        kd!(ag, Ag::Template(idx as u8, *lang)),
+                    )
+                })
+                .collect::<Vec<_>>();
+            kyb.push(row);
+        }
+        kyb.push(vec![KeyData::main_menu_btn(Lang::Fa)]);
+
+        self.s
+            .bot
+            .send_message(self.s.cid, m)
+            .reply_markup(InlineKeyboardMarkup::new(kyb))
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn admin_template(&self, idx: u8, lang: Lang) -> HR {
+        let Some(name) = Template::name(idx) else {
+            self.s.notify("قالبی پیدا نشد 🤡").await?;
+            return Ok(());
+        };
+
+        let (body, edited) = match Template::get(&self.s.ctx, name, lang).await
+        {
+            Some(t) => (t.body, format!("ویرایش: {}", fmt_date(t.updated_at))),
+            None => (tr(lang, name).to_string(), String::from("متن پیشفرض")),
+        };
+        let m = format!(
+            "قالب <b>{name}</b> ({})\n{edited}\n\n<pre>{}</pre>\n\nپیش نمایش 👇",
+            lang.code(),
+            escape(&body)
+        );
+        let kb = InlineKeyboardMarkup::new([
+            vec![
+                InlineKeyboardButton::callback(
+                    "ویرایش ✏️",
+                    kd!(ag, Ag::TemplateEdit(idx, lang)),
+                ),
+                InlineKeyboardButton::callback(
+                    "متن پیشفرض ♻️",
+                    kd!(ag, Ag::TemplateReset(idx, lang)),
+                ),
+            ],
+            vec![
+                InlineKeyboardButton::callback(
+                    "بازگشت ⬅️",
+                    kd!(gg, TemplateList),
+                ),
+                KeyData::main_menu_btn(Lang::Fa),
+            ],
+        ]);
+
+        self.s
+            .bot
+            .send_message(self.s.cid, m)
+            .parse_mode(ParseMode::Html)
+            .reply_markup(kb)
+            .await?;
+        self.s.template_preview(&body).await?;
+
+        Ok(())
+    }
+}
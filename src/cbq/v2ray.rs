@@ -16,7 +16,9 @@ impl Cbq {
             .bot
             .send_message(self.s.cid, msg)
             .parse_mode(ParseMode::Html)
-            .reply_markup(bk.keyboard())
+            .reply_markup(bk.keyboard().append_row([
+                InlineKeyboardButton::callback("خروجی ⬇️", KeyData::BookExport),
+            ]))
             .await?;
         self.s.store.update(State::AdminV2rayList).await?;
         self.del_msg().await?;
@@ -34,10 +36,12 @@ impl Cbq {
                         concat!(
                             "send a v2ray links. each link must be on ",
                             "a different line. like:\n\nv2ray 1\nproxy 2\n",
-                            "v2ray 3.\n\n send in a message or a .txt file"
+                            "v2ray 3.\n\n send in a message or a .txt file\n",
+                            "csv, json, base64 subscription and clash yaml ",
+                            "files are accepted too"
                         ),
                     )
-                    .reply_markup(KeyData::main_menu())
+                    .reply_markup(KeyData::main_menu(Lang::Fa))
                     .await?;
                 self.s.store.update(State::AdminV2rayAdd).await?;
             }
@@ -53,12 +57,14 @@ impl Cbq {
                     
                     up votes: {upp}% ({}) 👍
                     down votes: {dnp}% ({}) 👎
+                    score: {:.3}
                     فعال: {}
                 "#,
                     v2.label,
                     v2.link,
                     v2.up_votes,
                     v2.dn_votes,
+                    v2.score,
                     if v2.disabled { "❌" } else { "✅" },
                 );
 
@@ -90,7 +96,7 @@ impl Cbq {
                                 "<- بازگشت",
                                 KeyData::BookPagination(page),
                             ),
-                            KeyData::main_menu_btn(),
+                            KeyData::main_menu_btn(Lang::Fa),
                         ],
                     ]))
                     .await?;
@@ -98,6 +104,9 @@ impl Cbq {
             KeyData::BookPagination(page) => {
                 self.admin_v2ray_list(page).await?;
             }
+            KeyData::BookExport => {
+                self.s.admin_export_menu(Pool::V2ray).await?
+            }
             KeyData::BookDeleteAll => {
                 let m = concat!(
                     "آیا از حذف تمامی v2ray ها اتمینان کامل دارید ❓❓❓\n\n",
@@ -105,12 +114,12 @@ impl Cbq {
                 );
 
                 let kyb = InlineKeyboardMarkup::new([[
-                    KeyData::main_menu_btn(),
+                    KeyData::main_menu_btn(Lang::Fa),
                     InlineKeyboardButton::callback(
                         "⭕ حذف همه ⭕",
                         kd!(ag, Ag::V2rayDeleteAllConfirm),
                     ),
-                    KeyData::main_menu_btn(),
+                    KeyData::main_menu_btn(Lang::Fa),
                 ]]);
 
                 let cid = self.s.cid;
@@ -0,0 +1,66 @@
+//! take task rewards back from karbars that left the channel early.
+//! the chat member updates only reach the bot where it is an admin, so the
+//! rewarded joins inside the claw window are also checked every few hours
+
+use crate::cache::is_member;
+use crate::config::Config;
+use crate::db::{Channel, Karbar};
+use crate::error::AppErr;
+use crate::i18n::t;
+use crate::{Ctx, TB, utils};
+use std::time::Duration;
+use teloxide::prelude::*;
+use teloxide::types::{ChatId, UserId};
+
+/// take the reward of the join back and tell the karbar
+pub async fn left(
+    bot: &TB, ctx: &Ctx, channel: i64, karbar: i64, now: i64,
+) -> Result<(), AppErr> {
+    let Some((name, points)) =
+        Channel::claw_back(ctx, channel, karbar, now).await?
+    else {
+        return Ok(());
+    };
+
+    let lang = Karbar::find_with_tid(ctx, karbar)
+        .await
+        .map(|k| k.lang())
+        .unwrap_or_default();
+    let m = t!(lang, "task_clawed", name = name, points = points);
+    bot.send_message(ChatId(karbar), m).await?;
+    Ok(())
+}
+
+/// ask telegram about every rewarded join that can still be clawed back
+pub async fn recheck(bot: TB, ctx: Ctx) {
+    loop {
+        tokio::time::sleep(Duration::from_secs(Config::CLAW_CHECK_EVERY)).await;
+
+        let now = utils::now();
+        let joins = match Channel::open_joins(&ctx, now).await {
+            Ok(v) => v,
+            Err(e) => {
+                log::error!("open joins failed: {e:?}");
+                continue;
+            }
+        };
+
+        for (channel, karbar) in joins {
+            let uid = UserId(karbar as u64);
+            let cm = match bot.get_chat_member(ChatId(channel), uid).await {
+                Ok(v) => v,
+                Err(e) => {
+                    log::warn!("claw check {channel} failed: {e:?}");
+                    continue;
+                }
+            };
+            if is_member(&cm.kind) {
+                continue;
+            }
+            ctx.fj.set(ChatId(channel), uid, false, now);
+            if let Err(e) = left(&bot, &ctx, channel, karbar, now).await {
+                log::error!("claw back failed: {e:?}");
+            }
+        }
+    }
+}
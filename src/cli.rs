@@ -0,0 +1,305 @@
+//! admin subcommands of the binary. they work on the database with the
+//! same `db` code as the bot and exit without starting it
+
+use crate::bulk::{self, Pool};
+use crate::cache::MemberCache;
+use crate::config::Config;
+use crate::db::{self, Karbar, KarbarStats, PgRepo, Proxy, Repo, V2ray};
+use crate::error::AppErr;
+use crate::utils::{self, fmt_date};
+use crate::{Ctx, surge, vote};
+use std::str::FromStr;
+
+const USAGE: &str = indoc::indoc! {"
+    usage: tonel [-c config.toml] [command]
+
+    without a command the bot starts
+
+    commands:
+        --migrate-only                  apply the migrations and exit
+        --copy-to-postgres              copy the sqlite data to db.postgres
+        user show <tid>
+        user ban <tid>
+        user unban <tid>
+        points add <tid> <points>       negative points take away
+        points set <tid> <points>
+        proxy import <file> [--dry-run]
+        v2ray import <file> [--dry-run]
+        stats
+        settings show
+        settings set <key> <value>
+
+    user ban, user unban, points set and settings work on db.backend
+"};
+
+/// arguments after the binary name without the config flag
+fn args() -> Vec<String> {
+    let mut out = Vec::new();
+    let mut it = std::env::args().skip(1);
+    while let Some(arg) = it.next() {
+        if arg == "-c" || arg == "--config" {
+            it.next();
+            continue;
+        }
+        out.push(arg);
+    }
+    out
+}
+
+fn fail(msg: &str) -> ! {
+    eprintln!("{msg}");
+    std::process::exit(2)
+}
+
+fn num<T: FromStr>(value: &str) -> T {
+    match value.parse() {
+        Ok(v) => v,
+        Err(_) => fail(&format!("invalid value: {value}")),
+    }
+}
+
+/// run the command of the arguments, false when there is none
+pub async fn run() -> Result<bool, AppErr> {
+    let args = args();
+    let args = args.iter().map(String::as_str).collect::<Vec<_>>();
+    if args.is_empty() {
+        return Ok(false);
+    }
+    if matches!(args[..], ["-h" | "--help" | "help"]) {
+        print!("{USAGE}");
+        return Ok(true);
+    }
+
+    let ctx = Ctx { db: Config::init_db().await, fj: MemberCache::default() };
+    match args[..] {
+        ["--migrate-only"] => log::info!("migrations are applied, exit"),
+        ["--copy-to-postgres"] => copy_to_postgres(&ctx).await?,
+        ["user", "show", tid] => user_show(&ctx, num(tid)).await?,
+        ["user", "ban", tid] => {
+            user_ban(&db::open(&ctx.db).await?, num(tid), true).await?
+        }
+        ["user", "unban", tid] => {
+            user_ban(&db::open(&ctx.db).await?, num(tid), false).await?
+        }
+        ["points", "add", tid, points] => {
+            let tid = karbar(&ctx, num(tid)).await.tid;
+            Karbar::add_points(&ctx, tid, num(points)).await?;
+            let k = karbar(&ctx, tid).await;
+            println!("{} points: {}", k.tid, k.points);
+        }
+        ["points", "set", tid, points] => {
+            let store = db::open(&ctx.db).await?;
+            let mut k = stored_karbar(&store, num(tid)).await?;
+            k.points = num(points);
+            store.karbar_put(&k).await?;
+            println!("{} points: {}", k.tid, k.points);
+        }
+        ["proxy", "import", file] => {
+            import(&ctx, Pool::Proxy, file, false).await?
+        }
+        ["proxy", "import", file, "--dry-run"] => {
+            import(&ctx, Pool::Proxy, file, true).await?
+        }
+        ["v2ray", "import", file] => {
+            import(&ctx, Pool::V2ray, file, false).await?
+        }
+        ["v2ray", "import", file, "--dry-run"] => {
+            import(&ctx, Pool::V2ray, file, true).await?
+        }
+        ["stats"] => stats(&ctx).await?,
+        ["settings", "show"] => {
+            println!("{:#?}", db::open(&ctx.db).await?.settings().await?)
+        }
+        ["settings", "set", key, value] => {
+            settings_set(&db::open(&ctx.db).await?, key, value).await?
+        }
+        _ => fail(USAGE),
+    }
+
+    ctx.db.close().await;
+    Ok(true)
+}
+
+async fn karbar(ctx: &Ctx, tid: i64) -> Karbar {
+    match Karbar::find_with_tid(ctx, tid).await {
+        Some(k) => k,
+        None => fail(&format!("karbar {tid} was not found")),
+    }
+}
+
+async fn user_show(ctx: &Ctx, tid: i64) -> Result<(), AppErr> {
+    let k = karbar(ctx, tid).await;
+    let rep = vote::reputation(ctx, &k, utils::now()).await?;
+    let rep = rep.map(|r| format!("{r:.2}")).unwrap_or(String::from("---"));
+    indoc::printdoc!(
+        "
+            id: {}
+            name: {}
+            username: {}
+            points: {}
+            banned: {}
+            blocked: {}
+            verified: {}
+            created: {}
+            last activity: {}
+            invite code: {}
+            invited by: {}
+            streak: {}
+            vote reputation: {}
+        ",
+        k.tid,
+        k.fullname,
+        k.username.as_deref().unwrap_or("---"),
+        k.points,
+        k.banned,
+        k.blocked,
+        k.verified,
+        fmt_date(k.created_at),
+        fmt_date(k.updated_at),
+        k.invite_code,
+        k.invited_by.map(|v| v.to_string()).unwrap_or(String::from("---")),
+        k.streak,
+        rep
+    );
+    Ok(())
+}
+
+async fn stored_karbar(store: &impl Repo, tid: i64) -> Result<Karbar, AppErr> {
+    match store.karbar(tid).await? {
+        Some(k) => Ok(k),
+        None => fail(&format!("karbar {tid} was not found")),
+    }
+}
+
+async fn user_ban(
+    store: &impl Repo, tid: i64, banned: bool,
+) -> Result<(), AppErr> {
+    let mut k = stored_karbar(store, tid).await?;
+    k.banned = banned;
+    store.karbar_put(&k).await?;
+    println!("{} banned: {}", k.tid, k.banned);
+    Ok(())
+}
+
+async fn import(
+    ctx: &Ctx, pool: Pool, file: &str, dry_run: bool,
+) -> Result<(), AppErr> {
+    let data = tokio::fs::read_to_string(file).await?;
+    let report = bulk::parse(pool, &data);
+    let existing = match pool {
+        Pool::Proxy => Proxy::links(ctx).await?,
+        Pool::V2ray => V2ray::links(ctx).await?,
+    };
+    let (links, old): (Vec<_>, Vec<_>) =
+        report.links.into_iter().partition(|l| !existing.contains(l));
+
+    indoc::printdoc!(
+        "
+            format: {}
+            valid: {}
+            new: {}
+            repeated in file: {}
+            already added: {}
+            invalid: {}
+        ",
+        report.format.name(),
+        links.len() + old.len(),
+        links.len(),
+        report.repeated,
+        old.len(),
+        report.invalid.len()
+    );
+    for (n, reason) in report.invalid {
+        println!("{n}: {reason}");
+    }
+
+    if dry_run || links.is_empty() {
+        return Ok(());
+    }
+    let added = bulk::commit(ctx, pool, &links).await;
+    println!("added {added} of {}", links.len());
+    Ok(())
+}
+
+async fn stats(ctx: &Ctx) -> Result<(), AppErr> {
+    let ks = KarbarStats::get(ctx).await?;
+    let (proxies, proxies_active) = Proxy::count(ctx).await?;
+    let (v2rays, v2rays_active) = V2ray::count(ctx).await?;
+    indoc::printdoc!(
+        "
+            karbars: {}
+            blocked the bot: {}
+            active in 5 hours: {}
+            active in 7 days: {}
+            total points: {}
+            proxies: {} ({} active)
+            v2rays: {} ({} active)
+        ",
+        ks.total,
+        ks.blocked,
+        ks.active_5h,
+        ks.active_7d,
+        ks.total_points,
+        proxies,
+        proxies_active,
+        v2rays,
+        v2rays_active
+    );
+    Ok(())
+}
+
+async fn settings_set(
+    store: &impl Repo, key: &str, value: &str,
+) -> Result<(), AppErr> {
+    fn curve(value: &str) -> String {
+        let Some(curve) = surge::parse_curve(value) else {
+            fail(&format!("invalid curve: {value}"))
+        };
+        curve.iter().map(|v| v.to_string()).collect::<Vec<_>>().join(",")
+    }
+
+    let mut st = store.settings().await?;
+    macro_rules! fields {
+        (num: $($n:ident),*; curve: $($c:ident),*) => {
+            match key {
+                $(stringify!($n) => st.$n = num(value),)*
+                $(stringify!($c) => st.$c = curve(value),)*
+                _ => fail(&format!("unknown setting: {key}")),
+            }
+        };
+    }
+    fields!(
+        num: invite_points, star_point_price, free_point_delay, free_points,
+        proxy_cost, v2ray_cost, vip_cost, vip_max_views, ad_star_price,
+        fj_fail_closed, surge_decay, streak_window, captcha_enabled,
+        captcha_every, captcha_tries, captcha_lockout, bundle_size,
+        bundle_discount, vote_half_life, vote_min_weight, vote_disable_pct,
+        vote_window, submit_reward, submit_vote_reward;
+        curve: proxy_surge, v2ray_surge, vip_surge, streak_bonus
+    );
+    if key == "bundle_size" && !(3..=5).contains(&st.bundle_size) {
+        fail("bundle_size must be between 3 and 5");
+    }
+    store.settings_put(&st).await?;
+    println!("{:#?}", st);
+    Ok(())
+}
+
+async fn copy_to_postgres(ctx: &Ctx) -> Result<(), AppErr> {
+    let to = PgRepo::connect(db::postgres_url()?).await?;
+    let n = db::copy(&db::SqliteRepo(ctx.db.clone()), &to).await?;
+    indoc::printdoc!(
+        "
+            copied to postgres
+            karbars: {}
+            proxies: {}
+            v2rays: {}
+            flyers: {}
+        ",
+        n.karbars,
+        n.proxies,
+        n.v2rays,
+        n.flyers
+    );
+    Ok(())
+}
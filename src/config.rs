@@ -1,9 +1,13 @@
 use sqlx::{
     SqlitePool,
-    sqlite::{SqliteConnectOptions, SqliteJournalMode},
+    sqlite::{
+        SqliteConnectOptions, SqliteJournalMode, SqlitePoolOptions,
+        SqliteSynchronous,
+    },
 };
 use std::{
     collections::HashSet,
+    path::PathBuf,
     str::FromStr,
     sync::{Arc, OnceLock},
     time::Duration,
@@ -16,7 +20,7 @@ use teloxide::{
     types::{ChatId, UserId},
 };
 
-use crate::state::State;
+use crate::{db::Backend, state::State};
 
 mod config_toml {
     use std::path::PathBuf;
@@ -31,9 +35,64 @@ mod config_toml {
         pub channel: i64,
     }
 
+    fn db_pool_size() -> u32 {
+        8
+    }
+
+    fn db_busy_timeout() -> u64 {
+        5
+    }
+
     #[derive(Debug, serde::Deserialize)]
     pub struct Db {
         pub path: String,
+        /// the bot needs sqlite until it runs on the repo layer
+        #[serde(default)]
+        pub backend: super::Backend,
+        /// postgres url for the postgres backend and the copy tool
+        pub postgres: Option<String>,
+        /// ip2asn v4 tsv for proxy regions instead of the bundled one
+        pub geoip: Option<String>,
+        /// max open connections of the main database
+        #[serde(default = "db_pool_size")]
+        pub pool_size: u32,
+        /// seconds to wait for a locked database
+        #[serde(default = "db_busy_timeout")]
+        pub busy_timeout: u64,
+    }
+
+    fn backup_dir() -> PathBuf {
+        PathBuf::from("backups")
+    }
+
+    fn backup_keep() -> usize {
+        7
+    }
+
+    fn backup_every() -> u64 {
+        24
+    }
+
+    #[derive(Debug, serde::Deserialize)]
+    pub struct Backup {
+        #[serde(default = "backup_dir")]
+        pub dir: PathBuf,
+        /// backups kept on disk
+        #[serde(default = "backup_keep")]
+        pub keep: usize,
+        /// hours between two backups
+        #[serde(default = "backup_every")]
+        pub every: u64,
+    }
+
+    impl Default for Backup {
+        fn default() -> Self {
+            Self {
+                dir: backup_dir(),
+                keep: backup_keep(),
+                every: backup_every(),
+            }
+        }
     }
 
     #[derive(Debug, serde::Deserialize)]
@@ -47,6 +106,8 @@ mod config_toml {
     pub struct ConfigToml {
         pub bot: Bot,
         pub db: Db,
+        #[serde(default)]
+        pub backup: Backup,
         pub force_join: Vec<ForceJoin>,
     }
 
@@ -81,8 +142,16 @@ mod config_toml {
 /// Tonel Config
 pub struct Config {
     bot_token: String,
-    bot_storage: String,
-    db_path: String,
+    pub bot_storage: String,
+    pub db_path: String,
+    pub db_backend: Backend,
+    pub postgres_url: Option<String>,
+    db_pool_size: u32,
+    db_busy_timeout: Duration,
+    pub geoip_path: Option<String>,
+    pub backup_dir: PathBuf,
+    pub backup_keep: usize,
+    pub backup_every: Duration,
     pub admins: HashSet<UserId>,
     pub force_join: Vec<(ChatId, String, reqwest::Url)>,
     pub dev: UserId,
@@ -95,10 +164,43 @@ pub struct Config {
 }
 
 impl Config {
-    /// 24 hours
-    pub const PRICE_STACK_RESET: i64 = 24 * 3600;
+    /// 10 minutes
+    pub const FORCE_JOIN_TTL: i64 = 10 * 60;
+    pub const FORCE_JOIN_CACHE_MAX: usize = 100_000;
+    /// karbars younger than 7 days are new for flyer targeting
+    pub const NEW_KARBAR_AGE: i64 = 7 * 24 * 3600;
+    /// view packages that advertisers can buy
+    pub const AD_PACKAGES: [u32; 4] = [1000, 5000, 10000, 50000];
     pub const CODE_ABC: &[u8] =
         b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789";
+    /// seconds before a delivered proxy or v2ray can be handed out again
+    pub const DELIVERY_FRESH: i64 = 7 * 24 * 3600;
+    /// seconds that received items stay in the karbar history
+    pub const HISTORY: i64 = 7 * 24 * 3600;
+    /// karbars younger than this can not vote
+    pub const VOTE_MIN_AGE: i64 = 24 * 3600;
+    /// other voters needed on an item to judge a vote against them
+    pub const VOTE_PEERS: i64 = 4;
+    /// votes lighter than this are suspicious
+    pub const VOTE_SUSPICIOUS: f64 = 0.3;
+    /// suspicious down votes on one item in a day to report it
+    pub const VOTE_CLUSTER_MIN: i64 = 3;
+    /// pending proxy submissions of a karbar
+    pub const SUBMIT_MAX_PENDING: i64 = 5;
+    /// karbars below this [`crate::db::SubmitStats::trust`] can not submit
+    pub const SUBMIT_MIN_TRUST: f64 = 0.2;
+    /// up votes for the good votes reward of a submitted proxy
+    pub const SUBMIT_GOOD_VOTES: i64 = 10;
+    pub const PING_TIMEOUT: Duration = Duration::from_secs(5);
+    /// seconds between two rescores of all vote scores
+    pub const RESCORE_EVERY: u64 = 3600;
+    /// seconds between two membership checks of the rewarded task joins
+    pub const CLAW_CHECK_EVERY: u64 = 6 * 3600;
+    /// inline shares of a karbar in every window
+    pub const INLINE_LIMIT: i64 = 10;
+    pub const INLINE_WINDOW: i64 = 3600;
+    /// seconds to answer a captcha
+    pub const CAPTCHA_TTL: i64 = 5 * 60;
     pub const SEND_ALL_SLEEP: Duration = Duration::from_secs(10);
     pub const SEND_ALL_NOTIF_AFTER: usize = 1024;
     pub const V2RAY_AUTO_UPDATE: i64 = 2 * 3600;
@@ -121,6 +223,14 @@ impl Config {
             bot_token: ct.bot.token,
             bot_storage: ct.bot.storage,
             db_path: ct.db.path,
+            db_backend: ct.db.backend,
+            postgres_url: ct.db.postgres,
+            db_pool_size: ct.db.pool_size.max(1),
+            db_busy_timeout: Duration::from_secs(ct.db.busy_timeout),
+            geoip_path: ct.db.geoip,
+            backup_dir: ct.backup.dir,
+            backup_keep: ct.backup.keep.max(1),
+            backup_every: Duration::from_secs(ct.backup.every.max(1) * 3600),
             admins: ct.bot.admins.iter().map(|id| UserId(*id)).collect(),
             dev: UserId(ct.bot.dev),
             bot_username: ct.bot.username,
@@ -175,10 +285,21 @@ impl Config {
             .unwrap_or_else(|_| {
                 panic!("could not init sqlite connection with uri: {uri}")
             })
-            .journal_mode(SqliteJournalMode::Off);
+            .journal_mode(SqliteJournalMode::Wal)
+            .synchronous(SqliteSynchronous::Normal)
+            .foreign_keys(true)
+            .busy_timeout(conf.db_busy_timeout)
+            .create_if_missing(true);
 
-        SqlitePool::connect_with(cpt)
+        let db = SqlitePoolOptions::new()
+            .max_connections(conf.db_pool_size)
+            .connect_with(cpt)
             .await
-            .unwrap_or_else(|_| panic!("sqlite connection failed with: {uri}"))
+            .unwrap_or_else(|_| panic!("sqlite connection failed with: {uri}"));
+
+        if let Err(e) = crate::db::migrate(&db).await {
+            panic!("database migration failed: {e:#?}");
+        }
+        db
     }
 }
@@ -0,0 +1,51 @@
+use crate::{Ctx, error::AppErr};
+
+#[derive(Debug, sqlx::FromRow)]
+/// the pending challenge of a karbar
+pub struct Captcha {
+    /// index of the right option
+    pub answer: i64,
+    /// see [`crate::captcha::Purpose::code`]
+    pub purpose: String,
+    pub created_at: i64,
+}
+
+impl Captcha {
+    pub async fn get(ctx: &Ctx, tid: i64) -> Option<Self> {
+        sqlx::query_as!(
+            Self,
+            "select answer, purpose, created_at from captchas where tid = ?",
+            tid
+        )
+        .fetch_optional(&ctx.db)
+        .await
+        .ok()
+        .flatten()
+    }
+
+    /// replace the pending challenge of `tid`
+    pub async fn set(
+        ctx: &Ctx, tid: i64, answer: i64, purpose: &str, now: i64,
+    ) -> Result<(), AppErr> {
+        sqlx::query!(
+            "insert into captchas(tid, answer, purpose, created_at)
+            values(?,?,?,?) on conflict(tid) do update set
+            answer = excluded.answer, purpose = excluded.purpose,
+            created_at = excluded.created_at",
+            tid,
+            answer,
+            purpose,
+            now
+        )
+        .execute(&ctx.db)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn del(ctx: &Ctx, tid: i64) -> Result<(), AppErr> {
+        sqlx::query!("delete from captchas where tid = ?", tid)
+            .execute(&ctx.db)
+            .await?;
+        Ok(())
+    }
+}
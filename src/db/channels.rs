@@ -0,0 +1,243 @@
+use crate::{Ctx, book::BookItem, error::AppErr};
+use std::fmt::Display;
+use teloxide::utils::html::escape;
+
+#[derive(Debug, sqlx::FromRow)]
+/// Sponsor Channel, join and earn points
+pub struct Channel {
+    /// telegram chat id of the channel
+    pub id: i64,
+    pub name: String,
+    /// reward points
+    pub amount: i64,
+    /// -1 means unlimited
+    pub max_sub: i64,
+    pub enabled: bool,
+    pub url: String,
+    /// leaving before this many days takes the reward back
+    pub claw_days: i64,
+    pub joins: i64,
+}
+
+impl Channel {
+    pub async fn list(ctx: &Ctx, page: u32) -> Result<Vec<Self>, AppErr> {
+        let offset = page * 32;
+        let res = sqlx::query_as!(
+            Self,
+            "select * from channels limit 32 offset ?",
+            offset
+        )
+        .fetch_all(&ctx.db)
+        .await?;
+        Ok(res)
+    }
+
+    pub async fn count(ctx: &Ctx) -> Result<u32, AppErr> {
+        let count = sqlx::query!("select COUNT(1) as count from channels")
+            .fetch_one(&ctx.db)
+            .await?;
+        Ok(count.count as u32)
+    }
+
+    pub async fn get(ctx: &Ctx, id: i64) -> Result<Self, AppErr> {
+        let rs =
+            sqlx::query_as!(Self, "select * from channels where id = ?", id)
+                .fetch_one(&ctx.db)
+                .await?;
+        Ok(rs)
+    }
+
+    pub async fn add(
+        ctx: &Ctx, id: i64, name: &str, url: &str,
+    ) -> Result<(), AppErr> {
+        sqlx::query!(
+            "insert into channels(id, name, url) values(?,?,?)
+            on conflict(id) do update set name = excluded.name,
+            url = excluded.url",
+            id,
+            name,
+            url
+        )
+        .execute(&ctx.db)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn set(&self, ctx: &Ctx) -> Result<(), AppErr> {
+        sqlx::query!(
+            "update channels set
+            name = ?,
+            amount = ?,
+            max_sub = ?,
+            enabled = ?,
+            url = ?,
+            claw_days = ?,
+            joins = ?
+            where id = ?",
+            self.name,
+            self.amount,
+            self.max_sub,
+            self.enabled,
+            self.url,
+            self.claw_days,
+            self.joins,
+            self.id
+        )
+        .execute(&ctx.db)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn del(ctx: &Ctx, id: i64) -> Result<(), AppErr> {
+        sqlx::query!("delete from channels where id = ?", id)
+            .execute(&ctx.db)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn del_all(ctx: &Ctx) -> Result<(), AppErr> {
+        sqlx::query!("delete from channels").execute(&ctx.db).await?;
+        Ok(())
+    }
+
+    /// enabled channels that the karbar has not been rewarded for yet
+    pub async fn tasks(ctx: &Ctx, karbar: i64) -> Result<Vec<Self>, AppErr> {
+        let res = sqlx::query_as!(
+            Self,
+            "select * from channels where enabled AND
+            (max_sub < 0 OR joins < max_sub) AND
+            id not in (select channel from channel_joins where karbar = ?)
+            limit 10",
+            karbar
+        )
+        .fetch_all(&ctx.db)
+        .await?;
+        Ok(res)
+    }
+
+    pub async fn rewarded(ctx: &Ctx, id: i64, karbar: i64) -> bool {
+        sqlx::query!(
+            "select id from channel_joins where channel = ? AND karbar = ?",
+            id,
+            karbar
+        )
+        .fetch_optional(&ctx.db)
+        .await
+        .ok()
+        .flatten()
+        .is_some()
+    }
+
+    /// record the join and give the reward to the karbar
+    pub async fn reward(
+        &mut self, ctx: &Ctx, karbar: i64, now: i64,
+    ) -> Result<(), AppErr> {
+        let mut tx = ctx.db.begin().await?;
+        sqlx::query!(
+            "insert into channel_joins(channel, karbar, points, created_at)
+            values(?,?,?,?)",
+            self.id,
+            karbar,
+            self.amount,
+            now
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query!(
+            "update karbars set points = points + ? where tid = ?",
+            self.amount,
+            karbar
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query!(
+            "update channels set joins = joins + 1 where id = ?",
+            self.id
+        )
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+
+        self.joins += 1;
+        Ok(())
+    }
+
+    /// rewarded joins that are still inside their `claw_days`, as
+    /// channel and karbar
+    pub async fn open_joins(
+        ctx: &Ctx, now: i64,
+    ) -> Result<Vec<(i64, i64)>, AppErr> {
+        let rows = sqlx::query!(
+            "select j.channel, j.karbar from channel_joins j
+            join channels c on c.id = j.channel
+            where NOT j.clawed AND j.created_at + c.claw_days * 86400 >= ?",
+            now
+        )
+        .fetch_all(&ctx.db)
+        .await?;
+        Ok(rows.into_iter().map(|r| (r.channel, r.karbar)).collect())
+    }
+
+    /// take the reward back if the karbar left before `claw_days`.
+    /// returns the channel name and the points taken
+    pub async fn claw_back(
+        ctx: &Ctx, id: i64, karbar: i64, now: i64,
+    ) -> Result<Option<(String, i64)>, AppErr> {
+        let row = sqlx::query!(
+            "select j.id, j.points, j.created_at, c.name, c.claw_days
+            from channel_joins j join channels c on c.id = j.channel
+            where j.channel = ? AND j.karbar = ? AND NOT j.clawed",
+            id,
+            karbar
+        )
+        .fetch_optional(&ctx.db)
+        .await?;
+
+        let Some(row) = row else { return Ok(None) };
+        if row.created_at + row.claw_days * 24 * 3600 < now {
+            return Ok(None);
+        }
+
+        let mut tx = ctx.db.begin().await?;
+        sqlx::query!(
+            "update channel_joins set clawed = true where id = ?",
+            row.id
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query!(
+            "update karbars set points = points - ? where tid = ?",
+            row.points,
+            karbar
+        )
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+
+        Ok(Some((row.name, row.points)))
+    }
+}
+
+impl Display for Channel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            r#"<a href="{}">{}</a> 🍅 {} | {}/{} {}"#,
+            escape(&self.url),
+            escape(&self.name),
+            self.amount,
+            self.joins,
+            self.max_sub,
+            if self.enabled { "" } else { "❌" }
+        )
+    }
+}
+
+impl BookItem for Channel {
+    fn id(&self) -> i64 {
+        self.id
+    }
+}
@@ -1,9 +1,19 @@
 use std::fmt::Display;
 
+use rand::Rng;
 use teloxide::utils::html::escape;
 
-use crate::{Ctx, book::BookItem, error::AppErr};
+use crate::{
+    Ctx,
+    book::BookItem,
+    config::Config,
+    error::AppErr,
+    i18n::{Lang, t},
+};
 
+use super::Karbar;
+
+#[derive(Debug, sqlx::FromRow)]
 pub struct Flyer {
     pub id: i64,
     pub label: String,
@@ -12,6 +22,52 @@ pub struct Flyer {
     pub views: i64,
     pub max_views: i64,
     pub disabled: bool,
+    pub starts_at: i64,
+    /// 0 means no end
+    pub ends_at: i64,
+    /// -1 means unlimited
+    pub daily_max_views: i64,
+    /// day number of `day_views`
+    pub day: i64,
+    pub day_views: i64,
+    /// max views per karbar per day. -1 means unlimited
+    pub user_cap: i64,
+    pub weight: i64,
+    /// see [`Flyer::AUDIENCE_ALL`]
+    pub audience: i64,
+    /// two letter language code of the targeted karbars
+    pub lang: Option<String>,
+    pub clicks: i64,
+    /// the advertiser. None for the flyers made by admins
+    pub owner: Option<i64>,
+    /// see [`Flyer::STATUS_ACTIVE`]
+    pub status: i64,
+    /// why the flyer was rejected
+    pub reason: Option<String>,
+}
+
+#[derive(Debug)]
+/// a stars payment for views of a flyer, kept for refunds
+pub struct FlyerCharge {
+    pub id: i64,
+    /// telegram charge id
+    pub charge_id: String,
+    pub stars: i64,
+}
+
+#[derive(Debug, Default)]
+/// views and clicks of a flyer in a single day
+pub struct FlyerDay {
+    pub day: i64,
+    pub views: i64,
+    pub clicks: i64,
+}
+
+#[derive(Debug, Default)]
+pub struct FlyerReport {
+    pub days: Vec<FlyerDay>,
+    pub viewers: i64,
+    pub clickers: i64,
 }
 
 impl Default for Flyer {
@@ -24,15 +80,77 @@ impl Default for Flyer {
             views: 0,
             max_views: -1,
             disabled: false,
+            starts_at: 0,
+            ends_at: 0,
+            daily_max_views: -1,
+            day: 0,
+            day_views: 0,
+            user_cap: -1,
+            weight: 1,
+            audience: Self::AUDIENCE_ALL,
+            lang: None,
+            clicks: 0,
+            owner: None,
+            status: Self::STATUS_ACTIVE,
+            reason: None,
         }
     }
 }
 
 impl Flyer {
+    pub const AUDIENCE_ALL: i64 = 0;
+    pub const AUDIENCE_NEW: i64 = 1;
+    pub const AUDIENCE_OLD: i64 = 2;
+
+    pub const STATUS_ACTIVE: i64 = 0;
+    /// waiting for the advertiser to pay
+    pub const STATUS_DRAFT: i64 = 1;
+    /// waiting for an admin to review
+    pub const STATUS_PENDING: i64 = 2;
+    pub const STATUS_REJECTED: i64 = 3;
+
     pub fn new(label: String, mid: i64) -> Self {
         Self { label, mid, ..Default::default() }
     }
 
+    /// drafts and live flyers can be paid for, pending and rejected ones
+    /// can not go live by paying
+    pub fn payable(&self) -> bool {
+        matches!(self.status, Self::STATUS_DRAFT | Self::STATUS_ACTIVE)
+    }
+
+    /// click through rate in percent
+    pub fn ctr(&self) -> f64 {
+        Self::rate(self.clicks, self.views)
+    }
+
+    /// percent of `views` that were clicked. older views were not all
+    /// counted, so it is kept at most 100
+    pub fn rate(clicks: i64, views: i64) -> f64 {
+        if views < 1 {
+            return 0.0;
+        }
+        (clicks as f64 * 100.0 / views as f64).clamp(0.0, 100.0)
+    }
+
+    pub fn status_str(&self, lang: Lang) -> &'static str {
+        match self.status {
+            Self::STATUS_DRAFT => t!(lang, "flyer_draft"),
+            Self::STATUS_PENDING => t!(lang, "flyer_pending"),
+            Self::STATUS_REJECTED => t!(lang, "flyer_rejected"),
+            _ if self.disabled => t!(lang, "flyer_finished"),
+            _ => t!(lang, "flyer_active"),
+        }
+    }
+
+    pub fn audience_str(&self) -> &'static str {
+        match self.audience {
+            Self::AUDIENCE_NEW => "کاربران جدید 🐣",
+            Self::AUDIENCE_OLD => "کاربران قدیمی 🦉",
+            _ => "همه کاربران 👥",
+        }
+    }
+
     pub async fn list(ctx: &Ctx, page: u32) -> Result<Vec<Self>, AppErr> {
         let offset = page * 32;
         let res = sqlx::query_as!(
@@ -52,10 +170,38 @@ impl Flyer {
         Ok(count.count as u32)
     }
 
+    /// flyers of an advertiser
+    pub async fn list_owner(
+        ctx: &Ctx, owner: i64,
+    ) -> Result<Vec<Self>, AppErr> {
+        let res = sqlx::query_as!(
+            Self,
+            "select * from flyers where owner = ? order by id desc limit 20",
+            owner
+        )
+        .fetch_all(&ctx.db)
+        .await?;
+        Ok(res)
+    }
+
+    /// flyers waiting for review
+    pub async fn queue(ctx: &Ctx) -> Result<Vec<Self>, AppErr> {
+        let res = sqlx::query_as!(
+            Self,
+            "select * from flyers where status = ? order by id limit 10",
+            Self::STATUS_PENDING
+        )
+        .fetch_all(&ctx.db)
+        .await?;
+        Ok(res)
+    }
+
     pub async fn add(&mut self, ctx: &Ctx) -> Result<(), AppErr> {
         let res = sqlx::query! {
-            "insert into flyers(label, mid) values(?,?)",
-            self.label, self.mid
+            "insert into flyers(label, mid, link, owner, status, max_views)
+            values(?,?,?,?,?,?)",
+            self.label, self.mid, self.link, self.owner, self.status,
+            self.max_views
         }
         .execute(&ctx.db)
         .await?;
@@ -71,31 +217,219 @@ impl Flyer {
         Ok(rs)
     }
 
-    pub async fn get_good(ctx: &Ctx) -> Option<Self> {
-        sqlx::query_as!(
+    /// all the flyers that this karbar is allowed to see right now
+    async fn candidates(
+        ctx: &Ctx, karbar: &Karbar, now: i64, link: bool,
+    ) -> Result<Vec<Self>, AppErr> {
+        let day = now / 86400;
+        let audience = if karbar.created_at + Config::NEW_KARBAR_AGE > now {
+            Self::AUDIENCE_NEW
+        } else {
+            Self::AUDIENCE_OLD
+        };
+        let lang = karbar.lang_prefix();
+
+        let res = sqlx::query_as!(
             Self,
-            "select * from flyers
-            where NOT (disabled OR (max_views > -1 AND views >= max_views))
-            order by random() limit 1"
+            "select * from flyers where status = 0 AND
+            NOT (disabled OR (max_views > -1 AND views >= max_views)) AND
+            (? = false OR link is not NULL) AND
+            starts_at <= ? AND (ends_at = 0 OR ends_at > ?) AND
+            (daily_max_views < 0 OR day != ? OR day_views < daily_max_views)
+            AND (audience = 0 OR audience = ?)
+            AND (lang is NULL OR lang = ?)
+            AND (user_cap < 0 OR user_cap > coalesce((
+                select views from flyer_views
+                where flyer = flyers.id AND karbar = ? AND day = ?
+            ), 0))",
+            link,
+            now,
+            now,
+            day,
+            audience,
+            lang,
+            karbar.tid,
+            day
         )
-        .fetch_optional(&ctx.db)
-        .await
-        .ok()
-        .flatten()
+        .fetch_all(&ctx.db)
+        .await?;
+
+        Ok(res)
     }
 
-    pub async fn get_good_link(ctx: &Ctx) -> Option<Self> {
-        sqlx::query_as!(
-            Self,
-            "select * from flyers
-            where link is not NULL AND
-            NOT (disabled OR (max_views > -1 AND views >= max_views))
-            order by random() limit 1"
+    /// weighted random pick
+    fn pick(mut flyers: Vec<Self>) -> Option<Self> {
+        let total: i64 = flyers.iter().map(|f| f.weight.max(1)).sum();
+        if total == 0 {
+            return None;
+        }
+
+        let mut r = rand::rng().random_range(0..total);
+        let idx = flyers.iter().position(|f| {
+            let w = f.weight.max(1);
+            if r < w {
+                return true;
+            }
+            r -= w;
+            false
+        })?;
+
+        Some(flyers.swap_remove(idx))
+    }
+
+    pub async fn get_good(
+        ctx: &Ctx, karbar: &Karbar, now: i64,
+    ) -> Option<Self> {
+        let flyers = Self::candidates(ctx, karbar, now, false).await.ok()?;
+        Self::pick(flyers)
+    }
+
+    pub async fn get_good_link(
+        ctx: &Ctx, karbar: &Karbar, now: i64,
+    ) -> Option<Self> {
+        let flyers = Self::candidates(ctx, karbar, now, true).await.ok()?;
+        Self::pick(flyers)
+    }
+
+    /// count a view of this flyer for the karbar. the caps are checked in
+    /// the same update, false when the flyer can not take another view
+    pub async fn view(
+        &mut self, ctx: &Ctx, karbar: i64, now: i64,
+    ) -> Result<bool, AppErr> {
+        let day = now / 86400;
+        let mut tx = ctx.db.begin().await?;
+        let res = sqlx::query!(
+            "update flyers set views = views + 1,
+            day_views = CASE WHEN day = ? THEN day_views + 1 ELSE 1 END,
+            day = ?
+            where id = ? AND status = 0 AND NOT disabled AND
+            (max_views < 0 OR views < max_views) AND
+            (ends_at = 0 OR ends_at > ?) AND
+            (daily_max_views < 0 OR day != ? OR day_views < daily_max_views)
+            AND (user_cap < 0 OR user_cap > coalesce((
+                select views from flyer_views
+                where flyer = flyers.id AND karbar = ? AND day = ?
+            ), 0))",
+            day,
+            day,
+            self.id,
+            now,
+            day,
+            karbar,
+            day
         )
-        .fetch_optional(&ctx.db)
-        .await
-        .ok()
-        .flatten()
+        .execute(&mut *tx)
+        .await?;
+        if res.rows_affected() == 0 {
+            return Ok(false);
+        }
+
+        sqlx::query!(
+            "insert into flyer_views(flyer, karbar, day, views)
+            values(?,?,?,1) on conflict(flyer, karbar, day)
+            do update set views = views + 1",
+            self.id,
+            karbar,
+            day
+        )
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+
+        if self.day != day {
+            self.day = day;
+            self.day_views = 0;
+        }
+        self.views += 1;
+        self.day_views += 1;
+        Ok(true)
+    }
+
+    /// count a click on the flyer link
+    pub async fn click(
+        &mut self, ctx: &Ctx, karbar: i64, now: i64,
+    ) -> Result<(), AppErr> {
+        sqlx::query!(
+            "insert into flyer_clicks(flyer, karbar, created_at) values(?,?,?)",
+            self.id,
+            karbar,
+            now
+        )
+        .execute(&ctx.db)
+        .await?;
+
+        sqlx::query!(
+            "update flyers set clicks = clicks + 1 where id = ?",
+            self.id
+        )
+        .execute(&ctx.db)
+        .await?;
+
+        self.clicks += 1;
+        Ok(())
+    }
+
+    /// daily views and clicks since `day`
+    pub async fn report(
+        ctx: &Ctx, id: i64, day: i64,
+    ) -> Result<FlyerReport, AppErr> {
+        let views = sqlx::query!(
+            r#"select day, SUM(views) as "views!: i64"
+            from flyer_views where flyer = ? AND day >= ?
+            group by day order by day"#,
+            id,
+            day
+        )
+        .fetch_all(&ctx.db)
+        .await?;
+
+        let since = day * 86400;
+        let clicks = sqlx::query!(
+            r#"select created_at / 86400 as "day!: i64",
+            COUNT(1) as "clicks!: i64"
+            from flyer_clicks where flyer = ? AND created_at >= ?
+            group by 1 order by 1"#,
+            id,
+            since
+        )
+        .fetch_all(&ctx.db)
+        .await?;
+
+        let mut days: Vec<FlyerDay> = views
+            .iter()
+            .map(|v| FlyerDay { day: v.day, views: v.views, clicks: 0 })
+            .collect();
+        for c in clicks {
+            match days.iter_mut().find(|d| d.day == c.day) {
+                Some(d) => d.clicks = c.clicks,
+                None => days.push(FlyerDay {
+                    day: c.day,
+                    clicks: c.clicks,
+                    views: 0,
+                }),
+            }
+        }
+        days.sort_by_key(|d| d.day);
+
+        let viewers = sqlx::query!(
+            r#"select COUNT(distinct karbar) as "count!: i64"
+            from flyer_views where flyer = ?"#,
+            id
+        )
+        .fetch_one(&ctx.db)
+        .await?
+        .count;
+
+        let clickers = sqlx::query!(
+            r#"select COUNT(distinct karbar) as "count!: i64"
+            from flyer_clicks where flyer = ?"#,
+            id
+        )
+        .fetch_one(&ctx.db)
+        .await?
+        .count;
+
+        Ok(FlyerReport { days, viewers, clickers })
     }
 
     pub async fn del(ctx: &Ctx, id: i64) -> Result<(), AppErr> {
@@ -110,6 +444,64 @@ impl Flyer {
         Ok(())
     }
 
+    pub async fn charge_add(
+        &self, ctx: &Ctx, charge_id: &str, stars: i64, views: i64, now: i64,
+    ) -> Result<(), AppErr> {
+        sqlx::query!(
+            "insert into flyer_charges(flyer, charge_id, stars, views, created_at)
+            values(?,?,?,?,?)",
+            self.id,
+            charge_id,
+            stars,
+            views,
+            now
+        )
+        .execute(&ctx.db)
+        .await?;
+        Ok(())
+    }
+
+    /// payments of this flyer that were not refunded
+    pub async fn charges(&self, ctx: &Ctx) -> Result<Vec<FlyerCharge>, AppErr> {
+        let res = sqlx::query_as!(
+            FlyerCharge,
+            "select id, charge_id, stars from flyer_charges
+            where flyer = ? AND NOT refunded",
+            self.id
+        )
+        .fetch_all(&ctx.db)
+        .await?;
+        Ok(res)
+    }
+
+    pub async fn charge_refunded(ctx: &Ctx, id: i64) -> Result<(), AppErr> {
+        sqlx::query!(
+            "update flyer_charges set refunded = true where id = ?",
+            id
+        )
+        .execute(&ctx.db)
+        .await?;
+        Ok(())
+    }
+
+    /// stop showing a flyer whose message can not be sent
+    pub async fn disable(&mut self, ctx: &Ctx) -> Result<(), AppErr> {
+        sqlx::query!("update flyers set disabled = true where id = ?", self.id)
+            .execute(&ctx.db)
+            .await?;
+        self.disabled = true;
+        Ok(())
+    }
+
+    /// drop a link that is not a valid url
+    pub async fn unlink(&mut self, ctx: &Ctx) -> Result<(), AppErr> {
+        sqlx::query!("update flyers set link = NULL where id = ?", self.id)
+            .execute(&ctx.db)
+            .await?;
+        self.link = None;
+        Ok(())
+    }
+
     pub async fn set(&mut self, ctx: &Ctx) -> Result<(), AppErr> {
         if self.max_views > -1 && self.views >= self.max_views {
             self.disabled = true;
@@ -121,13 +513,35 @@ impl Flyer {
             label = ?,
             views = ?,
             link = ?,
-            max_views = ?
+            max_views = ?,
+            starts_at = ?,
+            ends_at = ?,
+            daily_max_views = ?,
+            day = ?,
+            day_views = ?,
+            user_cap = ?,
+            weight = ?,
+            audience = ?,
+            lang = ?,
+            status = ?,
+            reason = ?
             where id = ?",
             self.disabled,
             self.label,
             self.views,
             self.link,
             self.max_views,
+            self.starts_at,
+            self.ends_at,
+            self.daily_max_views,
+            self.day,
+            self.day_views,
+            self.user_cap,
+            self.weight,
+            self.audience,
+            self.lang,
+            self.status,
+            self.reason,
             self.id
         )
         .execute(&ctx.db)
@@ -141,10 +555,18 @@ impl Display for Flyer {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
             f,
-            r#"<b>{}</b> {}/{} {}"#,
+            r#"<b>{}</b> {}{}/{} 👆{} ⚖{} {}"#,
             escape(&self.label),
+            match self.status {
+                Self::STATUS_ACTIVE => "",
+                Self::STATUS_PENDING => "⏳ ",
+                Self::STATUS_DRAFT => "💳 ",
+                _ => "🚫 ",
+            },
             self.views,
             self.max_views,
+            self.clicks,
+            self.weight,
             if self.disabled { "❌" } else { "" }
         )
     }
@@ -155,3 +577,62 @@ impl BookItem for Flyer {
         self.id
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::Flyer;
+    use crate::{Ctx, cache::MemberCache};
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    async fn ctx() -> Ctx {
+        let db = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        crate::db::migrate(&db).await.unwrap();
+        sqlx::query(
+            "insert into karbars(tid, fullname, created_at, updated_at,
+            invite_code) values (1, 'a', 0, 0, 'c1'), (2, 'b', 0, 0, 'c2')",
+        )
+        .execute(&db)
+        .await
+        .unwrap();
+        Ctx { db, fj: MemberCache::default() }
+    }
+
+    #[tokio::test]
+    async fn view_caps() {
+        let ctx = ctx().await;
+        let mut f = Flyer::new("f".into(), 1);
+        f.max_views = 3;
+        f.add(&ctx).await.unwrap();
+        sqlx::query(
+            "update flyers set daily_max_views = 2, user_cap = 1 where id = ?",
+        )
+        .bind(f.id)
+        .execute(&ctx.db)
+        .await
+        .unwrap();
+
+        let day = 86400 * 10;
+        assert!(f.view(&ctx, 1, day).await.unwrap());
+        // one view per karbar a day
+        assert!(!f.view(&ctx, 1, day).await.unwrap());
+        assert!(f.view(&ctx, 2, day).await.unwrap());
+        // two views a day
+        sqlx::query("update flyers set user_cap = -1")
+            .execute(&ctx.db)
+            .await
+            .unwrap();
+        assert!(!f.view(&ctx, 1, day).await.unwrap());
+        // a new day starts the daily count over
+        assert!(f.view(&ctx, 1, day + 86400).await.unwrap());
+        // three views in total
+        assert!(!f.view(&ctx, 2, day + 86400).await.unwrap());
+
+        let g = Flyer::get(&ctx, f.id).await.unwrap();
+        assert_eq!((g.views, g.day, g.day_views), (3, 11, 1));
+        assert_eq!((f.views, f.day, f.day_views), (3, 11, 1));
+    }
+}
@@ -1,5 +1,8 @@
 use crate::config::Config;
 use crate::error::{AppErr, err};
+use crate::i18n::Lang;
+use crate::streak;
+use crate::surge::{self, Product};
 use crate::utils::now;
 use crate::{Ctx, utils};
 use teloxide::types::{ChatId, User, UserId};
@@ -19,8 +22,36 @@ pub struct Karbar {
     pub last_free_point_at: i64,
     pub invite_code: String,
     pub blocked: bool,
-    pub last_request: i64,
-    pub price_stack: i64,
+    /// telegram language code of the user
+    pub language_code: Option<String>,
+    /// chosen bot language, see [`Lang::code`]
+    pub lang: Option<String>,
+    /// surge stacks and the time they were last stacked
+    pub proxy_stack: i64,
+    pub proxy_stack_at: i64,
+    pub v2ray_stack: i64,
+    pub v2ray_stack_at: i64,
+    pub vip_stack: i64,
+    pub vip_stack_at: i64,
+    /// spend points without the confirm step
+    pub skip_confirm: bool,
+    /// free point claims in a row
+    pub streak: i64,
+    /// the next claim reminder was sent
+    pub streak_reminded: bool,
+    /// solved the first captcha
+    pub verified: bool,
+    /// tid of the inviter, paid after verification
+    pub invited_by: Option<i64>,
+    pub captcha_fails: i64,
+    pub captcha_locked_until: i64,
+    /// free point claims since the last captcha
+    pub captcha_claims: i64,
+    /// inline shares since `inline_window_at`
+    pub inline_shares: i64,
+    pub inline_window_at: i64,
+    /// preferred proxy country, see [`crate::geoip`]
+    pub region: Option<String>,
 }
 
 impl Karbar {
@@ -32,6 +63,22 @@ impl Karbar {
         UserId(self.tid as u64)
     }
 
+    /// two letter language code. e.g. `en` from `en-US`
+    pub fn lang_prefix(&self) -> Option<String> {
+        let lc = self.language_code.as_deref()?;
+        let p = lc.split(['-', '_']).next().unwrap_or(lc);
+        Some(p.to_lowercase())
+    }
+
+    /// chosen language or the telegram one, persian by default
+    pub fn lang(&self) -> Lang {
+        self.lang
+            .as_deref()
+            .or(self.language_code.as_deref())
+            .and_then(Lang::from_code)
+            .unwrap_or_default()
+    }
+
     pub fn is_admin(&self) -> bool {
         let conf = Config::get();
         conf.admins.contains(&self.uid())
@@ -61,6 +108,7 @@ impl Karbar {
         let tid = user.id.0 as i64;
         let fullname = user.full_name();
         let username = user.username.clone();
+        let language_code = user.language_code.clone();
         let updated_at = now();
 
         let karbar = sqlx::query_as! {
@@ -70,7 +118,11 @@ impl Karbar {
         .await?;
 
         let Some(mut karbar) = karbar else {
-            let _ = Self::invited(ctx, c).await;
+            let invited_by = Self::find_with_code(ctx, c).await.map(|k| k.tid);
+            let verified = !Settings::get(&ctx.db).await.captcha_enabled;
+            if let (true, Some(inviter)) = (verified, invited_by) {
+                let _ = Self::invite_reward(ctx, inviter).await;
+            }
 
             let code = loop {
                 let code = utils::random_code();
@@ -92,14 +144,20 @@ impl Karbar {
                 username,
                 created_at,
                 updated_at,
-                invite_code
-            ) values(?,?,?,?,?,?)",
+                invite_code,
+                language_code,
+                verified,
+                invited_by
+            ) values(?,?,?,?,?,?,?,?,?)",
                 tid,
                 fullname,
                 username,
                 updated_at,
                 updated_at,
-                code
+                code,
+                language_code,
+                verified,
+                invited_by
             }
             .execute(&ctx.db)
             .await?;
@@ -115,8 +173,25 @@ impl Karbar {
                 points: 0,
                 last_free_point_at: 0,
                 invite_code: code,
-                last_request: 0,
-                price_stack: 0,
+                language_code,
+                lang: None,
+                proxy_stack: 0,
+                proxy_stack_at: 0,
+                v2ray_stack: 0,
+                v2ray_stack_at: 0,
+                vip_stack: 0,
+                vip_stack_at: 0,
+                skip_confirm: false,
+                streak: 0,
+                streak_reminded: false,
+                verified,
+                invited_by,
+                captcha_fails: 0,
+                captcha_locked_until: 0,
+                captcha_claims: 0,
+                inline_shares: 0,
+                inline_window_at: 0,
+                region: None,
             });
         };
 
@@ -126,6 +201,7 @@ impl Karbar {
 
         karbar.username = username;
         karbar.fullname = fullname;
+        karbar.language_code = language_code;
         karbar.updated_at = updated_at;
         karbar.blocked = false;
 
@@ -144,8 +220,25 @@ impl Karbar {
             updated_at = ?,
             points = ?,
             last_free_point_at = ?,
-            last_request = ?,
-            price_stack = ?
+            language_code = ?,
+            lang = ?,
+            proxy_stack = ?,
+            proxy_stack_at = ?,
+            v2ray_stack = ?,
+            v2ray_stack_at = ?,
+            vip_stack = ?,
+            vip_stack_at = ?,
+            skip_confirm = ?,
+            streak = ?,
+            -- only the reminder loop sets it, a claim resets it
+            streak_reminded = streak_reminded AND last_free_point_at = ?,
+            verified = ?,
+            captcha_fails = ?,
+            captcha_locked_until = ?,
+            captcha_claims = ?,
+            inline_shares = ?,
+            inline_window_at = ?,
+            region = ?
             where tid = ?
         ",
             self.fullname,
@@ -156,8 +249,24 @@ impl Karbar {
             self.updated_at,
             self.points,
             self.last_free_point_at,
-            self.last_request,
-            self.price_stack,
+            self.language_code,
+            self.lang,
+            self.proxy_stack,
+            self.proxy_stack_at,
+            self.v2ray_stack,
+            self.v2ray_stack_at,
+            self.vip_stack,
+            self.vip_stack_at,
+            self.skip_confirm,
+            self.streak,
+            self.last_free_point_at,
+            self.verified,
+            self.captcha_fails,
+            self.captcha_locked_until,
+            self.captcha_claims,
+            self.inline_shares,
+            self.inline_window_at,
+            self.region,
             self.tid
         }
         .execute(&ctx.db)
@@ -166,51 +275,252 @@ impl Karbar {
         Ok(())
     }
 
-    pub fn calc_cost(&mut self, cost: i64) -> i64 {
-        let now = crate::utils::now();
-        if self.last_request + Config::PRICE_STACK_RESET < now {
-            self.price_stack = 0;
+    fn stack_mut(&mut self, product: Product) -> (&mut i64, &mut i64) {
+        match product {
+            Product::Proxy | Product::Bundle => {
+                (&mut self.proxy_stack, &mut self.proxy_stack_at)
+            }
+            Product::V2ray => (&mut self.v2ray_stack, &mut self.v2ray_stack_at),
+            Product::Vip => (&mut self.vip_stack, &mut self.vip_stack_at),
         }
+    }
 
-        self.last_request = now;
-        self.price_stack += 1;
-
-        let added = match self.price_stack {
-            1 => 0.0,
-            2 => 0.001,
-            3 => 0.03,
-            4 => 0.1,
-            5 => 0.3,
-            6 => 0.6,
-            7 => 1.1,
-            8 => 1.7,
-            9 => 3.0,
-            x => x as f64,
+    /// current cost of `product` with the surge of the earlier requests
+    pub fn surge_cost(
+        &self, product: Product, settings: &Settings, now: i64,
+    ) -> i64 {
+        let (stack, at) = match product {
+            Product::Proxy | Product::Bundle => {
+                (self.proxy_stack, self.proxy_stack_at)
+            }
+            Product::V2ray => (self.v2ray_stack, self.v2ray_stack_at),
+            Product::Vip => (self.vip_stack, self.vip_stack_at),
         };
+        let stack = surge::decayed(stack, at, now, settings.surge_decay);
+        surge::price(product.cost(settings), &product.curve(settings), stack)
+    }
 
-        cost + (cost as f64 * added) as i64
+    /// stack one more paid request of `product`
+    pub fn surge_add(
+        &mut self, product: Product, settings: &Settings, now: i64,
+    ) {
+        let (stack, at) = self.stack_mut(product);
+        *stack = surge::decayed(*stack, *at, now, settings.surge_decay) + 1;
+        *at = now;
     }
 
-    pub async fn invited(ctx: &Ctx, code: &str) -> Result<(), AppErr> {
+    /// proxies and v2rays received since `since`
+    pub async fn deliveries(
+        &self, ctx: &Ctx, since: i64,
+    ) -> Result<i64, AppErr> {
+        let r = sqlx::query!(
+            "select
+            (select COUNT(1) from proxy_deliveries where karbar = ? AND at > ?) +
+            (select COUNT(1) from v2rays_deliveries where karbar = ? AND at > ?)
+            as \"count!: i64\"",
+            self.tid,
+            since,
+            self.tid,
+            since
+        )
+        .fetch_one(&ctx.db)
+        .await?;
+        Ok(r.count)
+    }
+
+    /// past votes that agree with the weighted consensus of the other
+    /// voters and all past votes, only counting items that at least
+    /// `VOTE_PEERS` others voted on. the own vote is left out of the
+    /// consensus so a voter can not agree with itself
+    pub async fn vote_agreement(
+        &self, ctx: &Ctx,
+    ) -> Result<(i64, i64), AppErr> {
+        let peers = Config::VOTE_PEERS;
+        let r = sqlx::query!(
+            "select COUNT(1) as \"total!: i64\",
+            SUM((v.kind > 0) = (o.up * 2 >= o.total)) as \"agree: i64\"
+            from (
+                select kind, proxy as item, 0 as v2 from proxy_votes
+                where karbar = ?
+                union all
+                select kind, v2ray as item, 1 as v2 from v2rays_votes
+                where karbar = ?
+            ) v join (
+                select proxy as item, 0 as v2, COUNT(1) as n,
+                SUM(IIF(kind > 0, weight, 0)) as up, SUM(weight) as total
+                from proxy_votes where karbar != ? group by proxy
+                union all
+                select v2ray as item, 1 as v2, COUNT(1) as n,
+                SUM(IIF(kind > 0, weight, 0)) as up, SUM(weight) as total
+                from v2rays_votes where karbar != ? group by v2ray
+            ) o on o.item = v.item AND o.v2 = v.v2
+            where o.n >= ? AND o.total > 0",
+            self.tid,
+            self.tid,
+            self.tid,
+            self.tid,
+            peers
+        )
+        .fetch_one(&ctx.db)
+        .await?;
+        Ok((r.agree.unwrap_or_default(), r.total))
+    }
+
+    pub async fn vip_delivered(
+        ctx: &Ctx, tid: i64, msg: i64, now: i64,
+    ) -> Result<(), AppErr> {
+        sqlx::query!(
+            "insert into vip_deliveries(karbar, msg, at) values(?,?,?)
+            on conflict(karbar, msg) do update set at = excluded.at",
+            tid,
+            msg,
+            now
+        )
+        .execute(&ctx.db)
+        .await?;
+        Ok(())
+    }
+
+    /// the karbar received the vip message `msg` since `since`
+    pub async fn vip_received(
+        ctx: &Ctx, tid: i64, msg: i64, since: i64,
+    ) -> bool {
+        sqlx::query!(
+            "select at from vip_deliveries
+            where karbar = ? AND msg = ? AND at > ?",
+            tid,
+            msg,
+            since
+        )
+        .fetch_optional(&ctx.db)
+        .await
+        .ok()
+        .flatten()
+        .is_some()
+    }
+
+    /// inline shares left in the current window
+    pub fn inline_left(&self, now: i64) -> i64 {
+        if now - self.inline_window_at >= Config::INLINE_WINDOW {
+            return Config::INLINE_LIMIT;
+        }
+        Config::INLINE_LIMIT - self.inline_shares
+    }
+
+    /// take the cost of an inline share and count it in the window.
+    /// false when the points or the shares left are not enough
+    pub async fn inline_charge(
+        ctx: &Ctx, tid: i64, cost: i64, now: i64,
+    ) -> Result<bool, AppErr> {
+        let (window, limit) = (Config::INLINE_WINDOW, Config::INLINE_LIMIT);
+        let res = sqlx::query!(
+            "update karbars set
+            points = points - ?,
+            inline_shares = case when ? - inline_window_at >= ?
+                then 1 else inline_shares + 1 end,
+            inline_window_at = case when ? - inline_window_at >= ?
+                then ? else inline_window_at end
+            where tid = ? AND points >= ? AND
+            (? - inline_window_at >= ? OR inline_shares < ?)",
+            cost,
+            now,
+            window,
+            now,
+            window,
+            now,
+            tid,
+            cost,
+            now,
+            window,
+            limit
+        )
+        .execute(&ctx.db)
+        .await?;
+        Ok(res.rows_affected() > 0)
+    }
+
+    /// the streak if it can still be continued, 0 otherwise
+    pub fn streak_alive(&self, settings: &Settings, now: i64) -> i64 {
+        let limit = settings.free_point_delay + settings.streak_window;
+        if now - self.last_free_point_at > limit {
+            return 0;
+        }
+        self.streak
+    }
+
+    /// claim the free points and return the reward
+    pub fn streak_claim(&mut self, settings: &Settings, now: i64) -> i64 {
+        self.streak = self.streak_alive(settings, now) + 1;
+        self.streak_reminded = false;
+        self.last_free_point_at = now;
+        let reward = streak::reward(settings, self.streak);
+        self.points += reward;
+        reward
+    }
+
+    /// karbars whose next claim is available and the streak is not lost yet
+    pub async fn streak_due(
+        ctx: &Ctx, settings: &Settings, now: i64,
+    ) -> Result<Vec<Self>, AppErr> {
+        let ready = now - settings.free_point_delay;
+        let lost = ready - settings.streak_window;
+        let res = sqlx::query_as!(
+            Self,
+            "select * from karbars where streak > 0 AND
+            streak_reminded = false AND blocked = false AND banned = false AND
+            last_free_point_at <= ? AND last_free_point_at > ?",
+            ready,
+            lost
+        )
+        .fetch_all(&ctx.db)
+        .await?;
+        Ok(res)
+    }
+
+    /// mark the karbar as reminded, false when it already was
+    pub async fn streak_reminded(ctx: &Ctx, tid: i64) -> Result<bool, AppErr> {
+        let res = sqlx::query!(
+            "update karbars set streak_reminded = true
+            where tid = ? AND NOT streak_reminded",
+            tid
+        )
+        .execute(&ctx.db)
+        .await?;
+        Ok(res.rows_affected() > 0)
+    }
+
+    pub async fn find_with_code(ctx: &Ctx, code: &str) -> Option<Self> {
         if code.is_empty() {
-            return Ok(());
+            return None;
         }
 
-        let karbar = sqlx::query_as!(
+        sqlx::query_as!(
             Karbar,
             "select * from karbars where invite_code = ?",
             code
         )
         .fetch_optional(&ctx.db)
-        .await?;
-
-        let Some(mut karbar) = karbar else { return Ok(()) };
+        .await
+        .ok()
+        .flatten()
+    }
 
+    /// pay the invite points to the inviter `tid`
+    pub async fn invite_reward(ctx: &Ctx, tid: i64) -> Result<(), AppErr> {
         let added = Settings::get(&ctx.db).await.invite_points;
+        Self::add_points(ctx, tid, added).await
+    }
 
-        karbar.points += added;
-        karbar.set(ctx).await?;
-
+    pub async fn add_points(
+        ctx: &Ctx, tid: i64, points: i64,
+    ) -> Result<(), AppErr> {
+        sqlx::query!(
+            "update karbars set points = points + ? where tid = ?",
+            points,
+            tid
+        )
+        .execute(&ctx.db)
+        .await?;
         Ok(())
     }
 
@@ -1,11 +1,25 @@
+mod captchas;
 mod karbars;
 mod flyer;
 mod proxies;
+mod repo;
+mod schema;
 mod settings;
+mod submissions;
 mod v2rays;
+mod channels;
+mod templates;
 
+pub use captchas::Captcha;
 pub use karbars::{Karbar, KarbarStats};
 pub use proxies::Proxy;
+pub use repo::{
+    Backend, PgRepo, Repo, SqliteRepo, copy, open, postgres_url,
+};
+pub use schema::{foreign_key_check, integrity_check, migrate, schema_version};
 pub use settings::Settings;
+pub use submissions::Submission;
 pub use flyer::Flyer;
+pub use channels::Channel;
+pub use templates::Template;
 pub use v2rays::{V2ray, v2ray_auto_update, v2ray_do_auto_update};
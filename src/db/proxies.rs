@@ -1,5 +1,12 @@
-use crate::{Ctx, book::BookItem, error::AppErr};
+use crate::vote::{self, Cast, Cluster, Tally};
+use crate::{
+    Ctx, book::BookItem, bulk::Filter, config::Config, error::AppErr, geoip,
+};
+
+use super::{Karbar, Settings};
+use std::collections::HashSet;
 use std::fmt::Display;
+use std::net::IpAddr;
 use teloxide::utils::html::escape;
 
 #[derive(Debug, sqlx::FromRow)]
@@ -12,20 +19,21 @@ pub struct Proxy {
     pub up_votes: i64,
     pub dn_votes: i64,
     pub disabled: bool,
+    /// see [`crate::vote::Tally::score`]
+    pub score: f64,
+    /// two letter country code from [`crate::geoip`]
+    pub country: Option<String>,
+    pub asn: Option<i64>,
 }
 
 impl Proxy {
     pub fn up_dn_pct(&self) -> (u8, u8) {
         let ttv = self.up_votes + self.dn_votes;
-        let mut upp = 0;
-        let mut dnp = 0;
-        if self.up_votes > 0 {
-            upp = (100 / (ttv / self.up_votes)) as u8;
-        }
-        if self.dn_votes > 0 {
-            dnp = (100 / (ttv / self.dn_votes)) as u8;
+        if ttv <= 0 {
+            return (0, 0);
         }
-        (upp, dnp)
+        let upp = self.up_votes * 100 / ttv;
+        (upp as u8, (100 - upp) as u8)
     }
 
     pub fn url(&self) -> String {
@@ -48,6 +56,9 @@ impl Proxy {
             dn_votes: 0,
             up_votes: 0,
             disabled: false,
+            score: vote::neutral(),
+            country: None,
+            asn: None,
         };
 
         for x in spp.split('&') {
@@ -69,6 +80,34 @@ impl Proxy {
         Some(px)
     }
 
+    /// milliseconds to open a tcp connection, none when unreachable.
+    /// servers in private or local networks are never dialed
+    pub async fn ping(&self) -> Option<i64> {
+        let port = self.port.parse::<u16>().ok()?;
+        let host = tokio::net::lookup_host((self.server.as_str(), port));
+        let timeout = Config::PING_TIMEOUT;
+        let addr =
+            tokio::time::timeout(timeout, host).await.ok()?.ok()?.next()?;
+        let local = match addr.ip() {
+            IpAddr::V4(ip) => {
+                ip.is_private()
+                    || ip.is_loopback()
+                    || ip.is_link_local()
+                    || ip.is_unspecified()
+                    || ip.is_broadcast()
+            }
+            IpAddr::V6(ip) => ip.is_loopback() || ip.is_unspecified(),
+        };
+        if local {
+            return None;
+        }
+
+        let start = std::time::Instant::now();
+        let conn = tokio::net::TcpStream::connect(addr);
+        tokio::time::timeout(timeout, conn).await.ok()?.ok()?;
+        Some(start.elapsed().as_millis() as i64)
+    }
+
     pub async fn list(ctx: &Ctx, page: u32) -> Result<Vec<Proxy>, AppErr> {
         let offset = page * 32;
         Ok(sqlx::query_as!(
@@ -91,6 +130,32 @@ impl Proxy {
         Ok(res)
     }
 
+    /// links of every proxy, to find duplicates of an import
+    pub async fn links(ctx: &Ctx) -> Result<HashSet<String>, AppErr> {
+        let res = sqlx::query_as!(Self, "select * from proxies")
+            .fetch_all(&ctx.db)
+            .await?;
+        Ok(res.iter().map(|px| px.url()).collect())
+    }
+
+    pub async fn export(
+        ctx: &Ctx, filter: Filter,
+    ) -> Result<Vec<Self>, AppErr> {
+        let active = filter != Filter::All;
+        let min_score =
+            if filter == Filter::Good { vote::neutral() } else { 0.0 };
+        let res = sqlx::query_as!(
+            Self,
+            "select * from proxies where (NOT ? OR NOT disabled) AND score >= ?
+            order by id",
+            active,
+            min_score
+        )
+        .fetch_all(&ctx.db)
+        .await?;
+        Ok(res)
+    }
+
     pub async fn count(ctx: &Ctx) -> Result<(u32, u32), AppErr> {
         let count = sqlx::query!(
             "select
@@ -105,9 +170,16 @@ impl Proxy {
     }
 
     pub async fn add(&mut self, ctx: &Ctx) -> Result<(), AppErr> {
+        if let Some(geo) = geoip::lookup(&self.server) {
+            self.country = Some(geo.country);
+            self.asn = Some(geo.asn);
+        }
+        self.score = vote::neutral();
         let res = sqlx::query! {
-            "insert into proxies(server, port, secret) values(?,?,?)",
-            self.server, self.port, self.secret
+            "insert into proxies(server, port, secret, country, asn, score)
+            values(?,?,?,?,?,?)",
+            self.server, self.port, self.secret, self.country, self.asn,
+            self.score
         }
         .execute(&ctx.db)
         .await?;
@@ -121,16 +193,105 @@ impl Proxy {
             .await?)
     }
 
-    pub async fn get_good(ctx: &Ctx) -> Option<Self> {
+    pub async fn get_good(
+        ctx: &Ctx, karbar: &Karbar, now: i64,
+    ) -> Option<Self> {
+        Self::get_good_many(ctx, karbar, now, 1).await.pop()
+    }
+
+    /// up to `count` random proxies, the ones in the karbar region first.
+    /// skips the ones the karbar recently received or voted down
+    pub async fn get_good_many(
+        ctx: &Ctx, karbar: &Karbar, now: i64, count: i64,
+    ) -> Vec<Self> {
+        let fresh = now - Config::DELIVERY_FRESH;
         sqlx::query_as!(
             Proxy,
-            "select * from proxies
-            where NOT disabled order by random() limit 1"
+            "select * from proxies where NOT disabled
+            AND id NOT IN (
+                select proxy from proxy_deliveries
+                where karbar = ? AND at > ?
+            )
+            AND id NOT IN (
+                select proxy from proxy_votes where karbar = ? AND kind < 0
+            )
+            order by country = ? desc,
+            score + (abs(random()) % 1000) / 4000.0 desc limit ?",
+            karbar.tid,
+            fresh,
+            karbar.tid,
+            karbar.region,
+            count
         )
-        .fetch_optional(&ctx.db)
+        .fetch_all(&ctx.db)
         .await
-        .ok()
-        .flatten()
+        .unwrap_or_default()
+    }
+
+    pub async fn delivered(
+        ctx: &Ctx, karbar: i64, proxy: i64, now: i64,
+    ) -> Result<(), AppErr> {
+        sqlx::query!(
+            "insert into proxy_deliveries(karbar, proxy, at) values(?,?,?)
+            on conflict(karbar, proxy) do update set at = excluded.at",
+            karbar,
+            proxy,
+            now
+        )
+        .execute(&ctx.db)
+        .await?;
+        Ok(())
+    }
+
+    /// active proxies the karbar received since `since`
+    pub async fn history(
+        ctx: &Ctx, karbar: i64, since: i64,
+    ) -> Result<Vec<Self>, AppErr> {
+        let res = sqlx::query_as!(
+            Proxy,
+            "select * from proxies where NOT disabled AND id IN (
+                select proxy from proxy_deliveries where karbar = ? AND at > ?
+            ) order by id desc limit 30",
+            karbar,
+            since
+        )
+        .fetch_all(&ctx.db)
+        .await?;
+        Ok(res)
+    }
+
+    /// countries of the active proxies with their count
+    pub async fn regions(ctx: &Ctx) -> Result<Vec<(String, i64)>, AppErr> {
+        let res = sqlx::query!(
+            "select country as \"country!\", COUNT(1) as count from proxies
+            where NOT disabled AND country IS NOT NULL
+            group by country order by count desc"
+        )
+        .fetch_all(&ctx.db)
+        .await?;
+        Ok(res.into_iter().map(|r| (r.country, r.count)).collect())
+    }
+
+    /// look up the country of proxies without one, like the ones added
+    /// with an older geoip table
+    pub async fn geo_fill(ctx: &Ctx) -> Result<(), AppErr> {
+        let rows = sqlx::query!(
+            "select id, server from proxies where country IS NULL"
+        )
+        .fetch_all(&ctx.db)
+        .await?;
+        for r in rows {
+            let Some(geo) = geoip::lookup(&r.server) else { continue };
+            sqlx::query!(
+                "update proxies set country = ?, asn = ? where id = ?",
+                geo.country,
+                geo.asn,
+                r.id
+            )
+            .execute(&ctx.db)
+            .await?;
+        }
+        Ok(())
     }
 
     pub async fn del(ctx: &Ctx, id: i64) -> Result<(), AppErr> {
@@ -184,52 +345,138 @@ impl Proxy {
         .map(|v| if v.kind >= 0 { 1 } else { -1 })
     }
 
+    /// add or change the vote of a karbar who received the proxy within
+    /// `window` seconds, keeping the vote counts in sync
     pub async fn vote_add(
-        ctx: &Ctx, karbar: i64, proxy: i64, mut kind: i8,
-    ) -> Result<(), AppErr> {
-        if kind >= 0 {
-            kind = 1;
-        } else {
-            kind = -1;
+        ctx: &Ctx, karbar: i64, proxy: i64, kind: i8, weight: f64, now: i64,
+        window: i64,
+    ) -> Result<Cast, AppErr> {
+        let kind: i64 = if kind >= 0 { 1 } else { -1 };
+
+        let mut tx = ctx.db.begin().await?;
+        let got = sqlx::query!(
+            "select at from proxy_deliveries where karbar = ? AND proxy = ?",
+            karbar,
+            proxy
+        )
+        .fetch_optional(&mut *tx)
+        .await?;
+        let Some(got) = got else { return Ok(Cast::NotReceived) };
+        if got.at + window < now {
+            return Ok(Cast::Expired);
+        }
+
+        let old = sqlx::query!(
+            "select kind from proxy_votes where karbar = ? AND proxy = ?",
+            karbar,
+            proxy
+        )
+        .fetch_optional(&mut *tx)
+        .await?
+        .map(|v| if v.kind >= 0 { 1 } else { -1 });
+        if old == Some(kind) {
+            return Ok(Cast::Same);
         }
 
         sqlx::query!(
-            "insert into proxy_votes(kind, karbar, proxy) values(?,?,?)",
+            "insert into proxy_votes(kind, karbar, proxy, weight, at)
+            values(?,?,?,?,?) on conflict(karbar, proxy) do update set
+            kind = excluded.kind, weight = excluded.weight, at = excluded.at",
             kind,
             karbar,
+            proxy,
+            weight,
+            now
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        let up = (kind == 1) as i64 - (old == Some(1)) as i64;
+        let dn = (kind == -1) as i64 - (old == Some(-1)) as i64;
+        sqlx::query!(
+            "update proxies set
+            up_votes = up_votes + ?, dn_votes = dn_votes + ? where id = ?",
+            up,
+            dn,
             proxy
         )
-        .execute(&ctx.db)
+        .execute(&mut *tx)
         .await?;
+        tx.commit().await?;
 
-        let mut px = Self::get(ctx, proxy).await?;
+        Ok(if old.is_some() { Cast::Changed } else { Cast::Added })
+    }
 
-        if kind == 1 {
-            px.up_votes += 1;
-        } else {
-            px.dn_votes += 1;
-        }
+    /// items with many light down votes since `since`
+    pub async fn vote_clusters(
+        ctx: &Ctx, since: i64,
+    ) -> Result<Vec<Cluster>, AppErr> {
+        let res = sqlx::query_as!(
+            Cluster,
+            "select v.proxy as \"item!\", COUNT(1) as \"votes!: i64\",
+            MIN(k.created_at) as \"first!: i64\",
+            MAX(k.created_at) as \"last!: i64\",
+            GROUP_CONCAT(v.karbar) as \"voters!: String\"
+            from proxy_votes v join karbars k on k.tid = v.karbar
+            where v.kind < 0 AND v.at > ? AND v.weight < ?
+            group by v.proxy having COUNT(1) >= ?
+            order by COUNT(1) desc limit 20",
+            since,
+            Config::VOTE_SUSPICIOUS,
+            Config::VOTE_CLUSTER_MIN
+        )
+        .fetch_all(&ctx.db)
+        .await?;
+        Ok(res)
+    }
 
-        if px.up_votes + px.dn_votes > 25 {
-            let (_, dnp) = px.up_dn_pct();
-            if dnp > 60 {
-                return Self::del(ctx, px.id).await;
-                // px.disabled = true;
-            }
+    /// update the score from the decayed votes and disable it when bad.
+    /// returns the item if it was just disabled
+    pub async fn rescore(
+        ctx: &Ctx, id: i64, st: &Settings, now: i64,
+    ) -> Result<Option<Self>, AppErr> {
+        let votes = sqlx::query!(
+            "select kind, weight, at from proxy_votes where proxy = ?",
+            id
+        )
+        .fetch_all(&ctx.db)
+        .await?;
+
+        let mut tally = Tally::default();
+        for v in votes {
+            tally.add(v.kind, v.weight, v.at, now, st);
         }
 
+        let mut item = Self::get(ctx, id).await?;
+        let disable = !item.disabled && tally.is_bad(st);
+        item.score = tally.score();
+        item.disabled |= disable;
         sqlx::query!(
-            "update proxies set
-            up_votes = ?, dn_votes = ?, disabled = ? where id = ?",
-            px.up_votes,
-            px.dn_votes,
-            px.disabled,
-            px.id
+            "update proxies set score = ?, disabled = ? where id = ?",
+            item.score,
+            item.disabled,
+            id
         )
         .execute(&ctx.db)
         .await?;
 
-        Ok(())
+        Ok(disable.then_some(item))
+    }
+
+    /// rescore the active items, returns the ones that got disabled
+    pub async fn rescore_all(
+        ctx: &Ctx, st: &Settings, now: i64,
+    ) -> Result<Vec<Self>, AppErr> {
+        let ids = sqlx::query!("select id from proxies where NOT disabled")
+            .fetch_all(&ctx.db)
+            .await?;
+        let mut out = Vec::new();
+        for r in ids {
+            if let Some(item) = Self::rescore(ctx, r.id, st, now).await? {
+                out.push(item);
+            }
+        }
+        Ok(out)
     }
 }
 
@@ -238,7 +485,8 @@ impl Display for Proxy {
         let (upp, dnp) = self.up_dn_pct();
         write!(
             f,
-            r#"<a href="{}">{}:{}</a> {upp}% ({}) 👍 | {dnp}% ({}) 👎 ({}) {}"#,
+            r#"{}<a href="{}">{}:{}</a> {upp}% ({}) 👍 | {dnp}% ({}) 👎 ({}) {}"#,
+            self.country.as_deref().map(geoip::flag).unwrap_or_default(),
             escape(&self.url()),
             self.server,
             self.port,
@@ -0,0 +1,440 @@
+//! storage backends of karbars, settings, proxies, v2rays and flyers.
+//! both backends run the same sql, only the pools differ. the bot itself
+//! still runs on the sqlite pool of [`crate::Ctx`] and refuses postgres,
+//! its queries join tables like the deliveries and votes that postgres
+//! does not have yet. the one shot sqlite to postgres copy goes through
+//! [`Repo`]
+
+use super::{Flyer, Karbar, Proxy, Settings, V2ray};
+use crate::config::Config;
+use crate::error::{AppErr, Worm};
+use sqlx::{PgPool, SqlitePool, migrate::Migrator};
+
+/// the `migrations_pg` directory, embedded at build time
+static PG_MIGRATOR: Migrator = sqlx::migrate!("./migrations_pg");
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+/// `db.backend` of the config
+pub enum Backend {
+    #[default]
+    Sqlite,
+    Postgres,
+}
+
+pub trait Repo {
+    async fn karbar(&self, tid: i64) -> Result<Option<Karbar>, AppErr>;
+    async fn karbars(&self) -> Result<Vec<Karbar>, AppErr>;
+    /// insert or replace by tid
+    async fn karbar_put(&self, karbar: &Karbar) -> Result<(), AppErr>;
+    /// the defaults when there is no settings row yet
+    async fn settings(&self) -> Result<Settings, AppErr>;
+    async fn settings_put(&self, settings: &Settings) -> Result<(), AppErr>;
+    async fn proxies(&self) -> Result<Vec<Proxy>, AppErr>;
+    /// insert or replace by id
+    async fn proxy_put(&self, proxy: &Proxy) -> Result<(), AppErr>;
+    async fn v2rays(&self) -> Result<Vec<V2ray>, AppErr>;
+    /// insert or replace by id
+    async fn v2ray_put(&self, v2ray: &V2ray) -> Result<(), AppErr>;
+    async fn flyers(&self) -> Result<Vec<Flyer>, AppErr>;
+    /// insert or replace by id
+    async fn flyer_put(&self, flyer: &Flyer) -> Result<(), AppErr>;
+    /// move the id generators past the ids written by the puts
+    async fn sync_ids(&self) -> Result<(), AppErr> {
+        Ok(())
+    }
+}
+
+/// `insert .. on conflict do update` of `cols`, the first is the key.
+/// `$n` parameters work in sqlite and postgres alike
+fn upsert(table: &str, cols: &[&str]) -> String {
+    let args = (1..=cols.len()).map(|i| format!("${i}")).collect::<Vec<_>>();
+    let set = cols[1..]
+        .iter()
+        .map(|c| format!("{c} = excluded.{c}"))
+        .collect::<Vec<_>>();
+    format!(
+        "insert into {table}({}) values({}) on conflict({}) do update set {}",
+        cols.join(", "),
+        args.join(", "),
+        cols[0],
+        set.join(", ")
+    )
+}
+
+/// upsert the fields of `$v`, named like their columns
+macro_rules! put {
+    ($db:expr, $table:literal, $key:ident = $kv:expr, $v:ident;
+     $($f:ident),* $(,)?) => {{
+        let sql = upsert($table, &[stringify!($key), $(stringify!($f)),*]);
+        sqlx::query(&sql).bind($kv)$(.bind(&$v.$f))*.execute($db).await?;
+        Ok(())
+    }};
+}
+
+macro_rules! sql_repo {
+    ($name:ident, $($extra:item)*) => {
+        impl Repo for $name {
+            async fn karbar(&self, tid: i64) -> Result<Option<Karbar>, AppErr> {
+                Ok(sqlx::query_as("select * from karbars where tid = $1")
+                    .bind(tid)
+                    .fetch_optional(&self.0)
+                    .await?)
+            }
+
+            async fn karbars(&self) -> Result<Vec<Karbar>, AppErr> {
+                Ok(sqlx::query_as("select * from karbars order by tid")
+                    .fetch_all(&self.0)
+                    .await?)
+            }
+
+            async fn karbar_put(&self, k: &Karbar) -> Result<(), AppErr> {
+                put!(
+                    &self.0, "karbars", tid = k.tid, k;
+                    fullname, username, banned, created_at, updated_at,
+                    points, last_free_point_at, invite_code, blocked,
+                    language_code, lang, proxy_stack, proxy_stack_at,
+                    v2ray_stack, v2ray_stack_at, vip_stack, vip_stack_at,
+                    skip_confirm, streak, streak_reminded, verified,
+                    invited_by, captcha_fails, captcha_locked_until,
+                    captcha_claims, inline_shares, inline_window_at, region
+                )
+            }
+
+            async fn settings(&self) -> Result<Settings, AppErr> {
+                let st = sqlx::query_as("select * from settings where id = 1")
+                    .fetch_optional(&self.0)
+                    .await?;
+                Ok(st.unwrap_or_default())
+            }
+
+            async fn settings_put(&self, st: &Settings) -> Result<(), AppErr> {
+                put!(
+                    &self.0, "settings", id = 1i64, st;
+                    invite_points, free_points, proxy_cost, v2ray_cost,
+                    vip_cost, vip_views, vip_max_views, vip_msg, donate_msg,
+                    ch_last_sent, star_point_price, free_point_delay,
+                    total_stars, fj_fail_closed, ad_star_price, proxy_surge,
+                    v2ray_surge, vip_surge, surge_decay, streak_bonus,
+                    streak_window, captcha_enabled, captcha_every,
+                    captcha_tries, captcha_lockout, bundle_size,
+                    bundle_discount, vote_half_life, vote_min_weight,
+                    vote_disable_pct, vote_window, submit_reward,
+                    submit_vote_reward, v2ray_last_auto_update
+                )
+            }
+
+            async fn proxies(&self) -> Result<Vec<Proxy>, AppErr> {
+                Ok(sqlx::query_as("select * from proxies order by id")
+                    .fetch_all(&self.0)
+                    .await?)
+            }
+
+            async fn proxy_put(&self, px: &Proxy) -> Result<(), AppErr> {
+                put!(
+                    &self.0, "proxies", id = px.id, px;
+                    server, port, secret, up_votes, dn_votes, disabled,
+                    country, asn, score
+                )
+            }
+
+            async fn v2rays(&self) -> Result<Vec<V2ray>, AppErr> {
+                Ok(sqlx::query_as("select * from v2rays order by id")
+                    .fetch_all(&self.0)
+                    .await?)
+            }
+
+            async fn v2ray_put(&self, v2: &V2ray) -> Result<(), AppErr> {
+                put!(
+                    &self.0, "v2rays", id = v2.id, v2;
+                    label, link, up_votes, dn_votes, disabled, score
+                )
+            }
+
+            async fn flyers(&self) -> Result<Vec<Flyer>, AppErr> {
+                Ok(sqlx::query_as("select * from flyers order by id")
+                    .fetch_all(&self.0)
+                    .await?)
+            }
+
+            async fn flyer_put(&self, f: &Flyer) -> Result<(), AppErr> {
+                put!(
+                    &self.0, "flyers", id = f.id, f;
+                    label, link, mid, views, max_views, disabled, starts_at,
+                    ends_at, daily_max_views, day, day_views, user_cap,
+                    weight, audience, lang, clicks, owner, status, reason
+                )
+            }
+
+            $($extra)*
+        }
+    };
+}
+
+/// the main database of the bot
+pub struct SqliteRepo(pub SqlitePool);
+
+sql_repo!(SqliteRepo,);
+
+pub struct PgRepo(pub PgPool);
+
+impl PgRepo {
+    /// connect and apply the pending postgres migrations
+    pub async fn connect(url: &str) -> Result<Self, AppErr> {
+        let db = PgPool::connect(url).await?;
+        PG_MIGRATOR.run(&db).await?;
+        Ok(Self(db))
+    }
+}
+
+sql_repo!(
+    PgRepo,
+    async fn sync_ids(&self) -> Result<(), AppErr> {
+        for table in ["proxies", "v2rays", "flyers"] {
+            let sql = format!(
+                "select setval(pg_get_serial_sequence('{table}', 'id'),
+                COALESCE((select MAX(id) from {table}), 0) + 1, false)"
+            );
+            sqlx::query(&sql).execute(&self.0).await?;
+        }
+        Ok(())
+    }
+);
+
+/// the backend chosen in the config
+pub enum Store {
+    Sqlite(SqliteRepo),
+    Postgres(PgRepo),
+}
+
+/// the repo of `db.backend`, `db` is the main sqlite database
+pub async fn open(db: &SqlitePool) -> Result<Store, AppErr> {
+    let conf = Config::get();
+    match conf.db_backend {
+        Backend::Sqlite => Ok(Store::Sqlite(SqliteRepo(db.clone()))),
+        Backend::Postgres => {
+            let url = postgres_url()?;
+            Ok(Store::Postgres(PgRepo::connect(url).await?))
+        }
+    }
+}
+
+/// `db.postgres` of the config
+pub fn postgres_url() -> Result<&'static str, AppErr> {
+    Config::get().postgres_url.as_deref().ok_or(AppErr {
+        worm: Worm::Unknown,
+        debug: String::from("db.postgres is not set in the config"),
+    })
+}
+
+macro_rules! each {
+    ($store:expr, $r:ident => $e:expr) => {
+        match $store {
+            Store::Sqlite($r) => $e,
+            Store::Postgres($r) => $e,
+        }
+    };
+}
+
+impl Repo for Store {
+    async fn karbar(&self, tid: i64) -> Result<Option<Karbar>, AppErr> {
+        each!(self, r => r.karbar(tid).await)
+    }
+    async fn karbars(&self) -> Result<Vec<Karbar>, AppErr> {
+        each!(self, r => r.karbars().await)
+    }
+    async fn karbar_put(&self, karbar: &Karbar) -> Result<(), AppErr> {
+        each!(self, r => r.karbar_put(karbar).await)
+    }
+    async fn settings(&self) -> Result<Settings, AppErr> {
+        each!(self, r => r.settings().await)
+    }
+    async fn settings_put(&self, settings: &Settings) -> Result<(), AppErr> {
+        each!(self, r => r.settings_put(settings).await)
+    }
+    async fn proxies(&self) -> Result<Vec<Proxy>, AppErr> {
+        each!(self, r => r.proxies().await)
+    }
+    async fn proxy_put(&self, proxy: &Proxy) -> Result<(), AppErr> {
+        each!(self, r => r.proxy_put(proxy).await)
+    }
+    async fn v2rays(&self) -> Result<Vec<V2ray>, AppErr> {
+        each!(self, r => r.v2rays().await)
+    }
+    async fn v2ray_put(&self, v2ray: &V2ray) -> Result<(), AppErr> {
+        each!(self, r => r.v2ray_put(v2ray).await)
+    }
+    async fn flyers(&self) -> Result<Vec<Flyer>, AppErr> {
+        each!(self, r => r.flyers().await)
+    }
+    async fn flyer_put(&self, flyer: &Flyer) -> Result<(), AppErr> {
+        each!(self, r => r.flyer_put(flyer).await)
+    }
+    async fn sync_ids(&self) -> Result<(), AppErr> {
+        each!(self, r => r.sync_ids().await)
+    }
+}
+
+#[derive(Debug, Default)]
+/// rows written by [`copy`]
+pub struct Copied {
+    pub karbars: usize,
+    pub proxies: usize,
+    pub v2rays: usize,
+    pub flyers: usize,
+}
+
+/// write every row of `from` into `to`, keeping the ids. running it again
+/// overwrites the copied rows
+pub async fn copy(from: &impl Repo, to: &impl Repo) -> Result<Copied, AppErr> {
+    let mut n = Copied::default();
+    to.settings_put(&from.settings().await?).await?;
+    // flyers point to their owner karbar
+    for k in from.karbars().await? {
+        to.karbar_put(&k).await?;
+        n.karbars += 1;
+    }
+    for px in from.proxies().await? {
+        to.proxy_put(&px).await?;
+        n.proxies += 1;
+    }
+    for v2 in from.v2rays().await? {
+        to.v2ray_put(&v2).await?;
+        n.v2rays += 1;
+    }
+    for f in from.flyers().await? {
+        to.flyer_put(&f).await?;
+        n.flyers += 1;
+    }
+    to.sync_ids().await?;
+    Ok(n)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    async fn sqlite() -> SqliteRepo {
+        let db = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        crate::db::migrate(&db).await.unwrap();
+        SqliteRepo(db)
+    }
+
+    /// rows written with plain sql, so the reads are checked as well
+    async fn seed(repo: &SqliteRepo) {
+        let sql = [
+            "update settings set bundle_size = 4, proxy_surge = '0,1',
+            vip_msg = 7, fj_fail_closed = true where id = 1",
+            "insert into karbars(tid, fullname, username, created_at,
+            updated_at, points, invite_code, lang, invited_by, verified)
+            values (1, 'a', 'ua', 10, 20, 30, 'c1', 'fa', null, true),
+            (2, 'b', null, 11, 21, 31, 'c2', null, 1, false)",
+            "insert into proxies(id, server, port, secret, up_votes, score,
+            country, asn) values (3, 's1', '443', 'ee', 5, 0.75, 'DE', 24940),
+            (8, 's2', '80', 'dd', 0, 0.5, null, null)",
+            "insert into v2rays(id, label, link, dn_votes, disabled)
+            values (4, 'v', 'vless://x@h:1', 2, true)",
+            "insert into flyers(id, label, link, mid, max_views, owner,
+            status, reason) values (5, 'f', 'https://t.me/x', 9, 1000, 1,
+            2, 'no')",
+        ];
+        for q in sql {
+            sqlx::query(q).execute(&repo.0).await.unwrap();
+        }
+    }
+
+    async fn dump(repo: &impl Repo) -> String {
+        format!(
+            "{:?}\n{:?}\n{:?}\n{:?}\n{:?}",
+            repo.settings().await.unwrap(),
+            repo.karbars().await.unwrap(),
+            repo.proxies().await.unwrap(),
+            repo.v2rays().await.unwrap(),
+            repo.flyers().await.unwrap(),
+        )
+    }
+
+    #[tokio::test]
+    async fn round_trip() {
+        let repo = sqlite().await;
+        seed(&repo).await;
+
+        let mut k = repo.karbar(2).await.unwrap().unwrap();
+        assert_eq!(k.invited_by, Some(1));
+        assert!(repo.karbar(3).await.unwrap().is_none());
+        k.points = 99;
+        k.region = Some(String::from("DE"));
+        repo.karbar_put(&k).await.unwrap();
+        let k = repo.karbar(2).await.unwrap().unwrap();
+        assert_eq!((k.points, k.region.as_deref()), (99, Some("DE")));
+        assert_eq!(repo.karbars().await.unwrap().len(), 2);
+
+        let mut st = repo.settings().await.unwrap();
+        assert_eq!((st.bundle_size, st.vip_msg), (4, Some(7)));
+        assert!(st.fj_fail_closed);
+        st.bundle_size = 5;
+        repo.settings_put(&st).await.unwrap();
+        assert_eq!(repo.settings().await.unwrap().bundle_size, 5);
+
+        let mut px = repo.proxies().await.unwrap().remove(0);
+        assert_eq!((px.id, px.asn, px.score), (3, Some(24940), 0.75));
+        px.disabled = true;
+        repo.proxy_put(&px).await.unwrap();
+        assert!(repo.proxies().await.unwrap()[0].disabled);
+
+        let mut v2 = repo.v2rays().await.unwrap().remove(0);
+        v2.id = 6;
+        v2.link = String::from("trojan://p@h:2");
+        repo.v2ray_put(&v2).await.unwrap();
+        assert_eq!(repo.v2rays().await.unwrap().len(), 2);
+
+        let mut f = repo.flyers().await.unwrap().remove(0);
+        assert_eq!((f.owner, f.reason.as_deref()), (Some(1), Some("no")));
+        f.views = 10;
+        repo.flyer_put(&f).await.unwrap();
+        assert_eq!(repo.flyers().await.unwrap()[0].views, 10);
+    }
+
+    #[tokio::test]
+    async fn copy_to_sqlite() {
+        let (from, to) = (sqlite().await, sqlite().await);
+        seed(&from).await;
+        let n = copy(&from, &to).await.unwrap();
+        assert_eq!((n.karbars, n.proxies, n.v2rays, n.flyers), (2, 2, 1, 1));
+        assert_eq!(dump(&from).await, dump(&to).await);
+        // a second run only overwrites
+        copy(&from, &to).await.unwrap();
+        assert_eq!(dump(&from).await, dump(&to).await);
+    }
+
+    #[tokio::test]
+    #[ignore = "needs TONEL_TEST_PG, the url of a scratch postgres database"]
+    async fn copy_to_postgres() {
+        let url = std::env::var("TONEL_TEST_PG").expect("TONEL_TEST_PG");
+        let to = PgRepo::connect(&url).await.unwrap();
+        sqlx::query("truncate flyers, proxies, v2rays, karbars, settings")
+            .execute(&to.0)
+            .await
+            .unwrap();
+
+        let from = sqlite().await;
+        seed(&from).await;
+        copy(&from, &to).await.unwrap();
+        assert_eq!(dump(&from).await, dump(&to).await);
+
+        // new rows get ids after the copied ones
+        let id: i64 = sqlx::query_scalar(
+            "insert into proxies(server, port, secret)
+            values('s3', '1', 'ff') returning id",
+        )
+        .fetch_one(&to.0)
+        .await
+        .unwrap();
+        assert_eq!(id, 9);
+    }
+}
@@ -0,0 +1,170 @@
+use crate::error::AppErr;
+use sqlx::{Row, SqlitePool, migrate::Migrator};
+
+/// the migrations directory, embedded at build time
+static MIGRATOR: Migrator = sqlx::migrate!();
+
+/// last applied migration, none for a database without migrations
+pub async fn schema_version(db: &SqlitePool) -> Option<i64> {
+    sqlx::query("select MAX(version) from _sqlx_migrations where success")
+        .fetch_one(db)
+        .await
+        .ok()?
+        .try_get::<Option<i64>, _>(0)
+        .ok()
+        .flatten()
+}
+
+/// problems found by `PRAGMA integrity_check`, empty when healthy
+pub async fn integrity_check(db: &SqlitePool) -> Result<Vec<String>, AppErr> {
+    let rows: Vec<String> =
+        sqlx::query_scalar("PRAGMA integrity_check").fetch_all(db).await?;
+    Ok(rows.into_iter().filter(|r| r != "ok").collect())
+}
+
+/// rows pointing to a missing parent, these were written while the
+/// foreign keys were off
+pub async fn foreign_key_check(db: &SqlitePool) -> Result<Vec<String>, AppErr> {
+    let rows = sqlx::query("PRAGMA foreign_key_check").fetch_all(db).await?;
+    let mut all = Vec::with_capacity(rows.len());
+    for r in rows {
+        let table: String = r.try_get(0)?;
+        let rowid: Option<i64> = r.try_get(1)?;
+        let parent: String = r.try_get(2)?;
+        all.push(format!("{table} {} -> {parent}", rowid.unwrap_or_default()));
+    }
+    Ok(all)
+}
+
+/// newest migration of this build
+fn latest_version() -> i64 {
+    MIGRATOR.iter().map(|m| m.version).max().unwrap_or_default()
+}
+
+/// migrations adding a column that some databases got by hand before the
+/// migration existed. there they are marked as applied instead of failing
+const HAND_ADDED: [(i64, &str, &str); 1] =
+    [(21, "settings", "v2ray_last_auto_update")];
+
+async fn skip_hand_added(db: &SqlitePool) -> Result<(), AppErr> {
+    for (version, table, column) in HAND_ADDED {
+        let Some(m) = MIGRATOR.iter().find(|m| m.version == version) else {
+            continue;
+        };
+        let has: i64 = sqlx::query_scalar(
+            "select COUNT(1) from pragma_table_info(?) where name = ?",
+        )
+        .bind(table)
+        .bind(column)
+        .fetch_one(db)
+        .await?;
+        if has == 0 {
+            continue;
+        }
+
+        let res = sqlx::query(
+            "insert or ignore into _sqlx_migrations(version, description,
+            success, checksum, execution_time) values(?, ?, TRUE, ?, -1)",
+        )
+        .bind(m.version)
+        .bind(&*m.description)
+        .bind(&*m.checksum)
+        .execute(db)
+        .await?;
+        if res.rows_affected() > 0 {
+            log::info!("{table}.{column} exists, migration {version} skipped");
+        }
+    }
+    Ok(())
+}
+
+/// apply the pending migrations and return the schema version.
+/// a database migrated by a newer build is refused
+pub async fn migrate(db: &SqlitePool) -> Result<i64, AppErr> {
+    let latest = latest_version();
+    let current = schema_version(db).await.unwrap_or_default();
+    if current > latest {
+        return Err(AppErr {
+            worm: crate::error::Worm::Unknown,
+            debug: format!(
+                "database schema {current} is newer than this build {latest}"
+            ),
+        });
+    }
+
+    if current > 0 {
+        skip_hand_added(db).await?;
+    }
+    MIGRATOR.run(db).await?;
+    if current < latest {
+        log::info!("migrated the database from {current} to {latest}");
+    }
+    Ok(latest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    async fn memory() -> SqlitePool {
+        SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap()
+    }
+
+    /// a database of the old `cargo sqlx db setup` flow, migrated up to
+    /// 020 and without 021
+    async fn old_db(hand_added: bool) -> SqlitePool {
+        let db = memory().await;
+        let old = Migrator {
+            migrations: MIGRATOR
+                .iter()
+                .filter(|m| m.version <= 20)
+                .cloned()
+                .collect::<Vec<_>>()
+                .into(),
+            ..Migrator::DEFAULT
+        };
+        old.run(&db).await.unwrap();
+        if hand_added {
+            sqlx::query(
+                "alter table settings add column
+                v2ray_last_auto_update integer not null default 0",
+            )
+            .execute(&db)
+            .await
+            .unwrap();
+        }
+        db
+    }
+
+    async fn has_column(db: &SqlitePool) -> bool {
+        sqlx::query("select v2ray_last_auto_update from settings")
+            .fetch_all(db)
+            .await
+            .is_ok()
+    }
+
+    #[tokio::test]
+    async fn fresh() {
+        let db = memory().await;
+        assert_eq!(migrate(&db).await.unwrap(), latest_version());
+        assert!(has_column(&db).await);
+    }
+
+    #[tokio::test]
+    async fn upgrade() {
+        for hand_added in [false, true] {
+            let db = old_db(hand_added).await;
+            assert_eq!(schema_version(&db).await, Some(20));
+            assert_eq!(migrate(&db).await.unwrap(), latest_version());
+            assert_eq!(schema_version(&db).await, Some(latest_version()));
+            assert!(has_column(&db).await);
+            // nothing left to apply the second time
+            assert_eq!(migrate(&db).await.unwrap(), latest_version());
+        }
+    }
+}
@@ -1,7 +1,7 @@
 use crate::error::AppErr;
 use sqlx::SqlitePool;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, sqlx::FromRow)]
 /// Tonel Bot Settings
 pub struct Settings {
     #[allow(dead_code)]
@@ -20,6 +20,43 @@ pub struct Settings {
     pub ch_last_sent: i64,
     pub total_stars: i64,
     pub v2ray_last_auto_update: i64,
+    /// treat force join api errors as not joined
+    pub fj_fail_closed: bool,
+    /// stars for every 1000 views of an advertiser flyer
+    pub ad_star_price: i64,
+    /// surge curves, see [`crate::surge::parse_curve`]
+    pub proxy_surge: String,
+    pub v2ray_surge: String,
+    pub vip_surge: String,
+    /// seconds for a surge stack to drop by one
+    pub surge_decay: i64,
+    /// free point streak bonus, a curve like [`Settings::proxy_surge`]
+    pub streak_bonus: String,
+    /// seconds after the next free point claim before a streak is lost
+    pub streak_window: i64,
+    pub captcha_enabled: bool,
+    /// free point claims between two captchas, 0 only checks new karbars
+    pub captcha_every: i64,
+    /// wrong answers before a lockout
+    pub captcha_tries: i64,
+    /// seconds of a captcha lockout
+    pub captcha_lockout: i64,
+    /// proxies in a bundle
+    pub bundle_size: i64,
+    /// percent off the price of the proxies in a bundle
+    pub bundle_discount: i64,
+    /// seconds for the weight of a vote to halve
+    pub vote_half_life: i64,
+    /// decayed vote weight needed before auto disable
+    pub vote_min_weight: i64,
+    /// auto disable when the down vote lower bound reaches this percent
+    pub vote_disable_pct: i64,
+    /// seconds after a delivery that votes are accepted
+    pub vote_window: i64,
+    /// points for an approved proxy submission
+    pub submit_reward: i64,
+    /// points when a submitted proxy gets enough good votes
+    pub submit_vote_reward: i64,
 }
 
 impl Default for Settings {
@@ -40,11 +77,34 @@ impl Default for Settings {
             ch_last_sent: 0,
             total_stars: 0,
             v2ray_last_auto_update: 0,
+            fj_fail_closed: false,
+            ad_star_price: 50,
+            proxy_surge: String::from(Self::SURGE),
+            v2ray_surge: String::from(Self::SURGE),
+            vip_surge: String::from(Self::SURGE),
+            surge_decay: 6 * 3600,
+            streak_bonus: String::from(Self::STREAK),
+            streak_window: 86400,
+            captcha_enabled: true,
+            captcha_every: 5,
+            captcha_tries: 3,
+            captcha_lockout: 3600,
+            bundle_size: 3,
+            bundle_discount: 20,
+            vote_half_life: 3 * 86400,
+            vote_min_weight: 10,
+            vote_disable_pct: 60,
+            vote_window: 7 * 86400,
+            submit_reward: 50,
+            submit_vote_reward: 100,
         }
     }
 }
 
 impl Settings {
+    pub const SURGE: &str = "0,0.1,3,10,30,60,110,170,300,1000";
+    pub const STREAK: &str = "0,10,20,30,50,75,100";
+
     pub async fn get(pool: &SqlitePool) -> Self {
         let Ok(Some(settings)) = sqlx::query_as! {
             Settings, "select * from settings where id = 1"
@@ -94,7 +154,27 @@ impl Settings {
             free_point_delay = ?,
             total_stars = ?,
             star_point_price = ?,
-            v2ray_last_auto_update = ?
+            v2ray_last_auto_update = ?,
+            fj_fail_closed = ?,
+            ad_star_price = ?,
+            proxy_surge = ?,
+            v2ray_surge = ?,
+            vip_surge = ?,
+            surge_decay = ?,
+            streak_bonus = ?,
+            streak_window = ?,
+            captcha_enabled = ?,
+            captcha_every = ?,
+            captcha_tries = ?,
+            captcha_lockout = ?,
+            bundle_size = ?,
+            bundle_discount = ?,
+            vote_half_life = ?,
+            vote_min_weight = ?,
+            vote_disable_pct = ?,
+            vote_window = ?,
+            submit_reward = ?,
+            submit_vote_reward = ?
             where id = 1
         ",
             self.invite_points,
@@ -110,7 +190,27 @@ impl Settings {
             self.free_point_delay,
             self.total_stars,
             self.star_point_price,
-            self.v2ray_last_auto_update
+            self.v2ray_last_auto_update,
+            self.fj_fail_closed,
+            self.ad_star_price,
+            self.proxy_surge,
+            self.v2ray_surge,
+            self.vip_surge,
+            self.surge_decay,
+            self.streak_bonus,
+            self.streak_window,
+            self.captcha_enabled,
+            self.captcha_every,
+            self.captcha_tries,
+            self.captcha_lockout,
+            self.bundle_size,
+            self.bundle_discount,
+            self.vote_half_life,
+            self.vote_min_weight,
+            self.vote_disable_pct,
+            self.vote_window,
+            self.submit_reward,
+            self.submit_vote_reward
         }
         .execute(pool)
         .await?;
@@ -0,0 +1,219 @@
+use crate::{Ctx, error::AppErr, vote};
+
+use super::Proxy;
+
+#[derive(Debug, sqlx::FromRow)]
+/// a proxy sent by a karbar, waiting for review
+pub struct Submission {
+    pub id: i64,
+    pub karbar: i64,
+    pub server: String,
+    pub port: String,
+    pub secret: String,
+    /// see [`Submission::STATUS_PENDING`]
+    pub status: i64,
+    /// milliseconds to connect when submitted
+    pub ping: i64,
+    /// the proxy added on approval
+    pub proxy: Option<i64>,
+    pub vote_rewarded: bool,
+    pub created_at: i64,
+}
+
+#[derive(Debug, Default)]
+/// past submissions of a karbar
+pub struct SubmitStats {
+    pub pending: i64,
+    pub approved: i64,
+    pub rejected: i64,
+    /// approved ones that got enough good votes
+    pub good: i64,
+}
+
+impl SubmitStats {
+    /// share of reviewed submissions that were approved, 0.5 for none
+    pub fn trust(&self) -> f64 {
+        let total = self.approved + self.rejected;
+        (self.approved as f64 + 1.0) / (total as f64 + 2.0)
+    }
+}
+
+impl Submission {
+    pub const STATUS_PENDING: i64 = 0;
+    pub const STATUS_APPROVED: i64 = 1;
+    pub const STATUS_REJECTED: i64 = 2;
+
+    pub fn new(karbar: i64, px: &Proxy, ping: i64, now: i64) -> Self {
+        Self {
+            id: 0,
+            karbar,
+            server: px.server.clone(),
+            port: px.port.clone(),
+            secret: px.secret.clone(),
+            status: Self::STATUS_PENDING,
+            ping,
+            proxy: None,
+            vote_rewarded: false,
+            created_at: now,
+        }
+    }
+
+    pub fn to_proxy(&self) -> Proxy {
+        Proxy {
+            id: 0,
+            port: self.port.clone(),
+            server: self.server.clone(),
+            secret: self.secret.clone(),
+            up_votes: 0,
+            dn_votes: 0,
+            disabled: false,
+            score: vote::neutral(),
+            country: None,
+            asn: None,
+        }
+    }
+
+    pub async fn add(&mut self, ctx: &Ctx) -> Result<(), AppErr> {
+        let res = sqlx::query!(
+            "insert into submissions(karbar, server, port, secret, ping,
+            created_at) values(?,?,?,?,?,?)",
+            self.karbar,
+            self.server,
+            self.port,
+            self.secret,
+            self.ping,
+            self.created_at
+        )
+        .execute(&ctx.db)
+        .await?;
+        self.id = res.last_insert_rowid();
+        Ok(())
+    }
+
+    pub async fn get(ctx: &Ctx, id: i64) -> Result<Self, AppErr> {
+        let res =
+            sqlx::query_as!(Self, "select * from submissions where id = ?", id)
+                .fetch_one(&ctx.db)
+                .await?;
+        Ok(res)
+    }
+
+    pub async fn set(&self, ctx: &Ctx) -> Result<(), AppErr> {
+        sqlx::query!(
+            "update submissions set status = ?, proxy = ?, vote_rewarded = ?
+            where id = ?",
+            self.status,
+            self.proxy,
+            self.vote_rewarded,
+            self.id
+        )
+        .execute(&ctx.db)
+        .await?;
+        Ok(())
+    }
+
+    /// move a pending submission to `status`, false when it was
+    /// already decided
+    pub async fn decide(
+        ctx: &Ctx, id: i64, status: i64,
+    ) -> Result<bool, AppErr> {
+        let res = sqlx::query!(
+            "update submissions set status = ? where id = ? AND status = ?",
+            status,
+            id,
+            Self::STATUS_PENDING
+        )
+        .execute(&ctx.db)
+        .await?;
+        Ok(res.rows_affected() > 0)
+    }
+
+    /// submissions waiting for review
+    pub async fn queue(ctx: &Ctx) -> Result<Vec<Self>, AppErr> {
+        let res = sqlx::query_as!(
+            Self,
+            "select * from submissions where status = ? order by id limit 10",
+            Self::STATUS_PENDING
+        )
+        .fetch_all(&ctx.db)
+        .await?;
+        Ok(res)
+    }
+
+    /// the proxy is already in the bot or waiting for review
+    pub async fn exists(ctx: &Ctx, px: &Proxy) -> Result<bool, AppErr> {
+        let r = sqlx::query!(
+            "select
+            (select COUNT(1) from proxies
+                where server = ? AND port = ? AND secret = ?) +
+            (select COUNT(1) from submissions
+                where status = ? AND server = ? AND port = ? AND secret = ?)
+            as \"count!: i64\"",
+            px.server,
+            px.port,
+            px.secret,
+            Self::STATUS_PENDING,
+            px.server,
+            px.port,
+            px.secret
+        )
+        .fetch_one(&ctx.db)
+        .await?;
+        Ok(r.count > 0)
+    }
+
+    pub async fn stats(ctx: &Ctx, karbar: i64) -> Result<SubmitStats, AppErr> {
+        let r = sqlx::query!(
+            "select
+            SUM(status = ?) as \"pending: i64\",
+            SUM(status = ?) as \"approved: i64\",
+            SUM(status = ?) as \"rejected: i64\",
+            SUM(vote_rewarded) as \"good: i64\"
+            from submissions where karbar = ?",
+            Self::STATUS_PENDING,
+            Self::STATUS_APPROVED,
+            Self::STATUS_REJECTED,
+            karbar
+        )
+        .fetch_one(&ctx.db)
+        .await?;
+
+        Ok(SubmitStats {
+            pending: r.pending.unwrap_or_default(),
+            approved: r.approved.unwrap_or_default(),
+            rejected: r.rejected.unwrap_or_default(),
+            good: r.good.unwrap_or_default(),
+        })
+    }
+
+    /// approved submissions with no reward yet whose proxy got at least
+    /// `good_votes` weight of up votes and a good weighted score. votes of
+    /// the submitter are left out
+    pub async fn good_unrewarded(
+        ctx: &Ctx, good_votes: i64,
+    ) -> Result<Vec<Self>, AppErr> {
+        let rows = sqlx::query!(
+            "select s.id as \"id!: i64\",
+            SUM(IIF(v.kind > 0, v.weight, 0)) as \"up!: f64\",
+            SUM(v.weight) as \"total!: f64\"
+            from submissions s join proxies p on p.id = s.proxy
+            join proxy_votes v on v.proxy = p.id AND v.karbar != s.karbar
+            where s.status = ? AND NOT s.vote_rewarded AND NOT p.disabled
+            group by s.id",
+            Self::STATUS_APPROVED
+        )
+        .fetch_all(&ctx.db)
+        .await?;
+
+        let mut res = Vec::new();
+        for r in rows {
+            let good = r.up >= good_votes as f64
+                && vote::Tally { up: r.up, total: r.total }.score()
+                    >= vote::neutral();
+            if good {
+                res.push(Self::get(ctx, r.id).await?);
+            }
+        }
+        Ok(res)
+    }
+}
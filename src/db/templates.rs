@@ -0,0 +1,135 @@
+use crate::{
+    Ctx,
+    error::AppErr,
+    i18n::{Lang, tr},
+};
+
+#[derive(Debug, sqlx::FromRow)]
+/// admin edited text that replaces a built-in catalog text
+pub struct Template {
+    /// catalog key, one of [`Template::NAMES`]
+    pub name: String,
+    pub lang: String,
+    /// telegram html with `{points}`, `{cost}`, `{link}` and `{username}`
+    pub body: String,
+    pub updated_at: i64,
+}
+
+impl Template {
+    pub const NAMES: [&str; 6] = [
+        "welcome",
+        "menu",
+        "no_points_vip",
+        "no_points_proxy",
+        "no_points_v2ray",
+        "v2ray_config",
+    ];
+
+    pub fn name(idx: u8) -> Option<&'static str> {
+        Self::NAMES.get(idx as usize).copied()
+    }
+
+    pub async fn get(ctx: &Ctx, name: &str, lang: Lang) -> Option<Self> {
+        let lang = lang.code();
+        sqlx::query_as!(
+            Self,
+            "select * from templates where name = ? AND lang = ?",
+            name,
+            lang
+        )
+        .fetch_optional(&ctx.db)
+        .await
+        .ok()
+        .flatten()
+    }
+
+    /// the edited body or the built-in default
+    pub async fn body(ctx: &Ctx, name: &'static str, lang: Lang) -> String {
+        match Self::get(ctx, name, lang).await {
+            Some(t) => t.body,
+            None => tr(lang, name).to_string(),
+        }
+    }
+
+    pub async fn list(ctx: &Ctx) -> Result<Vec<Self>, AppErr> {
+        let res = sqlx::query_as!(Self, "select * from templates")
+            .fetch_all(&ctx.db)
+            .await?;
+        Ok(res)
+    }
+
+    pub async fn set(
+        ctx: &Ctx, name: &str, lang: Lang, body: &str, now: i64,
+    ) -> Result<(), AppErr> {
+        let lang = lang.code();
+        sqlx::query!(
+            "insert into templates(name, lang, body, updated_at)
+            values(?,?,?,?) on conflict(name, lang) do update set
+            body = excluded.body, updated_at = excluded.updated_at",
+            name,
+            lang,
+            body,
+            now
+        )
+        .execute(&ctx.db)
+        .await?;
+        Ok(())
+    }
+
+    /// back to the built-in default
+    pub async fn del(ctx: &Ctx, name: &str, lang: Lang) -> Result<(), AppErr> {
+        let lang = lang.code();
+        sqlx::query!(
+            "delete from templates where name = ? AND lang = ?",
+            name,
+            lang
+        )
+        .execute(&ctx.db)
+        .await?;
+        Ok(())
+    }
+
+    /// replace the placeholders in one pass over the body, values are html
+    /// escaped and never scanned for placeholders themselves
+    pub fn render(body: &str, vars: &[(&str, String)]) -> String {
+        let mut out = String::with_capacity(body.len());
+        let mut rest = body;
+        while let Some(open) = rest.find('{') {
+            out.push_str(&rest[..open]);
+            rest = &rest[open..];
+            let value = rest.find('}').and_then(|close| {
+                let name = &rest[1..close];
+                let (_, v) = vars.iter().find(|(n, _)| *n == name)?;
+                Some((close, v))
+            });
+            match value {
+                Some((close, v)) => {
+                    out.push_str(&teloxide::utils::html::escape(v));
+                    rest = &rest[close + 1..];
+                }
+                None => {
+                    out.push('{');
+                    rest = &rest[1..];
+                }
+            }
+        }
+        out.push_str(rest);
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Template;
+
+    #[test]
+    fn render() {
+        let vars = [("name", String::from("{points}")), ("points", "5".into())];
+        assert_eq!(
+            Template::render("{name} has {points} {x} {", &vars),
+            "{points} has 5 {x} {"
+        );
+        let vars = [("name", String::from("<b>"))];
+        assert_eq!(Template::render("hi {name}", &vars), "hi &lt;b&gt;");
+    }
+}
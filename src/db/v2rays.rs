@@ -1,10 +1,13 @@
 use teloxide::{payloads::SendMessageSetters, prelude::Requester};
 
 use crate::{
-    Ctx, book::BookItem, config::Config, error::AppErr, session::Session,
-    state::KeyData, utils::cut_off,
+    Ctx, book::BookItem, bulk::Filter, config::Config, error::AppErr, i18n::Lang,
+    session::Session, state::KeyData, utils::cut_off,
+    vote::{self, Cast, Cluster, Tally},
 };
-use std::{fmt::Display, str::FromStr};
+
+use super::Settings;
+use std::{collections::HashSet, fmt::Display, str::FromStr};
 
 #[derive(Debug, sqlx::FromRow)]
 /// Tonel V2ray
@@ -15,20 +18,18 @@ pub struct V2ray {
     pub up_votes: i64,
     pub dn_votes: i64,
     pub disabled: bool,
+    /// see [`crate::vote::Tally::score`]
+    pub score: f64,
 }
 
 impl V2ray {
     pub fn up_dn_pct(&self) -> (u8, u8) {
         let ttv = self.up_votes + self.dn_votes;
-        let mut upp = 0;
-        let mut dnp = 0;
-        if self.up_votes > 0 {
-            upp = (100 / (ttv / self.up_votes)) as u8;
-        }
-        if self.dn_votes > 0 {
-            dnp = (100 / (ttv / self.dn_votes)) as u8;
+        if ttv <= 0 {
+            return (0, 0);
         }
-        (upp, dnp)
+        let upp = self.up_votes * 100 / ttv;
+        (upp as u8, (100 - upp) as u8)
     }
 
     pub fn from_link(link: &str) -> Option<Self> {
@@ -51,6 +52,7 @@ impl V2ray {
             dn_votes: 0,
             up_votes: 0,
             disabled: false,
+            score: vote::neutral(),
         };
 
         Some(v2)
@@ -67,6 +69,29 @@ impl V2ray {
         .await?)
     }
 
+    /// links of every v2ray, to find duplicates of an import
+    pub async fn links(ctx: &Ctx) -> Result<HashSet<String>, AppErr> {
+        let res = sqlx::query!("select link from v2rays")
+            .fetch_all(&ctx.db)
+            .await?;
+        Ok(res.into_iter().map(|r| r.link).collect())
+    }
+
+    pub async fn export(ctx: &Ctx, filter: Filter) -> Result<Vec<Self>, AppErr> {
+        let active = filter != Filter::All;
+        let min_score = if filter == Filter::Good { vote::neutral() } else { 0.0 };
+        let res = sqlx::query_as!(
+            Self,
+            "select * from v2rays where (NOT ? OR NOT disabled) AND score >= ?
+            order by id",
+            active,
+            min_score
+        )
+        .fetch_all(&ctx.db)
+        .await?;
+        Ok(res)
+    }
+
     pub async fn count(ctx: &Ctx) -> Result<(u32, u32), AppErr> {
         let count = sqlx::query!(
             "select
@@ -81,9 +106,10 @@ impl V2ray {
     }
 
     pub async fn add(&mut self, ctx: &Ctx) -> Result<(), AppErr> {
+        self.score = vote::neutral();
         let res = sqlx::query! {
-            "insert into v2rays(label, link) values(?,?)",
-            self.label, self.link
+            "insert into v2rays(label, link, score) values(?,?,?)",
+            self.label, self.link, self.score
         }
         .execute(&ctx.db)
         .await?;
@@ -97,11 +123,23 @@ impl V2ray {
             .await?)
     }
 
-    pub async fn get_good(ctx: &Ctx) -> Option<Self> {
+    /// a random v2ray the karbar did not recently receive or vote down
+    pub async fn get_good(ctx: &Ctx, karbar: i64, now: i64) -> Option<Self> {
+        let fresh = now - Config::DELIVERY_FRESH;
         sqlx::query_as!(
             Self,
-            "select * from v2rays
-            where NOT disabled order by random() limit 1"
+            "select * from v2rays where NOT disabled
+            AND id NOT IN (
+                select v2ray from v2rays_deliveries
+                where karbar = ? AND at > ?
+            )
+            AND id NOT IN (
+                select v2ray from v2rays_votes where karbar = ? AND kind < 0
+            )
+            order by score + (abs(random()) % 1000) / 4000.0 desc limit 1",
+            karbar,
+            fresh,
+            karbar
         )
         .fetch_optional(&ctx.db)
         .await
@@ -109,6 +147,38 @@ impl V2ray {
         .flatten()
     }
 
+    pub async fn delivered(
+        ctx: &Ctx, karbar: i64, v2ray: i64, now: i64,
+    ) -> Result<(), AppErr> {
+        sqlx::query!(
+            "insert into v2rays_deliveries(karbar, v2ray, at) values(?,?,?)
+            on conflict(karbar, v2ray) do update set at = excluded.at",
+            karbar,
+            v2ray,
+            now
+        )
+        .execute(&ctx.db)
+        .await?;
+        Ok(())
+    }
+
+    /// active v2rays the karbar received since `since`
+    pub async fn history(
+        ctx: &Ctx, karbar: i64, since: i64,
+    ) -> Result<Vec<Self>, AppErr> {
+        let res = sqlx::query_as!(
+            Self,
+            "select * from v2rays where NOT disabled AND id IN (
+                select v2ray from v2rays_deliveries where karbar = ? AND at > ?
+            ) order by id desc limit 30",
+            karbar,
+            since
+        )
+        .fetch_all(&ctx.db)
+        .await?;
+        Ok(res)
+    }
+
     pub async fn del(ctx: &Ctx, id: i64) -> Result<(), AppErr> {
         sqlx::query!("delete from v2rays where id = ?", id)
             .execute(&ctx.db)
@@ -160,52 +230,138 @@ impl V2ray {
         .map(|v| if v.kind >= 0 { 1 } else { -1 })
     }
 
+    /// add or change the vote of a karbar who received the v2ray within
+    /// `window` seconds, keeping the vote counts in sync
     pub async fn vote_add(
-        ctx: &Ctx, karbar: i64, v2: i64, mut kind: i8,
-    ) -> Result<(), AppErr> {
-        if kind >= 0 {
-            kind = 1;
-        } else {
-            kind = -1;
+        ctx: &Ctx, karbar: i64, v2: i64, kind: i8, weight: f64, now: i64,
+        window: i64,
+    ) -> Result<Cast, AppErr> {
+        let kind: i64 = if kind >= 0 { 1 } else { -1 };
+
+        let mut tx = ctx.db.begin().await?;
+        let got = sqlx::query!(
+            "select at from v2rays_deliveries where karbar = ? AND v2ray = ?",
+            karbar,
+            v2
+        )
+        .fetch_optional(&mut *tx)
+        .await?;
+        let Some(got) = got else { return Ok(Cast::NotReceived) };
+        if got.at + window < now {
+            return Ok(Cast::Expired);
+        }
+
+        let old = sqlx::query!(
+            "select kind from v2rays_votes where karbar = ? AND v2ray = ?",
+            karbar,
+            v2
+        )
+        .fetch_optional(&mut *tx)
+        .await?
+        .map(|v| if v.kind >= 0 { 1 } else { -1 });
+        if old == Some(kind) {
+            return Ok(Cast::Same);
         }
 
         sqlx::query!(
-            "insert into v2rays_votes(kind, karbar, v2ray) values(?,?,?)",
+            "insert into v2rays_votes(kind, karbar, v2ray, weight, at)
+            values(?,?,?,?,?) on conflict(karbar, v2ray) do update set
+            kind = excluded.kind, weight = excluded.weight, at = excluded.at",
             kind,
             karbar,
+            v2,
+            weight,
+            now
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        let up = (kind == 1) as i64 - (old == Some(1)) as i64;
+        let dn = (kind == -1) as i64 - (old == Some(-1)) as i64;
+        sqlx::query!(
+            "update v2rays set
+            up_votes = up_votes + ?, dn_votes = dn_votes + ? where id = ?",
+            up,
+            dn,
             v2
         )
-        .execute(&ctx.db)
+        .execute(&mut *tx)
         .await?;
+        tx.commit().await?;
 
-        let mut v2 = Self::get(ctx, v2).await?;
+        Ok(if old.is_some() { Cast::Changed } else { Cast::Added })
+    }
 
-        if kind == 1 {
-            v2.up_votes += 1;
-        } else {
-            v2.dn_votes += 1;
-        }
+    /// items with many light down votes since `since`
+    pub async fn vote_clusters(
+        ctx: &Ctx, since: i64,
+    ) -> Result<Vec<Cluster>, AppErr> {
+        let res = sqlx::query_as!(
+            Cluster,
+            "select v.v2ray as \"item!\", COUNT(1) as \"votes!: i64\",
+            MIN(k.created_at) as \"first!: i64\",
+            MAX(k.created_at) as \"last!: i64\",
+            GROUP_CONCAT(v.karbar) as \"voters!: String\"
+            from v2rays_votes v join karbars k on k.tid = v.karbar
+            where v.kind < 0 AND v.at > ? AND v.weight < ?
+            group by v.v2ray having COUNT(1) >= ?
+            order by COUNT(1) desc limit 20",
+            since,
+            Config::VOTE_SUSPICIOUS,
+            Config::VOTE_CLUSTER_MIN
+        )
+        .fetch_all(&ctx.db)
+        .await?;
+        Ok(res)
+    }
 
-        if v2.up_votes + v2.dn_votes > 25 {
-            let (_, dnp) = v2.up_dn_pct();
-            if dnp > 60 {
-                return Self::del(ctx, v2.id).await;
-                // v2.disabled = true;
-            }
+    /// update the score from the decayed votes and disable it when bad.
+    /// returns the item if it was just disabled
+    pub async fn rescore(
+        ctx: &Ctx, id: i64, st: &Settings, now: i64,
+    ) -> Result<Option<Self>, AppErr> {
+        let votes = sqlx::query!(
+            "select kind, weight, at from v2rays_votes where v2ray = ?",
+            id
+        )
+        .fetch_all(&ctx.db)
+        .await?;
+
+        let mut tally = Tally::default();
+        for v in votes {
+            tally.add(v.kind, v.weight, v.at, now, st);
         }
 
+        let mut item = Self::get(ctx, id).await?;
+        let disable = !item.disabled && tally.is_bad(st);
+        item.score = tally.score();
+        item.disabled |= disable;
         sqlx::query!(
-            "update v2rays set
-            up_votes = ?, dn_votes = ?, disabled = ? where id = ?",
-            v2.up_votes,
-            v2.dn_votes,
-            v2.disabled,
-            v2.id
+            "update v2rays set score = ?, disabled = ? where id = ?",
+            item.score,
+            item.disabled,
+            id
         )
         .execute(&ctx.db)
         .await?;
 
-        Ok(())
+        Ok(disable.then_some(item))
+    }
+
+    /// rescore the active items, returns the ones that got disabled
+    pub async fn rescore_all(
+        ctx: &Ctx, st: &Settings, now: i64,
+    ) -> Result<Vec<Self>, AppErr> {
+        let ids = sqlx::query!("select id from v2rays where NOT disabled")
+            .fetch_all(&ctx.db)
+            .await?;
+        let mut out = Vec::new();
+        for r in ids {
+            if let Some(item) = Self::rescore(ctx, r.id, st, now).await? {
+                out.push(item);
+            }
+        }
+        Ok(out)
     }
 }
 
@@ -242,14 +398,14 @@ pub async fn v2ray_auto_update(s: &mut Session) {
             let m = format!("{added}/{total} v2ray auto from github 🐧");
             s.bot
                 .send_message(conf.dev, m)
-                .reply_markup(KeyData::main_menu())
+                .reply_markup(KeyData::main_menu(Lang::Fa))
                 .await
         }
         Err(e) => {
             let m = format!("auto update v2ray failed ❌\n\n{e:#?}");
             s.bot
                 .send_message(conf.dev, m)
-                .reply_markup(KeyData::main_menu())
+                .reply_markup(KeyData::main_menu(Lang::Fa))
                 .await
         }
     };
@@ -13,6 +13,8 @@ pub enum Worm {
     Sqlx(sqlx::Error),
     Down(DownloadError),
     Rqw(reqwest::Error),
+    Io(std::io::Error),
+    Migrate(sqlx::migrate::MigrateError),
 }
 
 #[derive(Debug)]
@@ -58,11 +60,27 @@ impl From<sqlx::Error> for AppErr {
         let debug = format!("[{value}]: {value:#?}");
         match value {
             sqlx::Error::RowNotFound => Self { worm: Worm::NotFound, debug },
+            sqlx::Error::Database(ref e) if e.is_unique_violation() => {
+                Self { worm: Worm::AlreadyExists, debug }
+            }
             _ => Self { worm: Worm::Sqlx(value), debug },
         }
     }
 }
 
+impl From<std::io::Error> for AppErr {
+    fn from(value: std::io::Error) -> Self {
+        Self { debug: format!("io error: {value:?}"), worm: Worm::Io(value) }
+    }
+}
+
+impl From<sqlx::migrate::MigrateError> for AppErr {
+    fn from(value: sqlx::migrate::MigrateError) -> Self {
+        let debug = format!("[{value}]: {value:#?}");
+        Self { worm: Worm::Migrate(value), debug }
+    }
+}
+
 impl From<Box<dyn std::error::Error + Send + Sync>> for AppErr {
     fn from(value: Box<dyn std::error::Error + Send + Sync>) -> Self {
         Self { worm: Worm::Unknown, debug: format!("[{value}]: {value:#?}") }
@@ -0,0 +1,126 @@
+//! offline ip to country and asn lookup, no network calls.
+//! uses an ip2asn v4 tsv (`range_start range_end as_number country_code
+//! as_description`, from iptoasn.com) bundled into the binary at build
+//! time, or the one at the `db.geoip` config path. only ipv4 literals are
+//! looked up, host names stay unknown.
+
+use crate::config::Config;
+use std::net::Ipv4Addr;
+use std::sync::OnceLock;
+
+/// the table bundled by `build.rs`, empty when it was missing
+static BUNDLED: &str = include_str!(concat!(env!("OUT_DIR"), "/ip2asn-v4.tsv"));
+
+struct Range {
+    start: u32,
+    end: u32,
+    asn: u32,
+    country: [u8; 2],
+}
+
+pub struct Geo {
+    /// upper case two letter country code
+    pub country: String,
+    pub asn: i64,
+}
+
+fn db() -> &'static [Range] {
+    static DB: OnceLock<Vec<Range>> = OnceLock::new();
+    DB.get_or_init(load)
+}
+
+fn load() -> Vec<Range> {
+    let out = match &Config::get().geoip_path {
+        Some(path) => match std::fs::read_to_string(path) {
+            Ok(v) => parse(&v),
+            Err(e) => {
+                log::warn!("could not read geoip db at {path}: {e:?}");
+                return Vec::new();
+            }
+        },
+        None => parse(BUNDLED),
+    };
+    log::info!("geoip loaded {} ranges", out.len());
+    out
+}
+
+fn parse(data: &str) -> Vec<Range> {
+    let mut out = Vec::new();
+    for line in data.lines() {
+        let mut it = line.split('\t');
+        let (Some(s), Some(e), Some(a), Some(c)) =
+            (it.next(), it.next(), it.next(), it.next())
+        else {
+            continue;
+        };
+        let (Ok(s), Ok(e), Ok(asn)) =
+            (s.parse::<Ipv4Addr>(), e.parse::<Ipv4Addr>(), a.parse::<u32>())
+        else {
+            continue;
+        };
+        let &[c1, c2] = c.as_bytes() else { continue };
+        if !c1.is_ascii_alphabetic() || !c2.is_ascii_alphabetic() {
+            continue;
+        }
+        out.push(Range {
+            start: s.into(),
+            end: e.into(),
+            asn,
+            country: [c1.to_ascii_uppercase(), c2.to_ascii_uppercase()],
+        });
+    }
+    out.sort_by_key(|r| r.start);
+    out
+}
+
+/// geo of a proxy server given as an ipv4 address
+pub fn lookup(server: &str) -> Option<Geo> {
+    let ip = server.trim().parse::<Ipv4Addr>().ok()?;
+    find(db(), ip)
+}
+
+fn find(db: &[Range], ip: Ipv4Addr) -> Option<Geo> {
+    let ip = u32::from(ip);
+    let idx = db.partition_point(|r| r.start <= ip).checked_sub(1)?;
+    let r = &db[idx];
+    if ip > r.end {
+        return None;
+    }
+    Some(Geo {
+        country: String::from_utf8_lossy(&r.country).into_owned(),
+        asn: r.asn as i64,
+    })
+}
+
+/// flag emoji of a two letter country code
+pub fn flag(country: &str) -> String {
+    country
+        .bytes()
+        .filter(u8::is_ascii_uppercase)
+        .filter_map(|b| char::from_u32(0x1F1E6 + (b - b'A') as u32))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn find_range() {
+        let db = parse(indoc::indoc! {"
+            1.0.0.0\t1.0.0.255\t13335\tUS\tCLOUDFLARENET
+            1.0.4.0\t1.0.7.255\t38803\tau\tGTELECOM
+            2.0.0.0\t2.0.0.255\t0\tNone\tNot routed
+            bad line
+        "});
+        assert_eq!(db.len(), 2);
+
+        let geo = |ip: &str| find(&db, ip.parse().unwrap());
+        let g = geo("1.0.5.9").unwrap();
+        assert_eq!((g.country.as_str(), g.asn), ("AU", 38803));
+        assert_eq!(geo("1.0.0.0").unwrap().asn, 13335);
+        assert!(geo("1.0.1.0").is_none());
+        assert!(geo("2.0.0.1").is_none());
+        assert!(geo("0.0.0.1").is_none());
+    }
+}
@@ -0,0 +1,106 @@
+use std::{collections::HashMap, sync::OnceLock};
+
+#[derive(
+    Debug,
+    Default,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Hash,
+    serde::Serialize,
+    serde::Deserialize
+)]
+#[serde(rename_all = "snake_case")]
+pub enum Lang {
+    #[default]
+    Fa,
+    En,
+}
+
+impl Lang {
+    pub const ALL: [Lang; 2] = [Lang::Fa, Lang::En];
+
+    pub const fn code(self) -> &'static str {
+        match self {
+            Self::Fa => "fa",
+            Self::En => "en",
+        }
+    }
+
+    /// language from a telegram `language_code`. e.g. `en-US`
+    pub fn from_code(code: &str) -> Option<Self> {
+        let p = code.split(['-', '_']).next().unwrap_or(code);
+        match p.to_lowercase().as_str() {
+            "fa" => Some(Self::Fa),
+            "en" => Some(Self::En),
+            _ => None,
+        }
+    }
+
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Fa => "فارسی 🇮🇷",
+            Self::En => "English 🇬🇧",
+        }
+    }
+}
+
+type Catalog = HashMap<String, String>;
+
+fn catalogs() -> &'static HashMap<Lang, Catalog> {
+    static CATALOGS: OnceLock<HashMap<Lang, Catalog>> = OnceLock::new();
+    CATALOGS.get_or_init(|| {
+        let load = |lang: Lang, data: &str| -> (Lang, Catalog) {
+            match toml::from_str(data) {
+                Ok(v) => (lang, v),
+                Err(e) => panic!("invalid locale {}: {e:#?}", lang.code()),
+            }
+        };
+
+        HashMap::from([
+            load(Lang::Fa, include_str!("../locales/fa.toml")),
+            load(Lang::En, include_str!("../locales/en.toml")),
+        ])
+    })
+}
+
+/// text of `key` in `lang`. falls back to persian and then the key itself
+pub fn tr(lang: Lang, key: &'static str) -> &'static str {
+    let cs = catalogs();
+    cs.get(&lang)
+        .and_then(|c| c.get(key))
+        .or_else(|| cs.get(&Lang::Fa).and_then(|c| c.get(key)))
+        .map(|v| v.as_str())
+        .unwrap_or(key)
+}
+
+/// [`tr`] with every `{name}` replaced by its value
+pub fn trf(lang: Lang, key: &'static str, args: &[(&str, String)]) -> String {
+    let mut out = tr(lang, key).to_string();
+    for (name, value) in args {
+        out = out.replace(&format!("{{{name}}}"), value);
+    }
+    out
+}
+
+/// the catalog key which its text is `txt` in any language
+pub fn key_of(keys: &[&'static str], txt: &str) -> Option<&'static str> {
+    keys.iter()
+        .copied()
+        .find(|key| Lang::ALL.iter().any(|lang| tr(*lang, key) == txt))
+}
+
+macro_rules! t {
+    ($lang:expr, $key:literal) => {
+        crate::i18n::tr($lang, $key)
+    };
+    ($lang:expr, $key:literal, $($name:ident = $val:expr),+ $(,)?) => {
+        crate::i18n::trf(
+            $lang,
+            $key,
+            &[$((stringify!($name), $val.to_string())),+],
+        )
+    };
+}
+pub(crate) use t;
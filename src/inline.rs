@@ -0,0 +1,154 @@
+//! inline mode, `@bot proxy` and `@bot v2ray` share a config in any chat.
+//! points are charged on the chosen result, so inline feedback must be
+//! enabled in @BotFather. telegram asks for a new answer on every key
+//! stroke and never tells us about results that were not picked, so a
+//! charge or a reservation on the answer could never be given back.
+
+use crate::{
+    Ctx, HR, TB,
+    config::Config,
+    db::{Karbar, Proxy, Settings, Template, V2ray},
+    i18n::t,
+    surge::Product,
+    utils,
+};
+use teloxide::payloads::AnswerInlineQuerySetters;
+use teloxide::prelude::*;
+use teloxide::types::{
+    ChosenInlineResult, InlineKeyboardButton, InlineKeyboardMarkup,
+    InlineQuery, InlineQueryResult, InlineQueryResultArticle,
+    InlineQueryResultsButton, InlineQueryResultsButtonKind,
+    InputMessageContent, InputMessageContentText, ParseMode,
+};
+
+pub async fn handle_query(bot: TB, ctx: Ctx, q: InlineQuery) -> HR {
+    let karbar = Karbar::init(&ctx, &q.from, "").await?;
+    let settings = Settings::get(&ctx.db).await;
+    let now = utils::now();
+    let lang = karbar.lang();
+    let conf = Config::get();
+
+    let product = match q.query.trim().to_lowercase().as_str() {
+        "v2" | "v2ray" => Product::V2ray,
+        _ => Product::Proxy,
+    };
+    let cost = karbar.surge_cost(product, &settings, now);
+
+    let deny = if settings.captcha_enabled && !karbar.verified {
+        Some(t!(lang, "inline_verify").to_string())
+    } else if karbar.inline_left(now) <= 0 {
+        Some(t!(lang, "inline_limit").to_string())
+    } else if karbar.points < cost {
+        Some(t!(lang, "inline_no_points", cost = cost))
+    } else {
+        None
+    };
+
+    let result = match deny {
+        Some(_) => None,
+        None => match product {
+            Product::V2ray => {
+                V2ray::get_good(&ctx, karbar.tid, now).await.map(|v2| {
+                    let vars = [("link", v2.link.clone())];
+                    (v2.id, v2.label, vars)
+                })
+            }
+            _ => Proxy::get_good(&ctx, &karbar, now).await.map(|px| {
+                let vars = [("link", px.url())];
+                (px.id, px.server, vars)
+            }),
+        },
+    };
+
+    let mut results = Vec::<InlineQueryResult>::new();
+    if let Some((id, label, vars)) = result {
+        let more = InlineKeyboardButton::url(
+            t!(lang, "btn_inline_more"),
+            conf.start_url.clone(),
+        );
+        let (title, text, kb) = match product {
+            Product::V2ray => {
+                let body = Template::body(&ctx, "v2ray_config", lang).await;
+                let text = Template::render(&body, &vars);
+                (t!(lang, "inline_v2ray_title"), text, vec![vec![more]])
+            }
+            _ => {
+                let text = t!(lang, "inline_proxy");
+                let kb = match reqwest::Url::parse(&vars[0].1) {
+                    Ok(url) => vec![
+                        vec![InlineKeyboardButton::url(
+                            t!(lang, "btn_proxy_connect"),
+                            url,
+                        )],
+                        vec![more],
+                    ],
+                    Err(_) => vec![vec![more]],
+                };
+                (t!(lang, "inline_proxy_title"), text.to_string(), kb)
+            }
+        };
+        let content = InputMessageContent::Text(
+            InputMessageContentText::new(text).parse_mode(ParseMode::Html),
+        );
+        let article = InlineQueryResultArticle::new(
+            format!("{}:{id}:{cost}", product.code()),
+            title,
+            content,
+        )
+        .description(t!(lang, "inline_desc", label = label, cost = cost))
+        .reply_markup(InlineKeyboardMarkup::new(kb));
+        results.push(article.into());
+    }
+
+    let empty = results.is_empty();
+    let mut answer =
+        bot.answer_inline_query(q.id, results).cache_time(0).is_personal(true);
+    if empty {
+        let text = deny.unwrap_or_else(|| t!(lang, "inline_empty").into());
+        answer = answer.button(InlineQueryResultsButton {
+            text,
+            kind: InlineQueryResultsButtonKind::StartParameter(String::from(
+                "inline",
+            )),
+        });
+    }
+    answer.await?;
+
+    Ok(())
+}
+
+/// charge the cost quoted in the result id
+pub async fn handle_chosen(bot: TB, ctx: Ctx, c: ChosenInlineResult) -> HR {
+    let mut it = c.result_id.splitn(3, ':');
+    let product = it.next().and_then(Product::from_code);
+    let id = it.next().and_then(|v| v.parse::<i64>().ok());
+    let cost = it.next().and_then(|v| v.parse::<i64>().ok());
+    let (Some(product), Some(id), Some(cost)) = (product, id, cost) else {
+        return Ok(());
+    };
+
+    let karbar = Karbar::init(&ctx, &c.from, "").await?;
+    let settings = Settings::get(&ctx.db).await;
+    let now = utils::now();
+
+    // the same cached result can be picked many times, check again
+    if !Karbar::inline_charge(&ctx, karbar.tid, cost, now).await? {
+        if let Some(imid) = c.inline_message_id {
+            let m = t!(karbar.lang(), "inline_refused");
+            let _ = bot.edit_message_text_inline(imid, m).await;
+        }
+        return Ok(());
+    }
+
+    let Some(mut karbar) = Karbar::find_with_tid(&ctx, karbar.tid).await else {
+        return Ok(());
+    };
+    karbar.surge_add(product, &settings, now);
+    karbar.set(&ctx).await?;
+    match product {
+        Product::V2ray => V2ray::delivered(&ctx, karbar.tid, id, now).await?,
+        _ => Proxy::delivered(&ctx, karbar.tid, id, now).await?,
+    }
+
+    Ok(())
+}
@@ -1,6 +1,9 @@
+use cache::{MemberCache, is_member};
+use captcha::Purpose;
 use config::Config;
-use db::{Karbar, Settings};
+use db::{Flyer, Karbar, Proxy, Settings};
 use error::{AppErr, Worm};
+use i18n::t;
 use session::Session;
 use sqlx::SqlitePool;
 use state::{State, Store};
@@ -14,16 +17,28 @@ use teloxide::prelude::*;
 use teloxide::utils::command::BotCommands;
 // use tokio::sync::Mutex;
 
+mod backup;
 mod book;
+mod bulk;
+mod cache;
+mod captcha;
 mod cbq;
+mod claw;
+mod cli;
 mod config;
 mod db;
 mod error;
+mod geoip;
+mod i18n;
+mod inline;
 mod logger;
 mod payam;
 mod session;
 mod state;
+mod streak;
+mod surge;
 mod utils;
+mod vote;
 
 type HR = Result<(), AppErr>;
 pub type TB = Throttle<Bot>;
@@ -31,6 +46,7 @@ pub type TB = Throttle<Bot>;
 #[derive(Debug, Clone)]
 pub struct Ctx {
     pub db: SqlitePool,
+    pub fj: MemberCache,
     // pub settings: Arc<Mutex<Settings>>,
 }
 
@@ -39,16 +55,54 @@ async fn main() -> Result<(), AppErr> {
     log::set_logger(&logger::MasterLogger).expect("could not init logger");
     log::set_max_level(log::LevelFilter::Info);
 
+    if cli::run().await? {
+        return Ok(());
+    }
+
     log::info!("Start 🐧!");
 
     let conf = Config::get();
+    if conf.db_backend != db::Backend::Sqlite {
+        return Err(AppErr {
+            worm: Worm::Unknown,
+            debug: String::from(
+                "the bot runs on sqlite only, see db.backend in the readme",
+            ),
+        });
+    }
+
     let bot = Config::init_bot();
     bot.send_message(conf.dev, "Starting Tonel 🌩").await?;
 
+    match backup::apply_staged() {
+        Ok(false) => {}
+        Ok(true) => {
+            bot.send_message(conf.dev, "database restored from backup 💾")
+                .await?;
+        }
+        Err(e) => {
+            log::error!("applying the staged restore failed: {e:?}");
+            let m = format!("applying the staged restore failed ❌\n\n{e:?}");
+            bot.send_message(conf.dev, m).await?;
+        }
+    }
+
     let storage = Config::init_storage().await;
     let db = Config::init_db().await;
     // let settings = Arc::new(Mutex::new(Settings::get(&db).await));
-    let ctx = Ctx { db };
+    let ctx = Ctx { db, fj: MemberCache::default() };
+    db_report(&bot, &ctx).await;
+    let gctx = ctx.clone();
+    tokio::spawn(async move {
+        if let Err(e) = Proxy::geo_fill(&gctx).await {
+            log::error!("proxy geo fill failed: {e:?}");
+        }
+    });
+
+    tokio::spawn(streak::reminder(bot.clone(), ctx.clone()));
+    tokio::spawn(vote::rescore(bot.clone(), ctx.clone()));
+    tokio::spawn(claw::recheck(bot.clone(), ctx.clone()));
+    tokio::spawn(backup::schedule(bot.clone(), ctx.clone()));
 
     let handler = dptree::entry()
         .branch(
@@ -61,7 +115,13 @@ async fn main() -> Result<(), AppErr> {
                 )
                 .endpoint(payam::Payam::handle),
         )
+        .branch(Update::filter_inline_query().endpoint(inline::handle_query))
+        .branch(
+            Update::filter_chosen_inline_result()
+                .endpoint(inline::handle_chosen),
+        )
         .branch(Update::filter_pre_checkout_query().endpoint(handle_pcq))
+        .branch(Update::filter_chat_member().endpoint(handle_chat_member))
         .branch(
             Update::filter_callback_query()
                 .enter_dialogue::<Update, ErasedStorage<State>, State>()
@@ -80,6 +140,30 @@ async fn main() -> Result<(), AppErr> {
     Ok(())
 }
 
+/// tell the dev about a damaged database on start
+async fn db_report(bot: &TB, ctx: &Ctx) {
+    let mut problems = match db::integrity_check(&ctx.db).await {
+        Ok(v) => v,
+        Err(e) => vec![format!("integrity check failed: {}", e.debug)],
+    };
+    match db::foreign_key_check(&ctx.db).await {
+        Ok(v) => problems.extend(v.into_iter().map(|r| format!("fk: {r}"))),
+        Err(e) => problems.push(format!("fk check failed: {}", e.debug)),
+    }
+    if problems.is_empty() {
+        return;
+    }
+
+    log::error!("database problems: {problems:?}");
+    let count = problems.len();
+    problems.truncate(20);
+    let m = format!(
+        "database check found {count} problems ⚠️\n\n{}",
+        problems.join("\n")
+    );
+    let _ = bot.send_message(Config::get().dev, m).await;
+}
+
 struct SendDevErrorHandler {
     bot: TB,
     dev: UserId,
@@ -110,9 +194,16 @@ impl ErrorHandler<AppErr> for SendDevErrorHandler {
 #[command(rename_rule = "snake_case")]
 /// Tonel Bot Commands
 pub enum TonelCommand {
-    Start { r: String },
+    Start {
+        r: String,
+    },
     Menu,
     Help,
+    /// owner only
+    #[command(hide)]
+    Backup,
+    #[command(hide)]
+    Restore,
 }
 
 pub async fn handle_commands(
@@ -138,6 +229,11 @@ pub async fn handle_commands(
                 store,
             };
 
+            if s.captcha_needed(Purpose::Start) {
+                s.captcha_send(Purpose::Start).await?;
+                return Ok(());
+            }
+
             s.ch_send().await?;
             s.send_welcome().await?;
 
@@ -158,6 +254,12 @@ pub async fn handle_commands(
                 bot,
                 store,
             };
+
+            if s.captcha_needed(Purpose::Start) {
+                s.captcha_send(Purpose::Start).await?;
+                return Ok(());
+            }
+
             s.ch_send().await?;
             s.send_menu().await?;
         }
@@ -165,14 +267,66 @@ pub async fn handle_commands(
             let desc = TonelCommand::descriptions().to_string();
             bot.send_message(user.id, desc).await?;
         }
+        TonelCommand::Backup | TonelCommand::Restore => {
+            if user.id != Config::get().dev {
+                return Ok(());
+            }
+            let karbar = Karbar::init(&ctx, &user, "").await?;
+            let s = Session {
+                cid: msg.chat.id,
+                settings: Settings::get(&ctx.db).await,
+                ctx,
+                now: utils::now(),
+                karbar,
+                conf: Config::get(),
+                bot,
+                store,
+            };
+            match cmd {
+                TonelCommand::Backup => s.owner_backup().await?,
+                _ => s.owner_restore().await?,
+            }
+        }
     }
 
     Ok(())
 }
 
 pub async fn handle_pcq(bot: TB, ctx: Ctx, q: PreCheckoutQuery) -> HR {
-    Karbar::init(&ctx, &q.from, "").await?;
+    let karbar = Karbar::init(&ctx, &q.from, "").await?;
     // store.update(State::Menu).await?;
+
+    if let Some(ad) = q.invoice_payload.strip_prefix("ad-") {
+        let id = ad.split('-').next().and_then(|v| v.parse::<i64>().ok());
+        let flyer = match id {
+            Some(id) => Flyer::get(&ctx, id).await.ok(),
+            None => None,
+        };
+        let ok =
+            flyer.is_some_and(|f| f.owner == Some(karbar.tid) && f.payable());
+        if !ok {
+            bot.answer_pre_checkout_query(q.id, false)
+                .error_message(t!(karbar.lang(), "ad_not_found"))
+                .await?;
+            return Ok(());
+        }
+    }
+
     bot.answer_pre_checkout_query(q.id, true).await?;
     Ok(())
 }
+
+/// only arrives for channels where the bot is admin
+pub async fn handle_chat_member(
+    bot: TB, ctx: Ctx, cmu: ChatMemberUpdated,
+) -> HR {
+    let now = utils::now();
+    let joined = is_member(&cmu.new_chat_member.kind);
+    let uid = cmu.new_chat_member.user.id;
+    ctx.fj.set(cmu.chat.id, uid, joined, now);
+    if joined {
+        return Ok(());
+    }
+
+    claw::left(&bot, &ctx, cmu.chat.id.0, uid.0 as i64, now).await
+}
@@ -1,17 +1,24 @@
 use crate::{
-    Ctx, HR, TB,
+    Ctx, HR, TB, backup,
+    bulk::{self, Pool},
     config::Config,
-    db::{Flyer, Karbar, Proxy, Settings, V2ray},
+    db::{Channel, Flyer, Karbar, Proxy, Settings, V2ray},
     error::AppErr,
+    i18n::{Lang, t},
     session::Session,
     state::{AdminGlobal as Ag, KeyData, State, Store, kd, keyboard},
+    surge::{self, Product},
+    utils::{cut_off, parse_date},
 };
 use std::str::FromStr;
 use teloxide::{
     net::Download,
     payloads::SendMessageSetters,
     prelude::Requester,
-    types::{InlineKeyboardButton, InlineKeyboardMarkup, Message},
+    types::{
+        ChatId, InlineKeyboardButton, InlineKeyboardMarkup, Message, Recipient,
+        TelegramTransactionId, UserId,
+    },
 };
 
 pub struct Payam {
@@ -38,25 +45,29 @@ impl Payam {
         };
 
         if let Some(sp) = payam.msg.successful_payment() {
+            if let Some(ad) = sp.invoice_payload.strip_prefix("ad-") {
+                payam.s.ad_paid(ad, sp).await?;
+                return Ok(());
+            }
+
             let sspp = payam.s.settings.star_point_price as u32;
             let added_points = sspp as u32 * sp.total_amount;
             payam.s.karbar.points += added_points as i64;
             payam.s.karbar.set(&payam.s.ctx).await?;
 
-            let msg = indoc::formatdoc!(
-                "از خرید شما سپاس گزاریم 🫠
-                
-                {} امتیاز به حساب شما اضافه شد! 🎉
-                امتیاز فعلی شما: {} 🍅",
-                added_points,
-                payam.s.karbar.points
+            let lang = payam.s.lang();
+            let msg = t!(
+                lang,
+                "payment_thanks",
+                added = added_points,
+                points = payam.s.karbar.points
             );
 
             payam
                 .s
                 .bot
                 .send_message(payam.s.cid, msg)
-                .reply_markup(KeyData::main_menu())
+                .reply_markup(KeyData::main_menu(lang))
                 .await?;
 
             return Ok(());
@@ -64,20 +75,32 @@ impl Payam {
 
         payam.s.ch_send().await?;
 
+        let owner = payam.s.karbar.uid() == payam.s.conf.dev;
+        if owner && matches!(payam.state, State::OwnerRestore) {
+            payam.owner_restore_upload().await?;
+            return Ok(());
+        }
+
         if is_admin && payam.handle_admin().await? {
             return Ok(());
         }
 
+        if payam.handle_karbar().await? {
+            return Ok(());
+        }
+
         let Some(txt) = payam.msg.text() else {
             return Ok(());
         };
 
-        match txt {
-            keyboard::GET_VIP => payam.s.get_vip().await?,
+        let Some(key) = keyboard::key(txt) else { return Ok(()) };
+
+        match key {
+            keyboard::GET_VIP => payam.s.get_vip(None).await?,
             keyboard::INVITE => payam.s.get_invite().await?,
             keyboard::FREE_PONT => payam.s.get_free_point().await?,
-            keyboard::GET_V2RAY => payam.s.get_v2ray().await?,
-            keyboard::GET_PROXY => payam.s.get_proxy().await?,
+            keyboard::GET_V2RAY => payam.s.get_v2ray(None).await?,
+            keyboard::GET_PROXY => payam.s.get_proxy(None).await?,
             keyboard::MENU => payam.s.send_menu().await?,
             keyboard::BUY_STAR_POINT => payam.s.buy_star_point().await?,
             keyboard::DONATE => payam.s.donate().await?,
@@ -87,6 +110,82 @@ impl Payam {
         Ok(())
     }
 
+    async fn handle_karbar(&mut self) -> Result<bool, AppErr> {
+        let ad = matches!(
+            self.state,
+            State::AdLabel
+                | State::AdMessage { .. }
+                | State::AdLink { .. }
+                | State::SubmitProxy
+        );
+        if ad && self.msg.text().map(keyboard::is_key).unwrap_or_default() {
+            self.s.store.update(State::Menu).await?;
+            return Ok(false);
+        }
+
+        let lang = self.s.lang();
+        match &self.state {
+            State::AdLabel => {
+                let Some(label) = self.msg.text() else {
+                    self.s.notify(t!(lang, "ad_no_text")).await?;
+                    return Ok(true);
+                };
+                let mut label = label.to_string();
+                cut_off(&mut label, 64);
+                let m = t!(lang, "ad_label", label = label);
+                self.s.store.update(State::AdMessage { label }).await?;
+                self.s.notify(&m).await?;
+            }
+            State::AdMessage { label } => {
+                let dev = self.s.conf.dev;
+                let (cid, mid) = (self.s.cid, self.msg.id);
+                let mx = self.s.bot.forward_message(dev, cid, mid).await?;
+                let st =
+                    State::AdLink { label: label.clone(), mid: mx.id.0 as i64 };
+                self.s.store.update(st).await?;
+                self.s.notify(t!(lang, "ad_link")).await?;
+            }
+            State::AdLink { label, mid } => {
+                let Some(txt) = self.msg.text() else {
+                    self.s.notify(t!(lang, "ad_no_text")).await?;
+                    return Ok(true);
+                };
+                let link = match txt.trim() {
+                    "-" => None,
+                    v => match reqwest::Url::from_str(v) {
+                        Ok(url) => Some(url.to_string()),
+                        Err(_) => {
+                            self.s.notify(t!(lang, "ad_bad_link")).await?;
+                            return Ok(true);
+                        }
+                    },
+                };
+
+                let mut flyer = Flyer::new(label.clone(), *mid);
+                flyer.link = link;
+                flyer.owner = Some(self.s.karbar.tid);
+                flyer.status = Flyer::STATUS_DRAFT;
+                flyer.max_views = 0;
+                flyer.add(&self.s.ctx).await?;
+
+                self.s.store.update(State::Menu).await?;
+                let m = t!(lang, "ad_created");
+                self.s.bot.send_message(self.s.cid, m).await?;
+                self.s.my_ad(flyer.id).await?;
+            }
+            State::SubmitProxy => {
+                let Some(txt) = self.msg.text() else {
+                    self.s.notify(t!(lang, "submit_bad_link")).await?;
+                    return Ok(true);
+                };
+                self.s.submit_proxy(txt).await?;
+            }
+            _ => return Ok(false),
+        }
+
+        Ok(true)
+    }
+
     async fn gn<T: FromStr>(&self) -> Result<Option<T>, AppErr> {
         let Some(txt) = self.msg.text() else {
             self.s.notify("پیام متنی ندارد ❌").await?;
@@ -113,9 +212,24 @@ impl Payam {
                 self.s.send_menu().await?;
             }};
         }
+        macro_rules! set_channel {
+            ($id:ident, $val:ident, $msg:literal) => {{
+                let Some(value) = self.gn::<i64>().await? else {
+                    return Ok(true);
+                };
+                let mut ch = Channel::get(&self.s.ctx, *$id).await?;
+                ch.$val = value;
+                ch.set(&self.s.ctx).await?;
+                self.s.notify($msg).await?;
+                self.s.store.update(State::Menu).await?;
+            }};
+        }
         match &self.state {
-            State::AdminProxyAdd => self.admin_proxy_add().await?,
-            State::AdminV2rayAdd => self.admin_v2ray_add().await?,
+            State::AdminProxyAdd => self.admin_import(Pool::Proxy).await?,
+            State::AdminV2rayAdd => self.admin_import(Pool::V2ray).await?,
+            State::AdminImportConfirm { pool, .. } => {
+                self.admin_import(*pool).await?
+            }
             State::AdminSetVipMsg => self.admin_set_vip_msg().await?,
             State::AdminSetDonateMsg => self.admin_set_donate_msg().await?,
             State::AdminFindKarbar => self.admin_find_karbar().await?,
@@ -127,6 +241,175 @@ impl Payam {
             State::AdminSetFreePt => set_int!(free_points),
             State::AdminSetFreePtDelay => set_int!(free_point_delay),
             State::AdminSetVipMaxViews => set_int!(vip_max_views),
+            State::AdminSetAdStarPrice => set_int!(ad_star_price),
+            State::AdminSetStreakWindow => set_int!(streak_window),
+            State::AdminSetCaptchaEvery => set_int!(captcha_every),
+            State::AdminSetCaptchaTries => set_int!(captcha_tries),
+            State::AdminSetCaptchaLockout => set_int!(captcha_lockout),
+            State::AdminSetBundleSize => {
+                let Some(value) = self.gn::<i64>().await? else {
+                    return Ok(true);
+                };
+                if !(3..=5).contains(&value) {
+                    self.s.notify("تعداد باید بین 3 تا 5 باشد ❌").await?;
+                    return Ok(true);
+                }
+                self.s.settings.bundle_size = value;
+                self.s.settings.set(&self.s.ctx.db).await?;
+                self.s.send_menu().await?;
+            }
+            State::AdminSetBundleDiscount => set_int!(bundle_discount),
+            State::AdminSetVoteHalfLife => set_int!(vote_half_life),
+            State::AdminSetVoteMinWeight => set_int!(vote_min_weight),
+            State::AdminSetVoteDisablePct => set_int!(vote_disable_pct),
+            State::AdminSetVoteWindow => set_int!(vote_window),
+            State::AdminSetSubmitReward => set_int!(submit_reward),
+            State::AdminSetSubmitVoteReward => set_int!(submit_vote_reward),
+            State::AdminSetStreakBonus => {
+                let Some(txt) = self.msg.text() else {
+                    self.s.notify("پیام متنی ندارد ❌").await?;
+                    return Ok(true);
+                };
+                let Some(bonus) = surge::parse_curve(txt) else {
+                    self.s.notify("پاداش پیاپی اشتباه است ❌").await?;
+                    return Ok(true);
+                };
+                self.s.settings.streak_bonus = bonus
+                    .iter()
+                    .map(|v| v.to_string())
+                    .collect::<Vec<_>>()
+                    .join(",");
+                self.s.settings.set(&self.s.ctx.db).await?;
+                self.s.send_menu().await?;
+            }
+            State::AdminSetSurgeDecay => {
+                let Some(value) = self.gn::<i64>().await? else {
+                    return Ok(true);
+                };
+                self.s.settings.surge_decay = value.max(0);
+                self.s.settings.set(&self.s.ctx.db).await?;
+                self.s.store.update(State::Menu).await?;
+                self.s.admin_surge().await?;
+            }
+            State::AdminSurgeSet(product) => {
+                let Some(txt) = self.msg.text() else {
+                    self.s.notify("پیام متنی ندارد ❌").await?;
+                    return Ok(true);
+                };
+                let Some(curve) = surge::parse_curve(txt) else {
+                    self.s.notify("منحنی اشتباه است ❌").await?;
+                    return Ok(true);
+                };
+                let curve = curve
+                    .iter()
+                    .map(|v| v.to_string())
+                    .collect::<Vec<_>>()
+                    .join(",");
+                let st = &mut self.s.settings;
+                match product {
+                    Product::Proxy | Product::Bundle => st.proxy_surge = curve,
+                    Product::V2ray => st.v2ray_surge = curve,
+                    Product::Vip => st.vip_surge = curve,
+                }
+                self.s.settings.set(&self.s.ctx.db).await?;
+                self.s.store.update(State::Menu).await?;
+                self.s.admin_surge().await?;
+            }
+            State::AdminTemplateEdit(idx, lang) => {
+                let Some(body) = self.msg.text() else {
+                    self.s.notify("پیام متنی ندارد ❌").await?;
+                    return Ok(true);
+                };
+                if !self.s.template_preview(body).await? {
+                    return Ok(true);
+                }
+
+                let kb = InlineKeyboardMarkup::new([[
+                    InlineKeyboardButton::callback(
+                        "ذخیره ✅",
+                        kd!(ag, Ag::TemplateSave),
+                    ),
+                    InlineKeyboardButton::callback(
+                        "ویرایش دوباره ✏️",
+                        kd!(ag, Ag::TemplateEdit(*idx, *lang)),
+                    ),
+                    KeyData::main_menu_btn(Lang::Fa),
+                ]]);
+                let m = "پیش نمایش بالا را بررسی و ذخیره کنید 👆";
+                self.s.bot.send_message(self.s.cid, m).reply_markup(kb).await?;
+
+                let (idx, lang) = (*idx, *lang);
+                let body = body.to_string();
+                let st = State::AdminTemplateConfirm { idx, lang, body };
+                self.s.store.update(st).await?;
+            }
+            State::AdminFlyerReject(id) => {
+                let Some(reason) = self.msg.text() else {
+                    self.s.notify("پیام متنی ندارد ❌").await?;
+                    return Ok(true);
+                };
+                let ctx = &self.s.ctx;
+                let mut flyer = Flyer::get(ctx, *id).await?;
+                if flyer.status != Flyer::STATUS_PENDING {
+                    let m =
+                        format!("وضعیت تبلیغ: {}", flyer.status_str(Lang::Fa));
+                    self.s.notify(&m).await?;
+                    self.s.store.update(State::Menu).await?;
+                    return Ok(true);
+                }
+
+                // pending flyers have not shown any of the paid views,
+                // so every payment since the last refund goes back
+                let (mut refunded, mut stars) = (false, 0);
+                if let Some(owner) = flyer.owner {
+                    for ch in flyer.charges(ctx).await? {
+                        let uid = UserId(owner as u64);
+                        let tx = TelegramTransactionId(ch.charge_id.clone());
+                        if let Err(e) =
+                            self.s.bot.refund_star_payment(uid, tx).await
+                        {
+                            // stays pending so the reject can be retried
+                            let m = format!(
+                                "بازگشت وجه انجام نشد، دوباره تلاش کنید ❌\n\n{e}"
+                            );
+                            self.s.notify(&m).await?;
+                            self.s.store.update(State::Menu).await?;
+                            return Ok(true);
+                        }
+                        Flyer::charge_refunded(ctx, ch.id).await?;
+                        refunded = true;
+                        stars += ch.stars;
+                    }
+                }
+
+                flyer.status = Flyer::STATUS_REJECTED;
+                flyer.reason = Some(reason.to_string());
+                flyer.max_views = flyer.views;
+                flyer.disabled = true;
+                flyer.set(ctx).await?;
+
+                if let Some(owner) = flyer.owner {
+                    let lang = Karbar::find_with_tid(&self.s.ctx, owner)
+                        .await
+                        .map(|k| k.lang())
+                        .unwrap_or_default();
+                    let mut m = t!(
+                        lang,
+                        "ad_rejected",
+                        label = flyer.label,
+                        reason = reason
+                    );
+                    if refunded {
+                        m += "\n\n";
+                        m += t!(lang, "ad_refunded");
+                    }
+                    let _ = self.s.bot.send_message(ChatId(owner), m).await;
+                }
+
+                let m = format!("تبلیغ رد شد ❌\n\nستاره بازگشتی: {stars} ⭐");
+                self.s.notify(&m).await?;
+                self.s.store.update(State::Menu).await?;
+            }
             State::AdminKarbarSetPoints(kid) => {
                 let Some(mv) = self.gn::<i64>().await? else {
                     return Ok(true);
@@ -177,6 +460,80 @@ impl Payam {
                 self.s.notify("لینک ثبت شد ✅").await?;
                 self.s.store.update(State::Menu).await?;
             }
+            State::AdminChannelAdd => self.admin_channel_add().await?,
+            State::AdminChannelSetAmount(id) => {
+                set_channel!(id, amount, "پاداش عضویت ثبت شد ✅")
+            }
+            State::AdminChannelSetMaxSub(id) => {
+                set_channel!(id, max_sub, "حداکثر عضو ثبت شد ✅")
+            }
+            State::AdminChannelSetClawDays(id) => {
+                set_channel!(id, claw_days, "حداقل روز ماندن ثبت شد ✅")
+            }
+            State::AdminFlyerSetDailyMax(id) => {
+                let m = "سقف بازدید روزانه ثبت شد ✅";
+                self.admin_flyer_int(*id, m, |f, v| {
+                    f.daily_max_views = v.max(-1)
+                })
+                .await?
+            }
+            State::AdminFlyerSetUserCap(id) => {
+                let m = "سقف بازدید هر کاربر ثبت شد ✅";
+                self.admin_flyer_int(*id, m, |f, v| f.user_cap = v.max(-1))
+                    .await?
+            }
+            State::AdminFlyerSetWeight(id) => {
+                let m = "وزن تبلیغ ثبت شد ✅";
+                self.admin_flyer_int(*id, m, |f, v| f.weight = v.max(1)).await?
+            }
+            State::AdminFlyerSetSchedule(id) => {
+                let Some(txt) = self.msg.text() else {
+                    self.s.notify("پیام متنی ندارد ❌").await?;
+                    return Ok(true);
+                };
+                let mut it = txt.split_whitespace();
+                let start = it.next().unwrap_or("-");
+                let end = it.next().unwrap_or("-");
+                let starts_at = match start {
+                    "-" => Some(0),
+                    v => parse_date(v),
+                };
+                let ends_at = match end {
+                    "-" => Some(0),
+                    v => parse_date(v).map(|v| v + 86400),
+                };
+                let (Some(starts_at), Some(ends_at)) = (starts_at, ends_at)
+                else {
+                    self.s.notify("تاریخ اشتباه است ❌").await?;
+                    return Ok(true);
+                };
+                let mut flyer = Flyer::get(&self.s.ctx, *id).await?;
+                flyer.starts_at = starts_at;
+                flyer.ends_at = ends_at;
+                flyer.set(&self.s.ctx).await?;
+                self.s.notify("زمان بندی ثبت شد ✅").await?;
+                self.s.store.update(State::Menu).await?;
+            }
+            State::AdminFlyerSetLang(id) => {
+                let Some(txt) = self.msg.text() else {
+                    self.s.notify("پیام متنی ندارد ❌").await?;
+                    return Ok(true);
+                };
+                let txt = txt.trim().to_lowercase();
+                let lang = match txt.as_str() {
+                    "-" => None,
+                    v if v.len() == 2 && v.is_ascii() => Some(v.to_string()),
+                    _ => {
+                        self.s.notify("کد زبان اشتباه است ❌").await?;
+                        return Ok(true);
+                    }
+                };
+                let mut flyer = Flyer::get(&self.s.ctx, *id).await?;
+                flyer.lang = lang;
+                flyer.set(&self.s.ctx).await?;
+                self.s.notify("زبان ثبت شد ✅").await?;
+                self.s.store.update(State::Menu).await?;
+            }
             State::AdminFlyerAdd => {
                 let Some(label) = self.msg.text() else {
                     self.s.notify("پیام شما هیچ متنی ندارد 🍌").await?;
@@ -224,12 +581,19 @@ impl Payam {
                             "تایید و ارسال ✅",
                             kd!(ag, Ag::SendAllConfirm(df, mid)),
                         ),
-                        KeyData::main_menu_btn(),
+                        KeyData::main_menu_btn(Lang::Fa),
                     ]]))
                     .await?;
             }
             State::Menu
+            | State::AdLabel
+            | State::AdMessage { .. }
+            | State::AdLink { .. }
+            | State::SubmitProxy
+            | State::OwnerRestore
+            | State::AdminTemplateConfirm { .. }
             | State::AdminFlyerList
+            | State::AdminChannelList
             | State::AdminProxyList
             | State::AdminV2rayList => {
                 return Ok(false);
@@ -239,6 +603,18 @@ impl Payam {
         Ok(true)
     }
 
+    async fn admin_flyer_int(
+        &self, id: i64, msg: &str, f: fn(&mut Flyer, i64),
+    ) -> HR {
+        let Some(value) = self.gn::<i64>().await? else { return Ok(()) };
+        let mut flyer = Flyer::get(&self.s.ctx, id).await?;
+        f(&mut flyer, value);
+        flyer.set(&self.s.ctx).await?;
+        self.s.notify(msg).await?;
+        self.s.store.update(State::Menu).await?;
+        Ok(())
+    }
+
     async fn admin_set_vip_msg(&mut self) -> HR {
         let (d, cid, mid) = (self.s.conf.dev, self.s.cid, self.msg.id);
         let mx = self.s.bot.forward_message(d, cid, mid).await?;
@@ -260,6 +636,56 @@ impl Payam {
         Ok(())
     }
 
+    async fn admin_channel_add(&mut self) -> HR {
+        let Some(txt) = self.msg.text() else {
+            self.s.notify("پیام متنی ندارد ❌").await?;
+            return Ok(());
+        };
+        let txt = txt.trim();
+        let rcp = if txt.starts_with('@') {
+            Recipient::ChannelUsername(txt.to_string())
+        } else if let Ok(id) = txt.parse::<i64>() {
+            Recipient::Id(ChatId(id))
+        } else {
+            self.s.notify("ایدی کانال اشتباه است ❌").await?;
+            return Ok(());
+        };
+
+        let chat = match self.s.bot.get_chat(rcp).await {
+            Ok(v) => v,
+            Err(e) => {
+                let m =
+                    format!("کانال پیدا نشد، ربات باید ادمین باشد ❌\n\n{e}");
+                self.s.notify(&m).await?;
+                return Ok(());
+            }
+        };
+
+        let name = chat.title().unwrap_or("---").to_string();
+        let url = if let Some(un) = chat.username() {
+            format!("https://t.me/{un}")
+        } else if let Some(link) = chat.invite_link() {
+            link.to_string()
+        } else {
+            self.s.notify("لینک دعوت کانال پیدا نشد ❌").await?;
+            return Ok(());
+        };
+
+        Channel::add(&self.s.ctx, chat.id.0, &name, &url).await?;
+        let st = State::AdminChannelSetAmount(chat.id.0);
+        self.s.store.update(st).await?;
+
+        let m = indoc::formatdoc!(
+            "کانال {name} ثبت شد ✅
+
+            پاداش عضویت را به صورت عدد ارسال کنید
+            کانال بعد از فعال سازی در لیست کانال ها نمایش داده می شود"
+        );
+        self.s.notify(&m).await?;
+
+        Ok(())
+    }
+
     async fn admin_find_karbar(&mut self) -> HR {
         let (tid, una) = 'a: {
             if let Some(u) = self.msg.forward_from_user() {
@@ -324,12 +750,28 @@ impl Payam {
                 break 'd;
             }
             let m = doc.mime_type.clone();
+            let text = m
+                .map(|v| {
+                    v.type_() == "text"
+                        || matches!(
+                            v.subtype().as_str(),
+                            "json" | "x-yaml" | "yaml"
+                        )
+                })
+                .unwrap_or_default();
+            let ext = doc
+                .file_name
+                .as_deref()
+                .and_then(|n| n.rsplit_once('.'))
+                .map(|(_, ext)| ext.to_ascii_lowercase());
+            let known = matches!(
+                ext.as_deref(),
+                Some("txt" | "csv" | "json" | "yaml" | "yml")
+            );
 
-            if !m.map(|v| v.type_() == "text").unwrap_or_default() {
-                self.s
-                    .bot
-                    .send_message(self.s.cid, "only text files are allowed")
-                    .await?;
+            if !text && !known {
+                let m = "only text, csv, json and yaml files are allowed";
+                self.s.bot.send_message(self.s.cid, m).await?;
                 break 'd;
             }
 
@@ -350,61 +792,109 @@ impl Payam {
         Ok(data)
     }
 
-    async fn admin_proxy_add(&self) -> HR {
-        let data = self.get_full_text().await?;
-        let mut added = 0;
-
-        for line in data.split('\n') {
-            if line.is_empty() {
-                continue;
-            }
+    /// download a database from the owner and check it before restore
+    async fn owner_restore_upload(&self) -> HR {
+        let Some(doc) = self.msg.document() else {
+            let m = "فایل دیتابیس را به صورت document ارسال کنید 📎";
+            return self.s.notify(m).await;
+        };
+        // the bot api can not download bigger files
+        if doc.file.size > 20 * 1024 * 1024 {
+            return self.s.notify("حجم فایل بیش از 20MB است ❌").await;
+        }
 
-            let Some(mut px) = Proxy::from_link(line) else { continue };
-            if px.add(&self.s.ctx).await.is_ok() {
-                added += 1;
+        let path = backup::upload();
+        tokio::fs::create_dir_all(&self.s.conf.backup_dir).await?;
+        let f = self.s.bot.get_file(doc.file.id.clone()).await?;
+        let mut file = tokio::fs::File::create(&path).await?;
+        self.s.bot.download_file(&f.path, &mut file).await?;
+        file.sync_all().await?;
+
+        let version = match backup::check(&self.s.ctx, &path).await {
+            Ok(v) => v,
+            Err(e) => {
+                tokio::fs::remove_file(&path).await?;
+                return self.s.notify(&format!("{e} ❌")).await;
             }
-        }
+        };
 
         let m = indoc::formatdoc!(
-            "{added} پروکسی اضافه شد ✅
+            "فایل سالم است ✅
+            نسخه دیتابیس: {version}
 
-            کانفیگ های دیگر را ارسال کنید یه به منوی اصلی بروید"
+            با تایید، دیتابیس فعلی در پوشه بکاپ نگه داشته می شود و ربات با
+            دیتابیس جدید دوباره راه اندازی می شود ⚠️"
         );
-        self.s
-            .bot
-            .send_message(self.s.cid, m)
-            .reply_markup(KeyData::main_menu())
-            .await?;
+        let kb = InlineKeyboardMarkup::new([[
+            InlineKeyboardButton::callback(
+                "بازگردانی ⚠️",
+                KeyData::RestoreConfirm,
+            ),
+            KeyData::main_menu_btn(Lang::Fa),
+        ]]);
+        self.s.bot.send_message(self.s.cid, m).reply_markup(kb).await?;
+        self.s.store.update(State::Menu).await?;
 
         Ok(())
     }
 
-    async fn admin_v2ray_add(&self) -> HR {
+    /// dry run of a proxy or v2ray import, the new links wait in
+    /// [`State::AdminImportConfirm`] until the admin commits them
+    async fn admin_import(&self, pool: Pool) -> HR {
         let data = self.get_full_text().await?;
-
-        let mut added = 0;
-
-        for line in data.split('\n') {
-            if line.is_empty() {
-                continue;
-            }
-
-            let Some(mut v2) = V2ray::from_link(line) else { continue };
-            if v2.add(&self.s.ctx).await.is_ok() {
-                added += 1;
-            }
+        let report = bulk::parse(pool, &data);
+        let existing = match pool {
+            Pool::Proxy => Proxy::links(&self.s.ctx).await?,
+            Pool::V2ray => V2ray::links(&self.s.ctx).await?,
+        };
+        let (links, old): (Vec<_>, Vec<_>) =
+            report.links.into_iter().partition(|l| !existing.contains(l));
+
+        let mut m = indoc::formatdoc!(
+            "گزارش بررسی 📋
+
+            فرمت: {}
+            معتبر: {}
+            جدید: {}
+            تکراری در فایل: {}
+            موجود در ربات: {}
+            نامعتبر: {}
+            ",
+            report.format.name(),
+            links.len() + old.len(),
+            links.len(),
+            report.repeated,
+            old.len(),
+            report.invalid.len()
+        );
+        for (n, reason) in report.invalid.iter().take(20) {
+            m += &format!("\n{n}: {reason}");
+        }
+        if report.invalid.len() > 20 {
+            m += "\n...";
         }
+        cut_off(&mut m, 3500);
 
-        let m = indoc::formatdoc!(
-            "{added} کانفیگ v2ray اضافه شد ✅
+        if links.is_empty() {
+            m += "\n\nمورد جدیدی برای ثبت نیست، فایل دیگری ارسال کنید";
+            self.s
+                .bot
+                .send_message(self.s.cid, m)
+                .reply_markup(KeyData::main_menu(Lang::Fa))
+                .await?;
+            return Ok(());
+        }
 
-            کانفیگ های دیگر را ارسال کنید یه به منوی اصلی بروید"
-        );
-        self.s
-            .bot
-            .send_message(self.s.cid, m)
-            .reply_markup(KeyData::main_menu())
-            .await?;
+        m += "\n\nبرای ثبت موارد جدید تایید کنید یا فایل دیگری ارسال کنید";
+        let kb = InlineKeyboardMarkup::new([[
+            InlineKeyboardButton::callback(
+                format!("ثبت {} مورد ✅", links.len()),
+                kd!(gg, ImportCommit),
+            ),
+            KeyData::main_menu_btn(Lang::Fa),
+        ]]);
+        self.s.bot.send_message(self.s.cid, m).reply_markup(kb).await?;
+        self.s.store.update(State::AdminImportConfirm { pool, links }).await?;
 
         Ok(())
     }
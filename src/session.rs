@@ -1,18 +1,31 @@
 use crate::{
-    Ctx, HR, TB,
+    Ctx, HR, TB, backup,
+    bulk::{self, Filter, Format, Pool},
+    cache::is_member,
+    captcha::{self, Purpose},
     config::Config,
-    db::{Flyer, Karbar, Proxy, Settings, V2ray},
-    error::AppErr,
+    db::{
+        Captcha, Channel, Flyer, Karbar, Proxy, Settings, Submission, Template,
+        V2ray,
+    },
+    error::{AppErr, Worm},
+    geoip,
+    i18n::{Lang, t, tr},
     state::{AdminGlobal as Ag, KeyData, State, Store, kd, keyboard},
+    streak,
+    surge::{self, Product},
+    utils::{check_html, fmt_date},
+    vote,
 };
 use std::str::FromStr;
 use teloxide::{
-    payloads::{CopyMessageSetters, SendMessageSetters},
+    payloads::{CopyMessageSetters, SendDocumentSetters, SendMessageSetters},
     prelude::Requester,
     sugar::request::RequestLinkPreviewExt,
     types::{
-        ChatId, ChatMemberKind, InlineKeyboardButton, InlineKeyboardMarkup,
-        KeyboardButton, KeyboardMarkup, MessageId, ParseMode,
+        ChatId, InlineKeyboardButton, InlineKeyboardMarkup, InputFile,
+        KeyboardButton, KeyboardMarkup, LabeledPrice, MessageId, ParseMode,
+        SuccessfulPayment, UserId,
     },
     utils::html::escape,
 };
@@ -29,14 +42,77 @@ pub struct Session {
 }
 
 impl Session {
+    pub fn lang(&self) -> Lang {
+        self.karbar.lang()
+    }
+
+    /// the template `name` rendered for the karbar, see [`Template`]
+    pub async fn tpl(
+        &self, name: &'static str, vars: &[(&str, String)],
+    ) -> String {
+        let body = Template::body(&self.ctx, name, self.lang()).await;
+        let username = match &self.karbar.username {
+            Some(u) => format!("@{u}"),
+            None => self.karbar.fullname.clone(),
+        };
+        let mut all = vec![
+            ("points", self.karbar.points.to_string()),
+            ("username", username),
+        ];
+        all.extend_from_slice(vars);
+        Template::render(&body, &all)
+    }
+
+    /// send `body` rendered with sample values.
+    /// returns false when it is not valid telegram html
+    pub async fn template_preview(&self, body: &str) -> Result<bool, AppErr> {
+        let vars = [
+            ("points", self.karbar.points.to_string()),
+            ("cost", String::from("10")),
+            ("link", String::from("vless://preview@example.com:443")),
+            ("username", String::from("@username")),
+        ];
+        let m = Template::render(body, &vars);
+        if let Err(e) = check_html(&m) {
+            self.notify(&format!("HTML نامعتبر است ❌\n\n{e}")).await?;
+            return Ok(false);
+        }
+        if m.chars().count() > 4096 {
+            self.notify("متن بیشتر از 4096 کاراکتر است ❌").await?;
+            return Ok(false);
+        }
+
+        let r = self.bot.send_message(self.cid, m).parse_mode(ParseMode::Html);
+        if let Err(e) = r.await {
+            let m = format!("تلگرام این پیام را نپذیرفت ❌\n\n{e}");
+            self.notify(&m).await?;
+            return Ok(false);
+        }
+
+        Ok(true)
+    }
+
     pub async fn check_force_join(
         &self, btn: InlineKeyboardButton,
     ) -> Result<bool, AppErr> {
         let mut ntj = Vec::with_capacity(self.conf.force_join.len());
+        let uid = self.karbar.uid();
         for (cid, title, url) in self.conf.force_join.iter() {
-            let r = self.bot.get_chat_member(*cid, self.karbar.uid()).await;
-            let Ok(cm) = r else { continue };
-            if cm.kind != ChatMemberKind::Left {
+            let joined = match self.ctx.fj.get(*cid, uid, self.now) {
+                Some(v) => v,
+                None => match self.bot.get_chat_member(*cid, uid).await {
+                    Ok(cm) => {
+                        let v = is_member(&cm.kind);
+                        self.ctx.fj.set(*cid, uid, v, self.now);
+                        v
+                    }
+                    Err(e) => {
+                        log::warn!("force join check {cid} failed: {e:?}");
+                        !self.settings.fj_fail_closed
+                    }
+                },
+            };
+            if joined {
                 continue;
             }
 
@@ -55,7 +131,7 @@ impl Session {
 
         kyb.push([btn]);
 
-        let m = concat!("شما ابتدا باید عضو چنل های زیر شوید:");
+        let m = t!(self.lang(), "fj_title");
 
         self.bot
             .send_message(self.cid, m)
@@ -100,28 +176,34 @@ impl Session {
     pub async fn notify(&self, text: &str) -> HR {
         self.bot
             .send_message(self.cid, text)
-            .reply_markup(KeyData::main_menu())
+            .reply_markup(KeyData::main_menu(self.lang()))
             .await?;
         Ok(())
     }
 
     pub async fn notify_no_points(&self, text: &str) -> HR {
+        let lang = self.lang();
         let kb = InlineKeyboardMarkup::new([
-            vec![KeyData::main_menu_btn(), KeyData::donate_btn()],
+            vec![KeyData::main_menu_btn(lang), KeyData::donate_btn(lang)],
             vec![InlineKeyboardButton::callback(
-                "دریافت امتیاز رایگان 🍅",
+                t!(lang, "btn_free_point"),
                 KeyData::GetFreePoints,
             )],
         ]);
-        self.bot.send_message(self.cid, text).reply_markup(kb).await?;
+        self.bot
+            .send_message(self.cid, text)
+            .parse_mode(ParseMode::Html)
+            .reply_markup(kb)
+            .await?;
         Ok(())
     }
 
     pub async fn donate(&self) -> HR {
-        let kyb = InlineKeyboardMarkup::new([[KeyData::main_menu_btn()]]);
+        let lang = self.lang();
+        let kyb = InlineKeyboardMarkup::new([[KeyData::main_menu_btn(lang)]]);
         let Some(msg) = self.settings.donate_msg else {
             self.bot
-                .send_message(self.cid, "پیام حمایت مالی پیدا نشد 😥")
+                .send_message(self.cid, t!(lang, "donate_not_found"))
                 .reply_markup(kyb.clone())
                 .await?;
             return Ok(());
@@ -134,53 +216,629 @@ impl Session {
     }
 
     pub async fn flyer_btn(&mut self) -> Option<InlineKeyboardButton> {
-        let mut flyer = Flyer::get_good_link(&self.ctx).await?;
+        let mut flyer =
+            Flyer::get_good_link(&self.ctx, &self.karbar, self.now).await?;
 
-        let u = flyer.link.and_then(|v| reqwest::Url::from_str(&v).ok());
-        let Some(url) = u else {
-            flyer.link = None;
-            let _ = flyer.set(&self.ctx).await;
+        let u =
+            flyer.link.as_ref().and_then(|v| reqwest::Url::from_str(v).ok());
+        if u.is_none() {
+            let _ = flyer.unlink(&self.ctx).await;
+            return None;
+        };
+
+        // the button counts as an impression like the message flyers
+        if !flyer.view(&self.ctx, self.karbar.tid, self.now).await.ok()? {
             return None;
+        }
+
+        Some(InlineKeyboardButton::callback(
+            flyer.label,
+            KeyData::FlyerClick(flyer.id),
+        ))
+    }
+
+    pub async fn flyer_click(&mut self, id: i64) -> HR {
+        let Ok(mut flyer) = Flyer::get(&self.ctx, id).await else {
+            self.notify(t!(self.lang(), "flyer_gone")).await?;
+            return Ok(());
+        };
+
+        let u =
+            flyer.link.as_ref().and_then(|v| reqwest::Url::from_str(v).ok());
+        let Some(url) = u else {
+            self.notify(t!(self.lang(), "flyer_no_link")).await?;
+            return Ok(());
+        };
+
+        flyer.click(&self.ctx, self.karbar.tid, self.now).await?;
+
+        let kb = InlineKeyboardMarkup::new([
+            vec![InlineKeyboardButton::url(&flyer.label, url)],
+            vec![KeyData::main_menu_btn(self.lang())],
+        ]);
+        let m = format!("🔗 {}", flyer.label);
+        self.bot.send_message(self.cid, m).reply_markup(kb).await?;
+
+        Ok(())
+    }
+
+    /// views and clicks of the last 7 days
+    pub async fn flyer_report(&self, flyer: &Flyer) -> Result<String, AppErr> {
+        let today = self.now / 86400;
+        let rp = Flyer::report(&self.ctx, flyer.id, today - 6).await?;
+
+        let mut m = t!(
+            self.lang(),
+            "flyer_report",
+            label = flyer.label,
+            views = flyer.views,
+            clicks = flyer.clicks,
+            ctr = format!("{:.2}", flyer.ctr()),
+            viewers = rp.viewers,
+            clickers = rp.clickers,
+        );
+        for d in rp.days.iter() {
+            let ctr = Flyer::rate(d.clicks, d.views);
+            m += &format!(
+                "{} | 👁 {} | 👆 {} | {ctr:.2}%\n",
+                fmt_date(d.day * 86400),
+                d.views,
+                d.clicks
+            );
+        }
+        if rp.days.is_empty() {
+            m += "---";
+        }
+
+        Ok(m)
+    }
+
+    pub async fn my_ads(&self) -> HR {
+        let lang = self.lang();
+        let flyers = Flyer::list_owner(&self.ctx, self.karbar.tid).await?;
+        let mut m = String::from(t!(lang, "my_ads"));
+        let mut kyb = Vec::with_capacity(flyers.len() + 2);
+        for f in flyers.iter() {
+            m += &format!(
+                "🔹 {} | {} | 👁 {} | 👆 {}\n",
+                f.label,
+                f.status_str(lang),
+                f.views,
+                f.clicks
+            );
+            kyb.push(vec![InlineKeyboardButton::callback(
+                &f.label,
+                KeyData::MyAd(f.id),
+            )]);
+        }
+        kyb.push(vec![InlineKeyboardButton::callback(
+            t!(lang, "btn_ad_new"),
+            KeyData::AdNew,
+        )]);
+        kyb.push(vec![KeyData::main_menu_btn(lang)]);
+
+        self.bot
+            .send_message(self.cid, m)
+            .reply_markup(InlineKeyboardMarkup::new(kyb))
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn my_ad(&self, id: i64) -> HR {
+        let lang = self.lang();
+        let flyer = Flyer::get(&self.ctx, id).await?;
+        if flyer.owner != Some(self.karbar.tid) {
+            self.notify(t!(lang, "ad_not_found")).await?;
+            return Ok(());
+        }
+
+        let mid = MessageId(flyer.mid as i32);
+        self.bot.copy_message(self.cid, self.conf.dev, mid).await?;
+
+        let mut m = self.flyer_report(&flyer).await?;
+        m += "\n\n";
+        m += &t!(
+            lang,
+            "ad_info",
+            status = flyer.status_str(lang),
+            left = flyer.max_views - flyer.views
+        );
+        if let Some(reason) = &flyer.reason {
+            m += "\n";
+            m += &t!(lang, "ad_reason", reason = reason);
+        }
+
+        let mut kyb = if flyer.payable() {
+            self.ad_packages(flyer.id)
+        } else {
+            Vec::new()
+        };
+        kyb.push(vec![
+            InlineKeyboardButton::callback(
+                t!(lang, "btn_back"),
+                KeyData::MyAds,
+            ),
+            KeyData::main_menu_btn(lang),
+        ]);
+
+        self.bot
+            .send_message(self.cid, m)
+            .reply_markup(InlineKeyboardMarkup::new(kyb))
+            .await?;
+
+        Ok(())
+    }
+
+    fn ad_packages(&self, id: i64) -> Vec<Vec<InlineKeyboardButton>> {
+        let price = self.settings.ad_star_price.max(1) as u32;
+        let mut kyb = Vec::with_capacity(Config::AD_PACKAGES.len() + 1);
+        for (idx, views) in Config::AD_PACKAGES.iter().enumerate() {
+            let stars = (views / 1000 * price).max(1);
+            kyb.push(vec![InlineKeyboardButton::callback(
+                t!(self.lang(), "ad_package", views = views, stars = stars),
+                KeyData::AdBuy(id, idx as u32),
+            )]);
+        }
+        kyb
+    }
+
+    pub async fn ad_new(&self) -> HR {
+        self.store.update(State::AdLabel).await?;
+        self.notify(t!(self.lang(), "ad_new")).await?;
+        Ok(())
+    }
+
+    pub async fn ad_buy(&self, id: i64, pkg: u32) -> HR {
+        let lang = self.lang();
+        let flyer = Flyer::get(&self.ctx, id).await?;
+        let views = Config::AD_PACKAGES.get(pkg as usize);
+        let mine = flyer.owner == Some(self.karbar.tid);
+        let (Some(views), true) = (views, mine && flyer.payable()) else {
+            self.notify(t!(lang, "ad_not_found")).await?;
+            return Ok(());
+        };
+
+        let price = self.settings.ad_star_price.max(1) as u32;
+        let stars = (views / 1000 * price).max(1);
+        self.bot
+            .send_invoice(
+                self.cid,
+                t!(lang, "ad_invoice_title", views = views),
+                t!(lang, "ad_invoice_desc", views = views, label = flyer.label),
+                format!("ad-{}-{views}", flyer.id),
+                "XTR",
+                [LabeledPrice::new("ad", stars)],
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    /// the advertiser paid for a view package. `payload` is `{id}-{views}`
+    pub async fn ad_paid(
+        &mut self, payload: &str, sp: &SuccessfulPayment,
+    ) -> HR {
+        let mut it = payload.splitn(2, '-');
+        let id = it.next().and_then(|v| v.parse::<i64>().ok());
+        let views = it.next().and_then(|v| v.parse::<i64>().ok());
+        let (Some(id), Some(views)) = (id, views) else {
+            log::error!("bad ad payload: {payload}");
+            return Ok(());
+        };
+
+        let mut flyer = Flyer::get(&self.ctx, id).await?;
+        flyer.max_views = flyer.max_views.max(flyer.views) + views;
+        let (charge, stars) =
+            (&sp.telegram_payment_charge_id.0, sp.total_amount as i64);
+        flyer.charge_add(&self.ctx, charge, stars, views, self.now).await?;
+        flyer.disabled = false;
+        let review = flyer.status != Flyer::STATUS_ACTIVE;
+        if review {
+            flyer.status = Flyer::STATUS_PENDING;
+            flyer.reason = None;
+        }
+        flyer.set(&self.ctx).await?;
+
+        let lang = self.lang();
+        let m = if review {
+            t!(lang, "ad_paid_review")
+        } else {
+            t!(lang, "ad_paid_added")
+        };
+        let kb = InlineKeyboardMarkup::new([[
+            InlineKeyboardButton::callback(
+                t!(lang, "btn_my_ads"),
+                KeyData::MyAds,
+            ),
+            KeyData::main_menu_btn(lang),
+        ]]);
+        self.bot.send_message(self.cid, m).reply_markup(kb).await?;
+
+        if review {
+            for admin in self.conf.admins.iter() {
+                if let Err(e) = self.ad_review(*admin, &flyer).await {
+                    log::warn!("ad review to {admin} failed: {e:?}");
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    pub async fn submit_new(&self) -> HR {
+        let lang = self.lang();
+        let good = Config::SUBMIT_GOOD_VOTES;
+        let stats = Submission::stats(&self.ctx, self.karbar.tid).await?;
+        let m = t!(
+            lang,
+            "submit_new",
+            reward = self.settings.submit_reward,
+            vote_reward = self.settings.submit_vote_reward,
+            votes = good,
+            approved = stats.approved,
+            rejected = stats.rejected,
+            pending = stats.pending
+        );
+        self.store.update(State::SubmitProxy).await?;
+        self.notify(&m).await?;
+        Ok(())
+    }
+
+    /// check the proxy link of the karbar and queue it for review
+    pub async fn submit_proxy(&self, link: &str) -> HR {
+        let lang = self.lang();
+        let Some(px) = Proxy::from_link(link) else {
+            return self.notify(t!(lang, "submit_bad_link")).await;
+        };
+        self.store.update(State::Menu).await?;
+
+        let kid = self.karbar.tid;
+        let stats = Submission::stats(&self.ctx, kid).await?;
+        if stats.trust() < Config::SUBMIT_MIN_TRUST {
+            return self.notify(t!(lang, "submit_blocked")).await;
+        }
+        if stats.pending >= Config::SUBMIT_MAX_PENDING {
+            return self.notify(t!(lang, "submit_too_many")).await;
+        }
+        if Submission::exists(&self.ctx, &px).await? {
+            return self.notify(t!(lang, "submit_exists")).await;
+        }
+
+        self.bot.send_message(self.cid, t!(lang, "submit_checking")).await?;
+        let Some(ping) = px.ping().await else {
+            return self.notify(t!(lang, "submit_unreachable")).await;
+        };
+
+        let mut sub = Submission::new(kid, &px, ping, self.now);
+        sub.add(&self.ctx).await?;
+        self.notify(&t!(lang, "submit_queued", ping = ping)).await?;
+
+        for admin in self.conf.admins.iter() {
+            if let Err(e) = self.submit_review(*admin, &sub).await {
+                log::warn!("submit review to {admin} failed: {e:?}");
+            }
+        }
+
+        Ok(())
+    }
+
+    /// send a submitted proxy to an admin for approval
+    pub async fn submit_review(&self, admin: UserId, sub: &Submission) -> HR {
+        let stats = Submission::stats(&self.ctx, sub.karbar).await?;
+        let m = indoc::formatdoc!(
+            "🛡 پروکسی ارسالی برای بررسی
+
+            <a href=\"{}\">{}:{}</a>
+            پینگ: {}ms
+            ارسال کننده: <code>{}</code>
+            تایید شده: {} | رد شده: {} | رای خوب: {}
+            اعتبار ارسال کننده: {:.2}",
+            escape(&sub.to_proxy().url()),
+            escape(&sub.server),
+            escape(&sub.port),
+            sub.ping,
+            sub.karbar,
+            stats.approved,
+            stats.rejected,
+            stats.good,
+            stats.trust()
+        );
+        let kb = InlineKeyboardMarkup::new([[
+            InlineKeyboardButton::callback(
+                "تایید ✅",
+                kd!(ag, Ag::SubmitApprove(sub.id)),
+            ),
+            InlineKeyboardButton::callback(
+                "رد ❌",
+                kd!(ag, Ag::SubmitReject(sub.id)),
+            ),
+        ]]);
+        self.bot
+            .send_message(admin, m)
+            .parse_mode(ParseMode::Html)
+            .disable_link_preview(true)
+            .reply_markup(kb)
+            .await?;
+
+        Ok(())
+    }
+
+    /// approve or reject a submission and tell the submitter
+    pub async fn submit_decide(&self, id: i64, approve: bool) -> HR {
+        let mut sub = Submission::get(&self.ctx, id).await?;
+        let status = if approve {
+            Submission::STATUS_APPROVED
+        } else {
+            Submission::STATUS_REJECTED
+        };
+        // two admins may press at once, only one takes it
+        if !Submission::decide(&self.ctx, id, status).await? {
+            return self.notify("این پروکسی قبلا بررسی شده است 🍏").await;
+        }
+        sub.status = status;
+
+        let mut added = false;
+        if approve {
+            let mut px = sub.to_proxy();
+            match px.add(&self.ctx).await {
+                Ok(()) => {
+                    added = true;
+                    sub.proxy = Some(px.id);
+                }
+                Err(AppErr { worm: Worm::AlreadyExists, .. }) => {
+                    sub.status = Submission::STATUS_REJECTED;
+                }
+                Err(e) => {
+                    log::error!("submitted proxy add failed: {e:?}");
+                    sub.status = Submission::STATUS_PENDING;
+                    sub.set(&self.ctx).await?;
+                    let m = "افزودن پروکسی ناموفق بود، دوباره تلاش کنید ❌";
+                    return self.notify(m).await;
+                }
+            }
+            sub.set(&self.ctx).await?;
+        }
+
+        let reward = self.settings.submit_reward;
+        if added {
+            Karbar::add_points(&self.ctx, sub.karbar, reward).await?;
+        }
+
+        let lang = Karbar::find_with_tid(&self.ctx, sub.karbar)
+            .await
+            .map(|k| k.lang())
+            .unwrap_or_default();
+        let m = if added {
+            t!(lang, "submit_approved", points = reward)
+        } else {
+            t!(lang, "submit_rejected").to_string()
+        };
+        let _ = self.bot.send_message(ChatId(sub.karbar), m).await;
+
+        let m = match (approve, added) {
+            (true, true) => "پروکسی تایید و اضافه شد ✅",
+            (true, false) => "این پروکسی از قبل وجود دارد و رد شد ❌",
+            _ => "پروکسی رد شد ❌",
         };
+        self.notify(m).await
+    }
+
+    /// send the flyer to an admin for approval
+    pub async fn ad_review(&self, admin: UserId, flyer: &Flyer) -> HR {
+        let mid = MessageId(flyer.mid as i32);
+        self.bot.copy_message(admin, self.conf.dev, mid).await?;
+
+        let m = indoc::formatdoc!(
+            "📢 تبلیغ جدید برای بررسی
+
+            عنوان: {}
+            لینک: {}
+            بازدید خریداری شده: {}
+            تبلیغ دهنده: {}",
+            flyer.label,
+            flyer.link.as_deref().unwrap_or("---"),
+            flyer.max_views - flyer.views,
+            flyer.owner.unwrap_or_default(),
+        );
+        let kb = InlineKeyboardMarkup::new([[
+            InlineKeyboardButton::callback(
+                "تایید ✅",
+                kd!(ag, Ag::FlyerApprove(flyer.id)),
+            ),
+            InlineKeyboardButton::callback(
+                "رد ❌",
+                kd!(ag, Ag::FlyerReject(flyer.id)),
+            ),
+        ]]);
+        self.bot
+            .send_message(admin, m)
+            .disable_link_preview(true)
+            .reply_markup(kb)
+            .await?;
+
+        Ok(())
+    }
+
+    /// spend points on `product`, `confirmed` is the cost the karbar accepted
+    pub async fn buy(
+        &mut self, product: Product, confirmed: Option<i64>,
+    ) -> HR {
+        match product {
+            Product::Proxy => self.get_proxy(confirmed).await,
+            Product::V2ray => self.get_v2ray(confirmed).await,
+            Product::Vip => self.get_vip(confirmed).await,
+            Product::Bundle => self.get_proxy_bundle(confirmed).await,
+        }
+    }
+
+    /// ask the karbar before paying `cost`. returns true when asked.
+    /// a confirmed cost lower than the current one asks again
+    async fn confirm_buy(
+        &self, product: Product, cost: i64, confirmed: Option<i64>,
+    ) -> Result<bool, AppErr> {
+        match confirmed {
+            Some(c) if cost <= c => return Ok(false),
+            None if self.karbar.skip_confirm => return Ok(false),
+            _ => {}
+        }
+
+        let lang = self.lang();
+        let base = product.cost(&self.settings);
+        let m = t!(
+            lang,
+            "confirm_buy",
+            product = product.label(lang),
+            points = self.karbar.points,
+            base = base,
+            surge = cost - base,
+            cost = cost
+        );
+        let kb = InlineKeyboardMarkup::new([
+            vec![
+                InlineKeyboardButton::callback(
+                    t!(lang, "btn_confirm"),
+                    KeyData::Buy(product, cost),
+                ),
+                InlineKeyboardButton::callback(
+                    t!(lang, "btn_cancel"),
+                    KeyData::Menu,
+                ),
+            ],
+            vec![InlineKeyboardButton::callback(
+                t!(lang, "btn_confirm_always"),
+                KeyData::BuyAlways(product, cost),
+            )],
+        ]);
+        self.bot.send_message(self.cid, m).reply_markup(kb).await?;
+
+        Ok(true)
+    }
 
-        Some(InlineKeyboardButton::url(flyer.label, url))
+    pub async fn skip_confirm_toggle(&mut self) -> HR {
+        self.karbar.skip_confirm = !self.karbar.skip_confirm;
+        self.karbar.set(&self.ctx).await?;
+        let m = if self.karbar.skip_confirm {
+            t!(self.lang(), "confirm_off")
+        } else {
+            t!(self.lang(), "confirm_on")
+        };
+        self.notify(m).await?;
+        Ok(())
     }
 
-    pub async fn get_vip(&mut self) -> HR {
+    pub async fn get_proxy_bundle(&mut self, confirmed: Option<i64>) -> HR {
+        let lang = self.lang();
         let cfj_btn = InlineKeyboardButton::callback(
-            "دریافت VIP بعد از عضویت ✅",
-            KeyData::GetVip,
+            t!(lang, "fj_proxy"),
+            KeyData::GetProxyBundle,
         );
         if self.check_force_join(cfj_btn).await? {
             return Ok(());
         }
 
-        let cost = self.karbar.calc_cost(self.settings.vip_cost);
+        let cost =
+            self.karbar.surge_cost(Product::Bundle, &self.settings, self.now);
         if self.karbar.points < cost {
-            let m = indoc::indoc!(
-                "❌ شما امتیاز کافی برای دریافت کانفیگ VIP ندارید.
+            let vars = [("cost", cost.to_string())];
+            let m = self.tpl("no_points_proxy", &vars).await;
+            self.notify_no_points(&m).await?;
+            return Ok(());
+        }
 
-            🔒 برای دسترسی به کانفیگ‌های ویژه، امتیاز بیشتری کسب کنید!
+        if self.confirm_buy(Product::Bundle, cost, confirmed).await? {
+            return Ok(());
+        }
 
-            📈 با فعالیت روزانه و دعوت از دوستان، امتیاز شما افزایش می‌یابد."
-            );
-            self.notify_no_points(m).await?;
+        let size = self.settings.bundle_size.max(1);
+        let pxs =
+            Proxy::get_good_many(&self.ctx, &self.karbar, self.now, size).await;
+        let mut kyb = pxs
+            .iter()
+            .enumerate()
+            .filter_map(|(i, px)| {
+                let url = reqwest::Url::from_str(&px.url()).ok()?;
+                let flag = px.country.as_deref().map(geoip::flag);
+                let label = t!(
+                    lang,
+                    "btn_bundle_proxy",
+                    n = i + 1,
+                    flag = flag.unwrap_or_default()
+                );
+                Some(vec![InlineKeyboardButton::url(label, url)])
+            })
+            .collect::<Vec<_>>();
+        let got = kyb.len() as i64;
+        if got == 0 {
+            let (_, active) = Proxy::count(&self.ctx).await?;
+            let m = if active > 0 {
+                t!(lang, "proxy_all_received")
+            } else {
+                t!(lang, "proxy_not_found")
+            };
+            self.notify(m).await?;
+            return Ok(());
+        }
+        kyb.push(vec![KeyData::main_menu_btn(lang), KeyData::donate_btn(lang)]);
+
+        // a short bundle only costs its share, rounded up
+        let cost =
+            if got < size { (cost * got + size - 1) / size } else { cost };
+        let mut m = t!(lang, "proxy_bundle", count = got);
+        if got < size {
+            m += "\n\n";
+            m += &t!(lang, "proxy_bundle_short", size = size, cost = cost);
+        }
+        self.bot
+            .send_message(self.cid, m)
+            .reply_markup(InlineKeyboardMarkup::new(kyb))
+            .await?;
+
+        self.karbar.points -= cost;
+        self.karbar.surge_add(Product::Bundle, &self.settings, self.now);
+        self.karbar.set(&self.ctx).await?;
+        for px in pxs.iter() {
+            Proxy::delivered(&self.ctx, self.karbar.tid, px.id, self.now)
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    pub async fn get_vip(&mut self, confirmed: Option<i64>) -> HR {
+        let lang = self.lang();
+        let cfj_btn =
+            InlineKeyboardButton::callback(t!(lang, "fj_vip"), KeyData::GetVip);
+        if self.check_force_join(cfj_btn).await? {
+            return Ok(());
+        }
+
+        let cost =
+            self.karbar.surge_cost(Product::Vip, &self.settings, self.now);
+        if self.karbar.points < cost {
+            let vars = [("cost", cost.to_string())];
+            let m = self.tpl("no_points_vip", &vars).await;
+            self.notify_no_points(&m).await?;
+            return Ok(());
+        }
+
+        if self.confirm_buy(Product::Vip, cost, confirmed).await? {
             return Ok(());
         }
 
         let Some(msg) = self.settings.vip_msg else {
             self.bot
-                .send_message(self.cid, "کانفیگ VIP پیدا نشد 😥")
-                .reply_markup(KeyData::main_menu())
+                .send_message(self.cid, t!(lang, "vip_not_found"))
+                .reply_markup(KeyData::main_menu(lang))
                 .await?;
             return Ok(());
         };
         let su = &self.conf.start_url;
         let mid = MessageId(msg as i32);
         let mut kyb = vec![vec![
-            InlineKeyboardButton::url("پروکسی رایگان", su.clone()),
-            InlineKeyboardButton::url("v2ray رایگان", su.clone()),
-            KeyData::donate_url(),
+            InlineKeyboardButton::url(t!(lang, "btn_free_proxy"), su.clone()),
+            InlineKeyboardButton::url(t!(lang, "btn_free_v2ray"), su.clone()),
+            KeyData::donate_url(lang),
         ]];
         if let Some(btn) = self.flyer_btn().await {
             kyb.push(vec![btn]);
@@ -191,29 +849,38 @@ impl Session {
             .await?;
 
         self.karbar.points -= cost;
+        self.karbar.surge_add(Product::Vip, &self.settings, self.now);
         self.karbar.set(&self.ctx).await?;
 
+        Karbar::vip_delivered(&self.ctx, self.karbar.tid, msg, self.now)
+            .await?;
+
         self.settings.vip_views += 1;
         self.settings.set(&self.ctx.db).await?;
 
         Ok(())
     }
 
-    pub async fn get_proxy(&mut self) -> HR {
+    pub async fn get_proxy(&mut self, confirmed: Option<i64>) -> HR {
+        let lang = self.lang();
         let cfj_btn = InlineKeyboardButton::callback(
-            "دریافت پروکسی بعد از عضویت ✅",
+            t!(lang, "fj_proxy"),
             KeyData::GetProxy,
         );
         if self.check_force_join(cfj_btn).await? {
             return Ok(());
         }
 
-        let cost = self.karbar.calc_cost(self.settings.proxy_cost);
+        let cost =
+            self.karbar.surge_cost(Product::Proxy, &self.settings, self.now);
         if self.karbar.points < cost {
-            self.notify_no_points(
-                "شما امتیاز کافی برای دریافت پروکسی ندارید 🐧",
-            )
-            .await?;
+            let vars = [("cost", cost.to_string())];
+            let m = self.tpl("no_points_proxy", &vars).await;
+            self.notify_no_points(&m).await?;
+            return Ok(());
+        }
+
+        if self.confirm_buy(Product::Proxy, cost, confirmed).await? {
             return Ok(());
         }
 
@@ -221,10 +888,21 @@ impl Session {
         let (px, purl) = loop {
             tries += 1;
             if tries > 6 {
-                self.notify("هیچ پروکسیی یافت نشد 😥").await?;
+                self.notify(t!(lang, "proxy_not_found")).await?;
                 return Ok(());
             }
-            let Some(px) = Proxy::get_good(&self.ctx).await else { continue };
+            let Some(px) =
+                Proxy::get_good(&self.ctx, &self.karbar, self.now).await
+            else {
+                let (_, active) = Proxy::count(&self.ctx).await?;
+                let m = if active > 0 {
+                    t!(lang, "proxy_all_received")
+                } else {
+                    t!(lang, "proxy_not_found")
+                };
+                self.notify(m).await?;
+                return Ok(());
+            };
             let Ok(purl) = reqwest::Url::from_str(&px.url()) else {
                 Proxy::disabled_toggle(&self.ctx, px.id).await?;
                 continue;
@@ -234,13 +912,16 @@ impl Session {
         };
 
         let mut kyb = vec![
-            vec![InlineKeyboardButton::url("فعال سازی پروکسی 👘", purl)],
+            vec![InlineKeyboardButton::url(
+                t!(lang, "btn_proxy_connect"),
+                purl,
+            )],
             vec![
                 InlineKeyboardButton::url(
-                    "دریافت پروکسی و v2ray رایگان 🍓",
+                    t!(lang, "btn_free_all"),
                     self.conf.start_url.clone(),
                 ),
-                KeyData::donate_url(),
+                KeyData::donate_url(lang),
             ],
         ];
         if let Some(btn) = self.flyer_btn().await {
@@ -249,46 +930,48 @@ impl Session {
         let kb = InlineKeyboardMarkup::new(kyb);
 
         let sent = 'a: {
-            let Some(mut flyer) = Flyer::get_good(&self.ctx).await else {
+            let Some(mut flyer) =
+                Flyer::get_good(&self.ctx, &self.karbar, self.now).await
+            else {
                 break 'a false;
             };
+            // counted before sending, so the caps hold for parallel sends
+            if !flyer
+                .view(&self.ctx, self.karbar.tid, self.now)
+                .await
+                .unwrap_or(false)
+            {
+                break 'a false;
+            }
             let m = MessageId(flyer.mid as i32);
             let (d, c) = (self.conf.dev, self.cid);
 
             let r = self.bot.copy_message(c, d, m).reply_markup(kb.clone());
 
             if r.await.is_err() {
-                flyer.disabled = true;
-                let _ = flyer.set(&self.ctx).await;
+                let _ = flyer.disable(&self.ctx).await;
                 break 'a false;
             }
 
-            flyer.views += 1;
-            let _ = flyer.set(&self.ctx).await;
-
             true
         };
 
         if !sent {
-            let m = "روی دکمه «فعال سازی پروکسی» کلیک کنید.👇";
+            let m = t!(lang, "proxy_hint");
             self.bot.send_message(self.cid, m).reply_markup(kb).await?;
         }
 
         self.karbar.points -= cost;
+        self.karbar.surge_add(Product::Proxy, &self.settings, self.now);
         self.karbar.set(&self.ctx).await?;
+        Proxy::delivered(&self.ctx, self.karbar.tid, px.id, self.now).await?;
 
         let vote = Proxy::vote_get(&self.ctx, self.karbar.tid, px.id).await;
         if vote.is_some() {
             return Ok(());
         }
 
-        let m = indoc::indoc!(
-            "🛡 لطفاً به این پروکسی رأی بدید:
-            👍 فعال بود و کار کرد؟ لایک کن
-            👎 کار نکرد یا قطع بود؟ دیسلایک کن
-            
-            ✅ رأی درست شما تعیین می‌کنه این پروکسی تو ربات بمونه یا حذف شه!"
-        );
+        let m = t!(lang, "proxy_vote");
 
         self.bot
             .send_message(self.cid, m)
@@ -303,73 +986,61 @@ impl Session {
                         KeyData::ProxyVote(px.id, -1),
                     ),
                 ],
-                [KeyData::main_menu_btn(), KeyData::donate_btn()],
+                [KeyData::main_menu_btn(lang), KeyData::donate_btn(lang)],
             ]))
             .await?;
 
         Ok(())
     }
 
-    pub async fn get_v2ray(&mut self) -> HR {
+    pub async fn get_v2ray(&mut self, confirmed: Option<i64>) -> HR {
+        let lang = self.lang();
         let cfj_btn = InlineKeyboardButton::callback(
-            "دریافت V2ray بعد از عضویت ✅",
+            t!(lang, "fj_v2ray"),
             KeyData::GetV2ray,
         );
         if self.check_force_join(cfj_btn).await? {
             return Ok(());
         }
 
-        let cost = self.karbar.calc_cost(self.settings.v2ray_cost);
+        let cost =
+            self.karbar.surge_cost(Product::V2ray, &self.settings, self.now);
         if self.karbar.points < cost {
-            self.notify_no_points(
-                "شما امتیاز کافی برای دریافت v2ray ندارید 🐧",
-            )
-            .await?;
+            let vars = [("cost", cost.to_string())];
+            let m = self.tpl("no_points_v2ray", &vars).await;
+            self.notify_no_points(&m).await?;
             return Ok(());
         }
 
-        let mut tries = 0u8;
-        let v2 = loop {
-            tries += 1;
-            if tries > 6 {
-                self.notify("هیچ کانفیگ v2ray یافت نشد 😥").await?;
-                return Ok(());
-            }
-            let Some(v2) = V2ray::get_good(&self.ctx).await else { continue };
-            break v2;
+        if self.confirm_buy(Product::V2ray, cost, confirmed).await? {
+            return Ok(());
+        }
+
+        let (kid, now) = (self.karbar.tid, self.now);
+        let Some(v2) = V2ray::get_good(&self.ctx, kid, now).await else {
+            let (_, active) = V2ray::count(&self.ctx).await?;
+            let m = if active > 0 {
+                t!(lang, "v2ray_all_received")
+            } else {
+                t!(lang, "v2ray_not_found")
+            };
+            self.notify(m).await?;
+            return Ok(());
         };
 
         let mut kyb = vec![vec![
             InlineKeyboardButton::url(
-                "دریافت پروکسی و v2ray رایگان 🍓",
+                t!(lang, "btn_free_all"),
                 self.conf.start_url.clone(),
             ),
-            KeyData::donate_url(),
+            KeyData::donate_url(lang),
         ]];
         if let Some(btn) = self.flyer_btn().await {
             kyb.push(vec![btn]);
         }
         let kb = InlineKeyboardMarkup::new(kyb);
 
-        let m = indoc::formatdoc!(
-            r#"<b>کانفیگ v2ray</b>
-
-            <code>{}</code>
-            
-            همه نت ها 
-            حجم 600 گیگ
-            
-            <a href="https://t.me/xixv2ray/40">آموزش وصل شدن</a>
-            
-            <a href="https://t.me/xixv2ray/44">برنامه برای اندروید</a>
-            
-            <a href="https://t.me/xixv2ray/43">برنامه برای آیفون</a>
-            
-            <a href="https://t.me/proxyxix">گروه پروکسی</a>
-            
-            «برای پایداری سرور ها به حمایت مالی شما نیاز داریم❤️»"#,
-            escape(&v2.link)
-        );
+        let m = self.tpl("v2ray_config", &[("link", v2.link.clone())]).await;
         self.bot
             .send_message(self.cid, m)
             .parse_mode(ParseMode::Html)
@@ -378,7 +1049,9 @@ impl Session {
             .await?;
 
         self.karbar.points -= cost;
+        self.karbar.surge_add(Product::V2ray, &self.settings, self.now);
         self.karbar.set(&self.ctx).await?;
+        V2ray::delivered(&self.ctx, self.karbar.tid, v2.id, self.now).await?;
 
         let vote = V2ray::vote_get(&self.ctx, self.karbar.tid, v2.id).await;
         if vote.is_some() {
@@ -396,16 +1069,10 @@ impl Session {
                     KeyData::V2rayVote(v2.id, -1),
                 ),
             ],
-            [KeyData::main_menu_btn(), KeyData::donate_btn()],
+            [KeyData::main_menu_btn(lang), KeyData::donate_btn(lang)],
         ];
 
-        let m = indoc::indoc!(
-            "📡 لطفاً به این کانفیگ V2Ray رأی بدید:
-            👍 فعال بود و کار کرد؟ لایک کن
-            👎 کار نکرد یا قطع بود؟ دیسلایک کن
-            
-            ✅ رأی درست شما تعیین می‌کنه این کانفیگ تو ربات بمونه یا حذف شه!"
-        );
+        let m = t!(lang, "v2ray_vote");
 
         self.bot
             .send_message(self.cid, m)
@@ -422,22 +1089,13 @@ impl Session {
         );
         let rurl =
             reqwest::Url::from_str(&url).unwrap_or(self.conf.start_url.clone());
-        let msg = indoc::formatdoc!(
-            "🤖 ربات دریافت رایگان کانفیگ V2RAY و پروکسی
-            
-            🔹 کانفیگ‌های اختصاصی با پینگ تست‌شده ✅
-            🔹 پروکسی تلگرام پرسرعت 🟢
-            🔹 دسترسی به کانفیگ‌های VIP 👑
-            
-            
-            📥 دریافت از ربات:
-            🔗 {url}"
-        );
+        let lang = self.lang();
+        let msg = t!(lang, "invite", url = url);
 
         let kyb = [[
-            InlineKeyboardButton::url("پروکسی رایگان", rurl.clone()),
-            InlineKeyboardButton::url("v2ray رایگان", rurl.clone()),
-            KeyData::donate_url(),
+            InlineKeyboardButton::url(t!(lang, "btn_free_proxy"), rurl.clone()),
+            InlineKeyboardButton::url(t!(lang, "btn_free_v2ray"), rurl.clone()),
+            KeyData::donate_url(lang),
         ]];
         self.bot
             .send_message(self.cid, msg)
@@ -450,12 +1108,18 @@ impl Session {
 
     pub async fn buy_star_point(&mut self) -> HR {
         let sp = self.settings.star_point_price as u32;
+        let lang = self.lang();
 
         macro_rules! btn {
             ($star:literal) => {
                 InlineKeyboardButton::callback(
-                    format!("{} امتیاز 🍅 = {} استار ⭐", $star * sp, $star),
-                    KeyData::BuyStarPoints($star)
+                    t!(
+                        lang,
+                        "star_price_btn",
+                        points = $star * sp,
+                        stars = $star
+                    ),
+                    KeyData::BuyStarPoints($star),
                 )
             };
         }
@@ -466,11 +1130,11 @@ impl Session {
             vec![btn!(25), btn!(30)],
             vec![btn!(35), btn!(40)],
             vec![btn!(45), btn!(50)],
-            vec![KeyData::main_menu_btn()],
+            vec![KeyData::main_menu_btn(lang)],
         ]);
 
         self.bot
-            .send_message(self.cid, "خرید امتیاز 🍅 با استار ⭐ تلگرام")
+            .send_message(self.cid, t!(lang, "star_prices"))
             .reply_markup(kyb)
             .await?;
 
@@ -478,36 +1142,47 @@ impl Session {
     }
 
     pub async fn get_free_point(&mut self) -> HR {
+        let lang = self.lang();
         let kyb1 = [InlineKeyboardButton::callback(
-            "دریافت امتیاز 🍅",
+            t!(lang, "btn_get_point"),
             KeyData::GetRealFreePoints,
         )];
         let sent = 'a: {
-            let Some(mut flyer) = Flyer::get_good(&self.ctx).await else {
+            let Some(mut flyer) =
+                Flyer::get_good(&self.ctx, &self.karbar, self.now).await
+            else {
                 break 'a false;
             };
+            // counted before sending, so the caps hold for parallel sends
+            if !flyer
+                .view(&self.ctx, self.karbar.tid, self.now)
+                .await
+                .unwrap_or(false)
+            {
+                break 'a false;
+            }
             let m = MessageId(flyer.mid as i32);
             let (d, c) = (self.conf.dev, self.cid);
 
             let mut krs = vec![kyb1];
 
-            let u = flyer.link.clone();
-            if let Some(lk) = u.and_then(|v| reqwest::Url::from_str(&v).ok()) {
-                krs.push([InlineKeyboardButton::url(&flyer.label, lk)]);
+            let u = flyer.link.as_ref();
+            if u.and_then(|v| reqwest::Url::from_str(v).ok()).is_some() {
+                krs.push([InlineKeyboardButton::callback(
+                    &flyer.label,
+                    KeyData::FlyerClick(flyer.id),
+                )]);
             }
+            krs.push([KeyData::tasks_btn(lang)]);
 
             let kb = InlineKeyboardMarkup::new(krs);
             let r = self.bot.copy_message(c, d, m).reply_markup(kb);
 
             if r.await.is_err() {
-                flyer.disabled = true;
-                let _ = flyer.set(&self.ctx).await;
+                let _ = flyer.disable(&self.ctx).await;
                 break 'a false;
             }
 
-            flyer.views += 1;
-            let _ = flyer.set(&self.ctx).await;
-
             true
         };
 
@@ -519,81 +1194,206 @@ impl Session {
     }
 
     pub async fn get_real_free_point(&mut self) -> HR {
+        let lang = self.lang();
         let rem = self.now - self.karbar.last_free_point_at;
         if rem < self.settings.free_point_delay {
             let wait = self.settings.free_point_delay - rem;
             let (wm, wt) = if wait > 3600 {
-                ("ساعت", wait / 3600)
+                (t!(lang, "unit_hour"), wait / 3600)
             } else if wait > 60 {
-                ("دقیقه", wait / 60)
+                (t!(lang, "unit_minute"), wait / 60)
             } else {
-                ("ثانیه", wait)
+                (t!(lang, "unit_second"), wait)
             };
-            let msg =
-                format!("{wt} {wm} تا دریافت امتیاز رایگان باقی مانده است ⏳");
-            self.bot
-                .send_message(self.cid, msg)
-                .reply_markup(KeyData::main_menu())
-                .await?;
+            let msg = t!(lang, "free_point_wait", wait = wt, unit = wm);
+            let kb = InlineKeyboardMarkup::new([
+                vec![KeyData::tasks_btn(lang)],
+                vec![KeyData::main_menu_btn(lang), KeyData::donate_btn(lang)],
+            ]);
+            self.bot.send_message(self.cid, msg).reply_markup(kb).await?;
 
             return Ok(());
         }
 
-        self.karbar.points += self.settings.free_points;
-        self.karbar.last_free_point_at = self.now;
+        if self.captcha_needed(Purpose::FreePoint) {
+            return self.captcha_send(Purpose::FreePoint).await;
+        }
+
+        let added = self.karbar.streak_claim(&self.settings, self.now);
+        self.karbar.captcha_claims += 1;
         self.karbar.set(&self.ctx).await?;
 
-        let msg = indoc::formatdoc!(
-            "{} امتیاز به حساب شما اضافه شد! 🎉
-            امتیاز فعلی شما: {} 🍅",
-            self.settings.free_points,
-            self.karbar.points
+        let msg = t!(
+            lang,
+            "points_added",
+            added = added,
+            points = self.karbar.points
         );
+        let streak = self.karbar.streak;
+        let msg = msg
+            + "\n\n"
+            + &t!(
+                lang,
+                "streak_line",
+                streak = streak,
+                next = streak::reward(&self.settings, streak + 1)
+            );
 
         self.bot
             .send_message(self.cid, msg)
-            .reply_markup(KeyData::main_menu())
+            .reply_markup(KeyData::main_menu(lang))
             .await?;
 
         Ok(())
     }
 
-    pub async fn send_menu(&self) -> HR {
-        let menu_text = indoc::formatdoc!(
-            r#"🌍 «اینترنت آزاد حق همه مردمه» 
+    pub async fn tasks(&self) -> HR {
+        let lang = self.lang();
+        let tasks = Channel::tasks(&self.ctx, self.karbar.tid).await?;
+        if tasks.is_empty() {
+            self.notify(t!(lang, "tasks_empty")).await?;
+            return Ok(());
+        }
 
-            🍅 امتیاز شما: {}
+        let mut m = format!("{}\n\n", t!(lang, "tasks_title"));
+        let mut kyb = Vec::with_capacity(tasks.len() + 1);
+        for ch in tasks.iter() {
+            m += &t!(lang, "tasks_item", name = ch.name, amount = ch.amount);
+            m += "\n";
+            let mut row = vec![InlineKeyboardButton::callback(
+                t!(lang, "btn_task_check"),
+                KeyData::TaskCheck(ch.id),
+            )];
+            if let Ok(url) = reqwest::Url::from_str(&ch.url) {
+                row.insert(0, InlineKeyboardButton::url(&ch.name, url));
+            }
+            kyb.push(row);
+        }
+        m += "\n";
+        m += t!(lang, "tasks_warn");
+        kyb.push(vec![KeyData::main_menu_btn(lang)]);
 
-            👥 با دعوت از دوستان و دریافت امتیاز رایگان، امتیاز بیشتری دریافت کن!"#,
-            self.karbar.points,
+        self.bot
+            .send_message(self.cid, m)
+            .reply_markup(InlineKeyboardMarkup::new(kyb))
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn task_check(&mut self, id: i64) -> HR {
+        let lang = self.lang();
+        let Ok(mut ch) = Channel::get(&self.ctx, id).await else {
+            self.notify(t!(lang, "task_not_found")).await?;
+            return Ok(());
+        };
+
+        if !ch.enabled || (ch.max_sub > -1 && ch.joins >= ch.max_sub) {
+            self.notify(t!(lang, "task_full")).await?;
+            return Ok(());
+        }
+
+        if Channel::rewarded(&self.ctx, ch.id, self.karbar.tid).await {
+            self.notify(t!(lang, "task_rewarded")).await?;
+            return Ok(());
+        }
+
+        let (cid, uid) = (ChatId(ch.id), self.karbar.uid());
+        let joined = match self.bot.get_chat_member(cid, uid).await {
+            Ok(cm) => is_member(&cm.kind),
+            Err(e) => {
+                log::warn!("task check {cid} failed: {e:?}");
+                false
+            }
+        };
+
+        if !joined {
+            let kb = InlineKeyboardMarkup::new([
+                vec![KeyData::tasks_btn(lang)],
+                vec![KeyData::main_menu_btn(lang)],
+            ]);
+            let m = t!(lang, "task_not_joined");
+            self.bot.send_message(self.cid, m).reply_markup(kb).await?;
+            return Ok(());
+        }
+
+        ch.reward(&self.ctx, self.karbar.tid, self.now).await?;
+        self.karbar.points += ch.amount;
+
+        let m = format!(
+            "{}\n\n{}",
+            t!(
+                lang,
+                "points_added",
+                added = ch.amount,
+                points = self.karbar.points
+            ),
+            t!(lang, "task_claw_warn", days = ch.claw_days)
         );
+        let kb = InlineKeyboardMarkup::new([
+            vec![KeyData::tasks_btn(lang)],
+            vec![KeyData::main_menu_btn(lang)],
+        ]);
+        self.bot.send_message(self.cid, m).reply_markup(kb).await?;
+
+        Ok(())
+    }
+
+    pub async fn send_menu(&self) -> HR {
+        let lang = self.lang();
+        let menu_text = self.tpl("menu", &[]).await;
 
         let mut ikb = vec![
             vec![
                 InlineKeyboardButton::callback(
-                    "امتیاز رایگان 🍅",
+                    match self.karbar.streak_alive(&self.settings, self.now) {
+                        0 => t!(lang, "btn_m_free_point").to_string(),
+                        n => {
+                            format!("{} | 🔥 {n}", t!(lang, "btn_m_free_point"))
+                        }
+                    },
                     KeyData::GetFreePoints,
                 ),
+                self.price_btn(t!(lang, "btn_m_vip"), Product::Vip),
+            ],
+            vec![
+                self.price_btn(t!(lang, "btn_m_proxy"), Product::Proxy),
+                self.price_btn(t!(lang, "btn_m_v2ray"), Product::V2ray),
+            ],
+            vec![
+                self.price_btn(t!(lang, "btn_m_bundle"), Product::Bundle),
                 InlineKeyboardButton::callback(
-                    "کانفیگ VIP 💎",
-                    KeyData::GetVip,
+                    t!(lang, "btn_m_submit"),
+                    KeyData::SubmitProxy,
                 ),
             ],
             vec![
-                InlineKeyboardButton::callback("پروکسی 🛡", KeyData::GetProxy),
-                InlineKeyboardButton::callback("V2RAY ⚡️", KeyData::GetV2ray),
+                InlineKeyboardButton::callback(
+                    t!(lang, "btn_m_invite"),
+                    KeyData::MyInviteLinks,
+                ),
+                KeyData::donate_btn(lang),
             ],
             vec![
                 InlineKeyboardButton::callback(
-                    "دعوت دوستان و امتیاز گیری 🫂",
-                    KeyData::MyInviteLinks,
+                    t!(lang, "btn_m_star"),
+                    KeyData::StarPrices,
+                ),
+                InlineKeyboardButton::callback(
+                    t!(lang, "btn_m_ads"),
+                    KeyData::MyAds,
+                ),
+            ],
+            vec![
+                InlineKeyboardButton::callback(
+                    t!(lang, "btn_my_configs"),
+                    KeyData::MyConfigs,
+                ),
+                InlineKeyboardButton::callback(
+                    t!(lang, "btn_prefs"),
+                    KeyData::Prefs,
                 ),
-                KeyData::donate_btn(),
             ],
-            vec![InlineKeyboardButton::callback(
-                "خرید امتیاز با استار ⭐",
-                KeyData::StarPrices,
-            )],
         ];
 
         if self.karbar.is_admin() {
@@ -629,14 +1429,39 @@ impl Session {
                     kd!(gg, FlyerList),
                 ),
             ]);
-            ikb.push(vec![InlineKeyboardButton::callback(
-                "بروزرسانی خودکار v2ray 🤖",
-                kd!(gg, V2rayAudoUpdate),
-            )]);
+            ikb.push(vec![
+                InlineKeyboardButton::callback(
+                    "بروزرسانی خودکار v2ray 🤖",
+                    kd!(gg, V2rayAudoUpdate),
+                ),
+                InlineKeyboardButton::callback(
+                    "کانال های اسپانسر 📢",
+                    kd!(gg, ChannelList),
+                ),
+                InlineKeyboardButton::callback(
+                    "بررسی تبلیغات ⏳",
+                    kd!(gg, FlyerQueue),
+                ),
+            ]);
+            ikb.push(vec![
+                InlineKeyboardButton::callback(
+                    "قالب پیام ها 📝",
+                    kd!(gg, TemplateList),
+                ),
+                InlineKeyboardButton::callback(
+                    "رای های مشکوک 🕵️",
+                    kd!(gg, VoteClusters),
+                ),
+                InlineKeyboardButton::callback(
+                    "پروکسی های ارسالی 📥",
+                    kd!(gg, SubmitQueue),
+                ),
+            ]);
         }
 
         self.bot
             .send_message(self.karbar.cid(), menu_text)
+            .parse_mode(ParseMode::Html)
             .reply_markup(InlineKeyboardMarkup::new(ikb))
             .await?;
 
@@ -645,39 +1470,651 @@ impl Session {
         Ok(())
     }
 
-    pub async fn send_welcome(&self) -> HR {
-        let msg = "آماده‌ی خدمات‌رسانی ۲۴ ساعته به شما هستیم! 🕒✨";
+    /// menu button of `product` with its current surge cost
+    fn price_btn(&self, label: &str, product: Product) -> InlineKeyboardButton {
+        let cost = self.karbar.surge_cost(product, &self.settings, self.now);
+        let kd = match product {
+            Product::Proxy => KeyData::GetProxy,
+            Product::V2ray => KeyData::GetV2ray,
+            Product::Vip => KeyData::GetVip,
+            Product::Bundle => KeyData::GetProxyBundle,
+        };
+        InlineKeyboardButton::callback(format!("{label} | {cost} 🍅"), kd)
+    }
 
-        let kkb = [
+    /// the karbar must solve a captcha before `purpose`
+    pub fn captcha_needed(&self, purpose: Purpose) -> bool {
+        let st = &self.settings;
+        if !st.captcha_enabled {
+            return false;
+        }
+        if !self.karbar.verified {
+            return true;
+        }
+        match purpose {
+            Purpose::Start => false,
+            Purpose::FreePoint => {
+                st.captcha_every > 0
+                    && self.karbar.captcha_claims >= st.captcha_every
+            }
+        }
+    }
+
+    pub async fn captcha_send(&self, purpose: Purpose) -> HR {
+        let lang = self.lang();
+        if self.karbar.captcha_locked_until > self.now {
+            let wait = (self.karbar.captcha_locked_until - self.now + 59) / 60;
+            let m = t!(lang, "captcha_locked", minutes = wait);
+            self.bot.send_message(self.cid, m).await?;
+            return Ok(());
+        }
+
+        let ch = captcha::challenge(lang);
+        let (tid, answer) = (self.karbar.tid, ch.answer as i64);
+        Captcha::set(&self.ctx, tid, answer, purpose.code(), self.now).await?;
+
+        let btns = ch
+            .options
+            .into_iter()
+            .enumerate()
+            .map(|(i, o)| {
+                InlineKeyboardButton::callback(o, KeyData::Captcha(i as u8))
+            })
+            .collect::<Vec<_>>();
+        let kb = btns.chunks(3).map(|r| r.to_vec()).collect::<Vec<_>>();
+        self.bot
+            .send_message(self.cid, ch.question)
+            .reply_markup(InlineKeyboardMarkup::new(kb))
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn captcha_answer(&mut self, idx: u8) -> HR {
+        let lang = self.lang();
+        let cap = Captcha::get(&self.ctx, self.karbar.tid).await;
+        let purpose = cap
+            .as_ref()
+            .and_then(|c| Purpose::from_code(&c.purpose))
+            .unwrap_or(Purpose::Start);
+        if self.karbar.captcha_locked_until > self.now {
+            return self.captcha_send(purpose).await;
+        }
+        let cap = match cap {
+            Some(c) if c.created_at + Config::CAPTCHA_TTL >= self.now => c,
+            _ => {
+                let m = t!(lang, "captcha_expired");
+                self.bot.send_message(self.cid, m).await?;
+                return self.captcha_send(purpose).await;
+            }
+        };
+
+        if cap.answer != idx as i64 {
+            self.karbar.captcha_fails += 1;
+            let left = self.settings.captcha_tries - self.karbar.captcha_fails;
+            if left <= 0 {
+                self.karbar.captcha_fails = 0;
+                self.karbar.captcha_locked_until =
+                    self.now + self.settings.captcha_lockout;
+                Captcha::del(&self.ctx, self.karbar.tid).await?;
+            }
+            self.karbar.set(&self.ctx).await?;
+            if left > 0 {
+                let m = t!(lang, "captcha_wrong", left = left);
+                self.bot.send_message(self.cid, m).await?;
+            }
+            return self.captcha_send(purpose).await;
+        }
+
+        Captcha::del(&self.ctx, self.karbar.tid).await?;
+        let first = !self.karbar.verified;
+        self.karbar.verified = true;
+        self.karbar.captcha_fails = 0;
+        self.karbar.captcha_claims = 0;
+        self.karbar.set(&self.ctx).await?;
+        if let (true, Some(inviter)) = (first, self.karbar.invited_by) {
+            Karbar::invite_reward(&self.ctx, inviter).await?;
+        }
+
+        self.bot.send_message(self.cid, t!(lang, "captcha_ok")).await?;
+        match purpose {
+            Purpose::Start => {
+                self.send_welcome().await?;
+                self.send_menu().await?;
+            }
+            Purpose::FreePoint => self.get_real_free_point().await?,
+        }
+
+        Ok(())
+    }
+
+    pub async fn admin_captcha(&self) -> HR {
+        let st = &self.settings;
+        let m = indoc::formatdoc!(
+            "کپچا 🤖
+
+            کاربران جدید قبل از شروع و همه کاربران هر {} پاداش رایگان
+            باید به یک سوال ساده پاسخ دهند.
+            پاداش دعوت بعد از پاسخ درست کاربر جدید پرداخت می شود.
+
+            بعد از {} پاسخ اشتباه، کاربر {} ثانیه قفل می شود.",
+            st.captcha_every,
+            st.captcha_tries,
+            st.captcha_lockout,
+        );
+
+        let toggle = if st.captcha_enabled {
+            "کپچا: روشن ✅"
+        } else {
+            "کپچا: خاموش ❌"
+        };
+        let kb = InlineKeyboardMarkup::new([
             vec![
-                KeyboardButton::new(keyboard::GET_PROXY),
-                KeyboardButton::new(keyboard::GET_V2RAY),
-                KeyboardButton::new(keyboard::GET_VIP),
+                InlineKeyboardButton::callback(toggle, kd!(gg, CaptchaToggle)),
+                InlineKeyboardButton::callback(
+                    format!("هر {} پاداش", st.captcha_every),
+                    kd!(gg, SetCaptchaEvery),
+                ),
             ],
             vec![
-                KeyboardButton::new(keyboard::FREE_PONT),
-                KeyboardButton::new(keyboard::INVITE),
-                KeyboardButton::new(keyboard::MENU),
+                InlineKeyboardButton::callback(
+                    format!("خطای مجاز {}", st.captcha_tries),
+                    kd!(gg, SetCaptchaTries),
+                ),
+                InlineKeyboardButton::callback(
+                    format!("زمان قفل {}", st.captcha_lockout),
+                    kd!(gg, SetCaptchaLockout),
+                ),
+            ],
+            vec![KeyData::main_menu_btn(Lang::Fa)],
+        ]);
+        self.bot.send_message(self.cid, m).reply_markup(kb).await?;
+
+        Ok(())
+    }
+
+    /// a fresh backup of both databases as documents
+    pub async fn owner_backup(&self) -> HR {
+        let bk = backup::create(&self.ctx).await?;
+        let m = format!("backup {} | {} 💾", fmt_date(bk.at), bk.at);
+        for path in [bk.main, bk.storage] {
+            self.bot
+                .send_document(self.cid, InputFile::file(path))
+                .caption(m.clone())
+                .await?;
+        }
+        Ok(())
+    }
+
+    pub async fn owner_restore(&self) -> HR {
+        let m = indoc::indoc!(
+            "فایل main.db یک بکاپ را ارسال کنید 📥
+
+            فقط دیتابیس اصلی بازگردانی می شود. سلامت و نسخه فایل قبل از
+            جایگزینی بررسی می شود."
+        );
+        self.store.update(State::OwnerRestore).await?;
+        self.notify(m).await
+    }
+
+    /// stage the checked upload and restart to swap it in
+    pub async fn owner_restore_confirm(&self) -> HR {
+        if self.karbar.uid() != self.conf.dev {
+            return Ok(());
+        }
+
+        let upload = backup::upload();
+        if !upload.exists() {
+            return self.notify("فایلی برای بازگردانی نیست 🤡").await;
+        }
+        if let Err(e) = backup::check(&self.ctx, &upload).await {
+            return self.notify(&format!("{e} ❌")).await;
+        }
+
+        tokio::fs::copy(&upload, backup::staged()).await?;
+        tokio::fs::remove_file(&upload).await?;
+        self.notify("بازگردانی آماده شد، ربات دوباره راه اندازی می شود 🔄")
+            .await?;
+
+        // systemd restarts the bot and the restore is applied on start
+        log::warn!("exit to apply the staged restore");
+        std::process::exit(0);
+    }
+
+    /// formats and filters to export a pool
+    pub async fn admin_export_menu(&self, pool: Pool) -> HR {
+        let kyb = Filter::ALL.map(|filter| {
+            Format::EXPORT.map(|format| {
+                InlineKeyboardButton::callback(
+                    format!("{} {}", filter.name(), format.name()),
+                    kd!(ag, Ag::Export(pool, format, filter)),
+                )
+            })
+        });
+        let kyb = InlineKeyboardMarkup::new(kyb)
+            .append_row([KeyData::main_menu_btn(Lang::Fa)]);
+
+        let m = indoc::formatdoc!(
+            "فرمت و فیلتر خروجی را انتخاب کنید ⬇️
+
+            فعال: بدون موارد غیرفعال
+            خوب: فعال با امتیاز حداقل {:.2}",
+            vote::neutral()
+        );
+        self.bot.send_message(self.cid, m).reply_markup(kyb).await?;
+        Ok(())
+    }
+
+    /// send the pool as a document
+    pub async fn admin_export(
+        &self, pool: Pool, format: Format, filter: Filter,
+    ) -> HR {
+        let (data, count, name) = match pool {
+            Pool::Proxy => {
+                let items = Proxy::export(&self.ctx, filter).await?;
+                (bulk::export_proxies(&items, format), items.len(), "proxies")
+            }
+            Pool::V2ray => {
+                let items = V2ray::export(&self.ctx, filter).await?;
+                (bulk::export_v2rays(&items, format), items.len(), "v2rays")
+            }
+        };
+        if count == 0 {
+            return self.notify("موردی برای خروجی پیدا نشد 🍃").await;
+        }
+
+        let ext = match format {
+            Format::Csv => "csv",
+            Format::Json => "json",
+            _ => "txt",
+        };
+        let name = format!("{name}-{}.{ext}", fmt_date(self.now));
+        let file = InputFile::memory(data.into_bytes()).file_name(name);
+        let m = format!("{count} مورد | {} | {}", filter.name(), format.name());
+        self.bot
+            .send_document(self.cid, file)
+            .caption(m)
+            .reply_markup(KeyData::main_menu(Lang::Fa))
+            .await?;
+
+        Ok(())
+    }
+
+    /// add the new links of a checked import
+    pub async fn admin_import_commit(
+        &self, pool: Pool, links: &[String],
+    ) -> HR {
+        let added = bulk::commit(&self.ctx, pool, links).await;
+        self.store.update(State::Menu).await?;
+        let m = format!("{added} از {} مورد اضافه شد ✅", links.len());
+        self.notify(&m).await
+    }
+
+    /// items with many light down votes in the last day
+    pub async fn admin_vote_clusters(&self) -> HR {
+        let since = self.now - 86400;
+        let pxs = Proxy::vote_clusters(&self.ctx, since).await?;
+        let v2s = V2ray::vote_clusters(&self.ctx, since).await?;
+
+        let mut m = indoc::formatdoc!(
+            "رای های مشکوک 🕵️
+
+            پروکسی و v2ray هایی که در 24 ساعت گذشته حداقل {}
+            رای منفی از کاربران کم اعتبار (زیر {}) گرفته اند:
+
+            ",
+            Config::VOTE_CLUSTER_MIN,
+            Config::VOTE_SUSPICIOUS
+        );
+        if pxs.is_empty() && v2s.is_empty() {
+            m += "موردی پیدا نشد ✅";
+        }
+        let all =
+            pxs.iter().map(|c| ("🛡", c)).chain(v2s.iter().map(|c| ("⚡️", c)));
+        for (icon, c) in all {
+            let spread = (c.last - c.first) / 3600;
+            m += &indoc::formatdoc!(
+                "{icon} #{}: {} رای، ساخت حساب ها در {spread} ساعت
+                <code>{}</code>
+
+                ",
+                c.item,
+                c.votes,
+                c.voters
+            );
+        }
+
+        self.bot
+            .send_message(self.cid, m)
+            .parse_mode(ParseMode::Html)
+            .reply_markup(KeyData::main_menu(Lang::Fa))
+            .await?;
+
+        Ok(())
+    }
+
+    /// surge curves and a preview table of the costs
+    pub async fn admin_surge(&self) -> HR {
+        let st = &self.settings;
+        let curves = Product::ALL.map(|p| p.curve(st));
+        let steps = curves.iter().map(|c| c.len()).max().unwrap_or_default();
+
+        let mut m = indoc::formatdoc!(
+            "افزایش قیمت 📈
+
+            هر درخواست قیمت درخواست بعدی را طبق منحنی زیاد می کند
+            و هر {} ثانیه یک پله کم می شود.
+
+            پروکسی: {}
+            v2ray: {}
+            VIP: {}
+
+            <pre>پله | پروکسی | v2ray | VIP
+            ",
+            st.surge_decay,
+            st.proxy_surge,
+            st.v2ray_surge,
+            st.vip_surge,
+        );
+        for stack in 0..=steps as i64 {
+            let mut row = format!("{:>3}", stack + 1);
+            for (p, c) in Product::ALL.iter().zip(curves.iter()) {
+                row += &format!(" | {:>6}", surge::price(p.cost(st), c, stack));
+            }
+            m += &row;
+            m += "\n";
+        }
+        m += "</pre>";
+
+        let mut kyb = Product::ALL
+            .iter()
+            .map(|p| {
+                InlineKeyboardButton::callback(
+                    format!("منحنی {}", p.name()),
+                    kd!(ag, Ag::SurgeSet(*p)),
+                )
+            })
+            .collect::<Vec<_>>();
+        kyb.push(InlineKeyboardButton::callback(
+            format!("زمان کاهش {}", st.surge_decay),
+            kd!(ag, Ag::SetSurgeDecay),
+        ));
+        let kb = InlineKeyboardMarkup::new([
+            kyb,
+            vec![KeyData::main_menu_btn(Lang::Fa)],
+        ]);
+
+        self.bot
+            .send_message(self.cid, m)
+            .parse_mode(ParseMode::Html)
+            .reply_markup(kb)
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn send_welcome(&self) -> HR {
+        let lang = self.lang();
+        let msg = self.tpl("welcome", &[]).await;
+        let kb = |key: &'static str| KeyboardButton::new(tr(lang, key));
+
+        let kkb = [
+            vec![
+                kb(keyboard::GET_PROXY),
+                kb(keyboard::GET_V2RAY),
+                kb(keyboard::GET_VIP),
             ],
             vec![
-                KeyboardButton::new(keyboard::DONATE),
-                KeyboardButton::new(keyboard::BUY_STAR_POINT),
+                kb(keyboard::FREE_PONT),
+                kb(keyboard::INVITE),
+                kb(keyboard::MENU),
             ],
+            vec![kb(keyboard::DONATE), kb(keyboard::BUY_STAR_POINT)],
         ];
 
         let kyb = KeyboardMarkup::new(kkb).resize_keyboard();
 
-        self.bot.send_message(self.cid, msg).reply_markup(kyb).await?;
+        self.bot
+            .send_message(self.cid, msg)
+            .parse_mode(ParseMode::Html)
+            .reply_markup(kyb)
+            .await?;
 
         Ok(())
     }
 
+    pub async fn language(&self) -> HR {
+        let row = Lang::ALL
+            .iter()
+            .map(|l| {
+                InlineKeyboardButton::callback(l.name(), KeyData::SetLang(*l))
+            })
+            .collect::<Vec<_>>();
+        let kb = InlineKeyboardMarkup::new([
+            row,
+            vec![KeyData::main_menu_btn(self.lang())],
+        ]);
+        let m = t!(self.lang(), "language_pick");
+        self.bot.send_message(self.cid, m).reply_markup(kb).await?;
+        Ok(())
+    }
+
+    /// items received in the last [`Config::HISTORY`], sent again for free
+    pub async fn my_configs(&self) -> HR {
+        let lang = self.lang();
+        let (kid, since) = (self.karbar.tid, self.now - Config::HISTORY);
+        let mut kyb = Vec::new();
+
+        let vip = match self.settings.vip_msg {
+            Some(msg) => Karbar::vip_received(&self.ctx, kid, msg, since).await,
+            None => false,
+        };
+        if vip {
+            kyb.push(vec![InlineKeyboardButton::callback(
+                t!(lang, "product_vip"),
+                KeyData::ResendVip,
+            )]);
+        }
+        for px in Proxy::history(&self.ctx, kid, since).await? {
+            let flag = px.country.as_deref().map(geoip::flag);
+            kyb.push(vec![InlineKeyboardButton::callback(
+                format!("🛡 {}{}", flag.unwrap_or_default(), px.server),
+                KeyData::ResendProxy(px.id),
+            )]);
+        }
+        for v2 in V2ray::history(&self.ctx, kid, since).await? {
+            kyb.push(vec![InlineKeyboardButton::callback(
+                format!("⚡️ {}", v2.label),
+                KeyData::ResendV2ray(v2.id),
+            )]);
+        }
+
+        let m = if kyb.is_empty() {
+            t!(lang, "my_configs_empty", days = Config::HISTORY / 86400)
+        } else {
+            t!(lang, "my_configs", days = Config::HISTORY / 86400)
+        };
+        kyb.push(vec![KeyData::main_menu_btn(lang)]);
+        self.bot
+            .send_message(self.cid, m)
+            .reply_markup(InlineKeyboardMarkup::new(kyb))
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn resend_proxy(&self, id: i64) -> HR {
+        let lang = self.lang();
+        let since = self.now - Config::HISTORY;
+        let px = Proxy::history(&self.ctx, self.karbar.tid, since)
+            .await?
+            .into_iter()
+            .find(|px| px.id == id);
+        let Some(purl) = px.and_then(|px| reqwest::Url::parse(&px.url()).ok())
+        else {
+            return self.notify(t!(lang, "config_gone")).await;
+        };
+
+        let kb = InlineKeyboardMarkup::new([
+            vec![InlineKeyboardButton::url(
+                t!(lang, "btn_proxy_connect"),
+                purl,
+            )],
+            vec![KeyData::main_menu_btn(lang), KeyData::donate_btn(lang)],
+        ]);
+        let m = t!(lang, "proxy_hint");
+        self.bot.send_message(self.cid, m).reply_markup(kb).await?;
+
+        let vote = Proxy::vote_get(&self.ctx, self.karbar.tid, id).await;
+        let kb = InlineKeyboardMarkup::new([vote_row(vote, |k| {
+            KeyData::ProxyVote(id, k)
+        })]);
+        let m = t!(lang, "proxy_vote");
+        self.bot.send_message(self.cid, m).reply_markup(kb).await?;
+
+        Ok(())
+    }
+
+    pub async fn resend_v2ray(&self, id: i64) -> HR {
+        let lang = self.lang();
+        let since = self.now - Config::HISTORY;
+        let v2 = V2ray::history(&self.ctx, self.karbar.tid, since)
+            .await?
+            .into_iter()
+            .find(|v2| v2.id == id);
+        let Some(v2) = v2 else {
+            return self.notify(t!(lang, "config_gone")).await;
+        };
+
+        let m = self.tpl("v2ray_config", &[("link", v2.link)]).await;
+        self.bot
+            .send_message(self.cid, m)
+            .parse_mode(ParseMode::Html)
+            .disable_link_preview(true)
+            .reply_markup(KeyData::main_menu(lang))
+            .await?;
+
+        let vote = V2ray::vote_get(&self.ctx, self.karbar.tid, id).await;
+        let kb = InlineKeyboardMarkup::new([vote_row(vote, |k| {
+            KeyData::V2rayVote(id, k)
+        })]);
+        let m = t!(lang, "v2ray_vote");
+        self.bot.send_message(self.cid, m).reply_markup(kb).await?;
+
+        Ok(())
+    }
+
+    pub async fn resend_vip(&self) -> HR {
+        let lang = self.lang();
+        let (kid, since) = (self.karbar.tid, self.now - Config::HISTORY);
+        let msg = match self.settings.vip_msg {
+            Some(msg)
+                if Karbar::vip_received(&self.ctx, kid, msg, since).await =>
+            {
+                msg
+            }
+            _ => return self.notify(t!(lang, "config_gone")).await,
+        };
+
+        self.bot
+            .copy_message(self.cid, self.conf.dev, MessageId(msg as i32))
+            .reply_markup(KeyData::main_menu(lang))
+            .await?;
+
+        Ok(())
+    }
+
+    /// karbar preferences
+    pub async fn prefs(&self) -> HR {
+        let lang = self.lang();
+        let region = match self.karbar.region.as_deref() {
+            Some(r) => format!("{} {r}", geoip::flag(r)),
+            None => t!(lang, "region_any").to_string(),
+        };
+        let kb = InlineKeyboardMarkup::new([
+            vec![InlineKeyboardButton::callback(
+                t!(lang, "btn_language"),
+                KeyData::Language,
+            )],
+            vec![InlineKeyboardButton::callback(
+                if self.karbar.skip_confirm {
+                    t!(lang, "btn_confirm_off")
+                } else {
+                    t!(lang, "btn_confirm_on")
+                },
+                KeyData::SkipConfirmToggle,
+            )],
+            vec![InlineKeyboardButton::callback(
+                t!(lang, "btn_region", region = region),
+                KeyData::Region,
+            )],
+            vec![KeyData::main_menu_btn(lang)],
+        ]);
+        let m = t!(lang, "prefs");
+        self.bot.send_message(self.cid, m).reply_markup(kb).await?;
+        Ok(())
+    }
+
+    pub async fn region(&self) -> HR {
+        let lang = self.lang();
+        let mut kyb = vec![vec![InlineKeyboardButton::callback(
+            t!(lang, "region_any"),
+            KeyData::SetRegion(None),
+        )]];
+        let regions = Proxy::regions(&self.ctx).await?;
+        for chunk in regions.chunks(3) {
+            kyb.push(
+                chunk
+                    .iter()
+                    .filter_map(|(r, count)| {
+                        let code: [u8; 2] = r.as_bytes().try_into().ok()?;
+                        Some(InlineKeyboardButton::callback(
+                            format!("{} {r} ({count})", geoip::flag(r)),
+                            KeyData::SetRegion(Some(code)),
+                        ))
+                    })
+                    .collect(),
+            );
+        }
+        kyb.push(vec![InlineKeyboardButton::callback(
+            t!(lang, "btn_back"),
+            KeyData::Prefs,
+        )]);
+        let m = t!(lang, "region_pick");
+        self.bot
+            .send_message(self.cid, m)
+            .reply_markup(InlineKeyboardMarkup::new(kyb))
+            .await?;
+        Ok(())
+    }
+
+    pub async fn set_region(&mut self, region: Option<[u8; 2]>) -> HR {
+        self.karbar.region = region
+            .and_then(|r| String::from_utf8(r.to_vec()).ok())
+            .filter(|r| r.bytes().all(|b| b.is_ascii_uppercase()));
+        self.karbar.set(&self.ctx).await?;
+        self.prefs().await
+    }
+
+    pub async fn set_lang(&mut self, lang: Lang) -> HR {
+        self.karbar.lang = Some(lang.code().to_string());
+        self.karbar.set(&self.ctx).await?;
+
+        self.bot.send_message(self.cid, t!(lang, "language_set")).await?;
+        self.send_welcome().await?;
+        self.send_menu().await?;
+        Ok(())
+    }
+
     pub async fn send_karbar(&self, karbar: &Karbar) -> HR {
         fn bol(v: bool) -> &'static str {
             if v { "✅" } else { "❌" }
         }
 
         let kid = karbar.tid;
+        let rep = vote::reputation(&self.ctx, karbar, self.now).await?;
+        let rep = rep.map(|r| format!("{r:.2}")).unwrap_or(String::from("---"));
+        let subs = Submission::stats(&self.ctx, kid).await?;
 
         let m = indoc::formatdoc!(
             "نام: {}
@@ -686,6 +2123,8 @@ impl Session {
             بلاک کرده: {}
             ساخت حساب: {}
             اخرین فعالیت: {}
+            اعتبار رای: {rep}
+            پروکسی ارسالی: ✅ {} | ❌ {} | ⏳ {} | 👍 {}
 
             id: {kid}
             username: {}
@@ -696,6 +2135,10 @@ impl Session {
             bol(karbar.blocked),
             karbar.created_at,
             karbar.updated_at,
+            subs.approved,
+            subs.rejected,
+            subs.pending,
+            subs.good,
             karbar.username.as_deref().unwrap_or("---"),
             karbar.invite_code
         );
@@ -711,7 +2154,7 @@ impl Session {
                     kd!(ag, Ag::KarbarSetPoints(kid)),
                 ),
             ],
-            vec![KeyData::main_menu_btn()],
+            vec![KeyData::main_menu_btn(Lang::Fa)],
         ]);
 
         self.bot.send_message(self.cid, m).reply_markup(kyb).await?;
@@ -719,3 +2162,17 @@ impl Session {
         Ok(())
     }
 }
+
+/// up and down vote buttons, marking the current vote of the karbar
+fn vote_row(
+    vote: Option<i8>, data: impl Fn(i8) -> KeyData,
+) -> Vec<InlineKeyboardButton> {
+    let mark = |k: i8, icon: &str| match vote {
+        Some(v) if v == k => format!("✅ {icon}"),
+        _ => icon.to_string(),
+    };
+    vec![
+        InlineKeyboardButton::callback(mark(1, "👍"), data(1)),
+        InlineKeyboardButton::callback(mark(-1, "👎"), data(-1)),
+    ]
+}
@@ -1,4 +1,7 @@
+use crate::bulk::{Filter, Format, Pool};
 use crate::config::Config;
+use crate::i18n::{Lang, t};
+use crate::surge::Product;
 use teloxide::dispatching::dialogue;
 use teloxide::dispatching::dialogue::ErasedStorage;
 use teloxide::types::{InlineKeyboardButton, InlineKeyboardMarkup};
@@ -15,13 +18,41 @@ pub enum KeyData {
     GetProxy,
     GetVip,
     GetV2ray,
+    GetProxyBundle,
+    MyConfigs,
+    ResendProxy(i64),
+    ResendV2ray(i64),
+    ResendVip,
+    /// confirmed purchase with the accepted cost
+    Buy(Product, i64),
+    /// confirm and do not ask again
+    BuyAlways(Product, i64),
+    SkipConfirmToggle,
     MyInviteLinks,
     StarPrices,
     BuyStarPoints(u32),
     GetFreePoints,
     GetRealFreePoints,
+    Tasks,
+    TaskCheck(i64),
+    FlyerClick(i64),
+    MyAds,
+    MyAd(i64),
+    AdNew,
+    AdBuy(i64, u32),
+    SubmitProxy,
+    Language,
+    SetLang(Lang),
+    Prefs,
+    Region,
+    /// two letter country code, none for any
+    SetRegion(Option<[u8; 2]>),
+    /// captcha option index
+    Captcha(u8),
     ProxyVote(i64, i8),
     V2rayVote(i64, i8),
+    /// owner only, stage the uploaded database
+    RestoreConfirm,
     // admin global
     Ag(AdminGlobal),
 
@@ -30,24 +61,52 @@ pub enum KeyData {
     BookItem(u32, i64),
     BookAdd,
     BookDeleteAll,
+    BookExport,
 }
 
+/// reply keyboard buttons, the values are catalog keys
 pub mod keyboard {
-    pub const GET_PROXY: &str = "پروکسی";
-    pub const GET_VIP: &str = "کانفیگ VIP 🍓";
-    pub const GET_V2RAY: &str = "V2ray";
-    pub const FREE_PONT: &str = "امتیاز رایگان 🍅";
-    pub const INVITE: &str = "دعوت دوستان";
-    pub const MENU: &str = "منو";
-    pub const DONATE: &str = "حمایت مالی 💰";
-    pub const BUY_STAR_POINT: &str = "خرید امتیاز با استار ⭐";
+    use crate::i18n;
+
+    pub const GET_PROXY: &str = "kb_get_proxy";
+    pub const GET_VIP: &str = "kb_get_vip";
+    pub const GET_V2RAY: &str = "kb_get_v2ray";
+    pub const FREE_PONT: &str = "kb_free_point";
+    pub const INVITE: &str = "kb_invite";
+    pub const MENU: &str = "kb_menu";
+    pub const DONATE: &str = "kb_donate";
+    pub const BUY_STAR_POINT: &str = "kb_buy_star_point";
+
+    pub const ALL: [&str; 8] = [
+        GET_PROXY,
+        GET_VIP,
+        GET_V2RAY,
+        FREE_PONT,
+        INVITE,
+        MENU,
+        DONATE,
+        BUY_STAR_POINT,
+    ];
+
+    /// the button of `txt` in any language
+    pub fn key(txt: &str) -> Option<&'static str> {
+        i18n::key_of(&ALL, txt)
+    }
+
+    pub fn is_key(txt: &str) -> bool {
+        key(txt).is_some()
+    }
 }
 
 #[derive(Debug, serde::Serialize, serde::Deserialize, Clone, Copy)]
 #[serde(rename_all = "snake_case")]
 pub enum AdminGlobal {
     ForceJoinList,
+    ForceJoinFailToggle,
     KarbarFind,
+    VoteClusters,
+    Export(Pool, Format, Filter),
+    ImportCommit,
     KarbarSetPoints(i64),
     KarbarBanToggle(i64),
     SendAll,
@@ -77,6 +136,28 @@ pub enum AdminGlobal {
     SetVipMaxViews,
     SetVipMsg,
     SetDonateMsg,
+    SetAdStarPrice,
+    Surge,
+    SurgeSet(Product),
+    SetSurgeDecay,
+    SetStreakBonus,
+    SetStreakWindow,
+    Captcha,
+    CaptchaToggle,
+    SetCaptchaEvery,
+    SetCaptchaTries,
+    SetCaptchaLockout,
+    SetBundleSize,
+    SetBundleDiscount,
+    SetVoteHalfLife,
+    SetVoteMinWeight,
+    SetVoteDisablePct,
+    SetVoteWindow,
+    SetSubmitReward,
+    SetSubmitVoteReward,
+    SubmitQueue,
+    SubmitApprove(i64),
+    SubmitReject(i64),
 
     FlyerList,
     FlyerDel(u32, i64),
@@ -86,7 +167,31 @@ pub enum AdminGlobal {
     FlyerSetLink(u32, i64),
     FlyerSetLabel(u32, i64),
     FlyerDelLink(u32, i64),
+    FlyerSetSchedule(u32, i64),
+    FlyerSetDailyMax(u32, i64),
+    FlyerSetUserCap(u32, i64),
+    FlyerSetWeight(u32, i64),
+    FlyerSetLang(u32, i64),
+    FlyerAudience(u32, i64),
+    FlyerReport(u32, i64),
+    FlyerQueue,
+    FlyerApprove(i64),
+    FlyerReject(i64),
     FlyerDeleteAllConfirm,
+
+    ChannelList,
+    ChannelDel(u32, i64),
+    ChannelToggle(u32, i64),
+    ChannelSetAmount(u32, i64),
+    ChannelSetMaxSub(u32, i64),
+    ChannelSetClawDays(u32, i64),
+    ChannelDeleteAllConfirm,
+
+    TemplateList,
+    Template(u8, Lang),
+    TemplateEdit(u8, Lang),
+    TemplateReset(u8, Lang),
+    TemplateSave,
 }
 
 macro_rules! kd {
@@ -103,21 +208,28 @@ macro_rules! kd {
 pub(crate) use kd;
 
 impl KeyData {
-    pub fn main_menu_btn() -> InlineKeyboardButton {
-        InlineKeyboardButton::callback("💼 منو", KeyData::Menu)
+    pub fn main_menu_btn(lang: Lang) -> InlineKeyboardButton {
+        InlineKeyboardButton::callback(t!(lang, "btn_menu"), KeyData::Menu)
     }
-    pub fn donate_url() -> InlineKeyboardButton {
+    pub fn donate_url(lang: Lang) -> InlineKeyboardButton {
         InlineKeyboardButton::url(
-            "حمایت مالی 💰",
+            t!(lang, "btn_donate"),
             Config::get().donate_url.clone(),
         )
     }
-    pub fn donate_btn() -> InlineKeyboardButton {
-        InlineKeyboardButton::callback("حمایت مالی 💰", KeyData::Donate)
+    pub fn donate_btn(lang: Lang) -> InlineKeyboardButton {
+        InlineKeyboardButton::callback(t!(lang, "btn_donate"), KeyData::Donate)
+    }
+
+    pub fn tasks_btn(lang: Lang) -> InlineKeyboardButton {
+        InlineKeyboardButton::callback(t!(lang, "btn_tasks"), KeyData::Tasks)
     }
 
-    pub fn main_menu() -> InlineKeyboardMarkup {
-        InlineKeyboardMarkup::new([[Self::main_menu_btn(), Self::donate_btn()]])
+    pub fn main_menu(lang: Lang) -> InlineKeyboardMarkup {
+        InlineKeyboardMarkup::new([[
+            Self::main_menu_btn(lang),
+            Self::donate_btn(lang),
+        ]])
     }
 
     // pub fn nothing() -> InlineKeyboardButton {
@@ -156,6 +268,11 @@ pub enum State {
 
     AdminV2rayList,
     AdminV2rayAdd,
+    /// dry run of an import, waiting for the admin to commit the new links
+    AdminImportConfirm {
+        pool: Pool,
+        links: Vec<String>,
+    },
 
     AdminFlyerList,
     AdminFlyerAdd,
@@ -165,6 +282,17 @@ pub enum State {
     AdminFlyerSetMaxView(i64),
     AdminFlyerSetLink(i64),
     AdminFlyerSetLabel(i64),
+    AdminFlyerSetSchedule(i64),
+    AdminFlyerSetDailyMax(i64),
+    AdminFlyerSetUserCap(i64),
+    AdminFlyerSetWeight(i64),
+    AdminFlyerSetLang(i64),
+
+    AdminChannelList,
+    AdminChannelAdd,
+    AdminChannelSetAmount(i64),
+    AdminChannelSetMaxSub(i64),
+    AdminChannelSetClawDays(i64),
 
     AdminSetStarPricePt,
     AdminSetFreePt,
@@ -176,6 +304,42 @@ pub enum State {
     AdminSetVipMsg,
     AdminSetVipMaxViews,
     AdminSetDonateMsg,
+    AdminSetAdStarPrice,
+    AdminSurgeSet(Product),
+    AdminSetSurgeDecay,
+    AdminSetStreakBonus,
+    AdminSetStreakWindow,
+    AdminSetCaptchaEvery,
+    AdminSetCaptchaTries,
+    AdminSetCaptchaLockout,
+    AdminSetBundleSize,
+    AdminSetBundleDiscount,
+    AdminSetVoteHalfLife,
+    AdminSetVoteMinWeight,
+    AdminSetVoteDisablePct,
+    AdminSetVoteWindow,
+    AdminSetSubmitReward,
+    AdminSetSubmitVoteReward,
+
+    AdminTemplateEdit(u8, Lang),
+    AdminTemplateConfirm {
+        idx: u8,
+        lang: Lang,
+        body: String,
+    },
+
+    AdminFlyerReject(i64),
+    AdLabel,
+    AdMessage {
+        label: String,
+    },
+    AdLink {
+        label: String,
+        mid: i64,
+    },
+    SubmitProxy,
+    /// owner uploads a database to restore
+    OwnerRestore,
 }
 
 // pub trait CutOff {
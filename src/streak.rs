@@ -0,0 +1,61 @@
+use crate::db::{Karbar, Settings};
+use crate::i18n::t;
+use crate::state::KeyData;
+use crate::{Ctx, TB, surge, utils};
+use std::time::Duration;
+use teloxide::prelude::*;
+use teloxide::types::{InlineKeyboardButton, InlineKeyboardMarkup};
+
+/// free points for the `streak`th claim in a row, starting at 1
+pub fn reward(settings: &Settings, streak: i64) -> i64 {
+    let bonus = surge::parse_curve(&settings.streak_bonus).unwrap_or_default();
+    surge::price(settings.free_points, &bonus, streak - 1)
+}
+
+/// tell karbars with a streak that their next claim is available
+pub async fn reminder(bot: TB, ctx: Ctx) {
+    loop {
+        tokio::time::sleep(Duration::from_secs(60)).await;
+
+        let settings = Settings::get(&ctx.db).await;
+        let now = utils::now();
+        let due = match Karbar::streak_due(&ctx, &settings, now).await {
+            Ok(v) => v,
+            Err(e) => {
+                log::error!("streak due failed: {e:?}");
+                continue;
+            }
+        };
+
+        for k in due {
+            match Karbar::streak_reminded(&ctx, k.tid).await {
+                Ok(true) => {}
+                Ok(false) => continue,
+                Err(e) => {
+                    log::error!("streak reminded failed: {e:?}");
+                    continue;
+                }
+            }
+
+            let lang = k.lang();
+            let next = k.streak + 1;
+            let m = t!(
+                lang,
+                "streak_remind",
+                streak = k.streak,
+                points = reward(&settings, next)
+            );
+            let kb = InlineKeyboardMarkup::new([
+                vec![InlineKeyboardButton::callback(
+                    t!(lang, "btn_free_point"),
+                    KeyData::GetRealFreePoints,
+                )],
+                vec![KeyData::main_menu_btn(lang)],
+            ]);
+            if let Err(e) = bot.send_message(k.cid(), m).reply_markup(kb).await
+            {
+                log::warn!("streak reminder to {} failed: {e:?}", k.tid);
+            }
+        }
+    }
+}
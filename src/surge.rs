@@ -0,0 +1,112 @@
+use crate::db::Settings;
+use crate::i18n::{Lang, t};
+
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    serde::Serialize,
+    serde::Deserialize
+)]
+#[serde(rename_all = "snake_case")]
+/// things a karbar pays points for
+pub enum Product {
+    Proxy,
+    V2ray,
+    Vip,
+    /// a few proxies at a discount, priced on the proxy curve
+    Bundle,
+}
+
+impl Product {
+    /// products with their own cost and curve
+    pub const ALL: [Product; 3] =
+        [Product::Proxy, Product::V2ray, Product::Vip];
+
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Proxy => "پروکسی",
+            Self::V2ray => "v2ray",
+            Self::Vip => "VIP",
+            Self::Bundle => "بسته پروکسی",
+        }
+    }
+
+    pub const fn code(self) -> &'static str {
+        match self {
+            Self::Proxy => "proxy",
+            Self::V2ray => "v2ray",
+            Self::Vip => "vip",
+            Self::Bundle => "bundle",
+        }
+    }
+
+    pub fn from_code(code: &str) -> Option<Self> {
+        Self::ALL.into_iter().chain([Self::Bundle]).find(|p| p.code() == code)
+    }
+
+    pub fn label(self, lang: Lang) -> &'static str {
+        match self {
+            Self::Proxy => t!(lang, "product_proxy"),
+            Self::V2ray => t!(lang, "product_v2ray"),
+            Self::Vip => t!(lang, "product_vip"),
+            Self::Bundle => t!(lang, "product_bundle"),
+        }
+    }
+
+    /// base cost without surge
+    pub fn cost(self, settings: &Settings) -> i64 {
+        match self {
+            Self::Proxy => settings.proxy_cost,
+            Self::V2ray => settings.v2ray_cost,
+            Self::Vip => settings.vip_cost,
+            Self::Bundle => {
+                let off = 100 - settings.bundle_discount.clamp(0, 100);
+                settings.proxy_cost * settings.bundle_size.max(1) * off / 100
+            }
+        }
+    }
+
+    pub fn curve(self, settings: &Settings) -> Vec<f64> {
+        let raw = match self {
+            Self::Proxy | Self::Bundle => &settings.proxy_surge,
+            Self::V2ray => &settings.v2ray_surge,
+            Self::Vip => &settings.vip_surge,
+        };
+        parse_curve(raw).unwrap_or_default()
+    }
+}
+
+/// comma separated extra percents. e.g. `0,0.1,3,10`
+pub fn parse_curve(value: &str) -> Option<Vec<f64>> {
+    let mut out = Vec::new();
+    for p in value.split(',') {
+        let v = p.trim().parse::<f64>().ok()?;
+        if !v.is_finite() || v < 0.0 {
+            return None;
+        }
+        out.push(v);
+    }
+    if out.is_empty() {
+        return None;
+    }
+    Some(out)
+}
+
+/// the stack after `decay` seconds for each step has passed since `at`
+pub fn decayed(stack: i64, at: i64, now: i64, decay: i64) -> i64 {
+    if decay <= 0 {
+        return 0;
+    }
+    (stack - (now - at).max(0) / decay).max(0)
+}
+
+/// cost for a request with `stack` earlier requests.
+/// stacks past the end of the curve use its last step
+pub fn price(cost: i64, curve: &[f64], stack: i64) -> i64 {
+    let Some(last) = curve.last() else { return cost };
+    let extra = curve.get(stack.max(0) as usize).unwrap_or(last);
+    cost + (cost as f64 * extra / 100.0) as i64
+}
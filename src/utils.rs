@@ -29,3 +29,218 @@ pub fn cut_off(value: &mut String, len: usize) {
     }
     value.truncate(idx);
 }
+
+fn month_days(y: i64, m: i64) -> i64 {
+    let leap = y % 4 == 0 && (y % 100 != 0 || y % 400 == 0);
+    match m {
+        2 if leap => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// parse a `YYYY-MM-DD` date into unix time at 00:00 UTC
+pub fn parse_date(value: &str) -> Option<i64> {
+    let mut it = value.trim().splitn(3, '-');
+    let y = it.next()?.parse::<i64>().ok()?;
+    let m = it.next()?.parse::<i64>().ok()?;
+    let d = it.next()?.parse::<i64>().ok()?;
+    if !(1..=12).contains(&m) || !(1..=month_days(y, m)).contains(&d) {
+        return None;
+    }
+
+    let y = if m <= 2 { y - 1 } else { y };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let doy = (153 * (m + if m > 2 { -3 } else { 9 }) + 2) / 5 + d - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    Some((era * 146097 + doe - 719468) * 86400)
+}
+
+/// format unix time as `YYYY-MM-DD` in UTC
+pub fn fmt_date(ts: i64) -> String {
+    let z = ts.div_euclid(86400) + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let d = doy - (153 * mp + 2) / 5 + 1;
+    let m = if mp < 10 { mp + 3 } else { mp - 9 };
+    let y = yoe + era * 400 + if m <= 2 { 1 } else { 0 };
+    format!("{y:04}-{m:02}-{d:02}")
+}
+
+/// check `text` against the html subset telegram accepts.
+/// the error is a message for the admin
+pub fn check_html(text: &str) -> Result<(), String> {
+    const TAGS: [&str; 16] = [
+        "b",
+        "strong",
+        "i",
+        "em",
+        "u",
+        "ins",
+        "s",
+        "strike",
+        "del",
+        "span",
+        "tg-spoiler",
+        "a",
+        "code",
+        "pre",
+        "blockquote",
+        "tg-emoji",
+    ];
+
+    let mut stack = Vec::<String>::new();
+    let mut rest = text;
+    while let Some(idx) = rest.find(['<', '&']) {
+        let tail = &rest[idx..];
+        if let Some(ent) = tail.strip_prefix('&') {
+            let Some(end) = ent.find(';') else {
+                return Err(String::from("& باید به صورت &amp; نوشته شود"));
+            };
+            let ent = &ent[..end];
+            let num = |n: &str| match n.strip_prefix(['x', 'X']) {
+                Some(h) => u32::from_str_radix(h, 16).is_ok(),
+                None => n.parse::<u32>().is_ok(),
+            };
+            let ok = matches!(ent, "lt" | "gt" | "amp" | "quot")
+                || ent.strip_prefix('#').is_some_and(num);
+            if !ok {
+                return Err(format!("موجودیت ناشناخته: &{ent};"));
+            }
+            rest = &tail[end + 2..];
+            continue;
+        }
+
+        let Some(end) = tail.find('>') else {
+            return Err(String::from("< باید به صورت &lt; نوشته شود"));
+        };
+        let inner = &tail[1..end];
+        if let Some(name) = inner.strip_prefix('/') {
+            let name = name.trim().to_lowercase();
+            match stack.pop() {
+                Some(open) if open == name => {}
+                Some(open) => {
+                    return Err(format!("انتظار </{open}> بود نه </{name}>"));
+                }
+                None => return Err(format!("تگ </{name}> باز نشده است")),
+            }
+        } else {
+            let name = inner
+                .split_whitespace()
+                .next()
+                .unwrap_or_default()
+                .to_lowercase();
+            if !TAGS.contains(&name.as_str()) {
+                return Err(format!("تگ <{name}> پشتیبانی نمی شود"));
+            }
+            if name == "a" && !inner.contains("href=") {
+                return Err(String::from("تگ <a> باید href داشته باشد"));
+            }
+            stack.push(name);
+        }
+        rest = &tail[end + 1..];
+    }
+
+    if let Some(open) = stack.pop() {
+        return Err(format!("تگ <{open}> بسته نشده است"));
+    }
+
+    Ok(())
+}
+
+const B64: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// standard base64 with padding
+pub fn b64_encode(data: &[u8]) -> String {
+    let mut out = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let b = [
+            chunk[0],
+            *chunk.get(1).unwrap_or(&0),
+            *chunk.get(2).unwrap_or(&0),
+        ];
+        let n = (b[0] as u32) << 16 | (b[1] as u32) << 8 | b[2] as u32;
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(B64[(n >> (18 - i * 6)) as usize & 63] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+/// standard or url safe base64, padding and whitespace are optional
+pub fn b64_decode(data: &str) -> Option<Vec<u8>> {
+    let mut out = Vec::with_capacity(data.len() * 3 / 4);
+    let (mut acc, mut bits) = (0u32, 0);
+    for c in data.bytes() {
+        let v = match c {
+            b'A'..=b'Z' => c - b'A',
+            b'a'..=b'z' => c - b'a' + 26,
+            b'0'..=b'9' => c - b'0' + 52,
+            b'+' | b'-' => 62,
+            b'/' | b'_' => 63,
+            b'=' => break,
+            c if c.is_ascii_whitespace() => continue,
+            _ => return None,
+        };
+        acc = acc << 6 | v as u32;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((acc >> bits) as u8);
+        }
+    }
+    Some(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dates() {
+        assert_eq!(parse_date("1970-01-01"), Some(0));
+        assert_eq!(parse_date("2024-03-01"), Some(1709251200));
+        assert_eq!(fmt_date(1709251200 - 86400), "2024-02-29");
+        assert_eq!(fmt_date(951782400), "2000-02-29");
+        assert_eq!(
+            fmt_date(parse_date("2023-12-31").unwrap() + 86400),
+            "2024-01-01"
+        );
+        assert_eq!(
+            fmt_date(parse_date("2023-04-30").unwrap() + 86400),
+            "2023-05-01"
+        );
+    }
+
+    #[test]
+    fn invalid_dates() {
+        assert!(parse_date("2000-02-29").is_some());
+        assert!(parse_date("2024-02-29").is_some());
+        assert!(parse_date("1900-02-29").is_none());
+        assert!(parse_date("2023-02-29").is_none());
+        assert!(parse_date("2023-04-31").is_none());
+        assert!(parse_date("2023-13-01").is_none());
+        assert!(parse_date("2023-01-00").is_none());
+        assert!(parse_date("2023-01").is_none());
+    }
+
+    #[test]
+    fn date_round_trip() {
+        let start = parse_date("1960-01-01").unwrap();
+        let end = parse_date("2101-01-01").unwrap();
+        for ts in (start..end).step_by(86400) {
+            assert_eq!(parse_date(&fmt_date(ts)), Some(ts));
+            assert_eq!(fmt_date(ts + 86399), fmt_date(ts));
+        }
+    }
+}
@@ -0,0 +1,254 @@
+//! vote scoring, the wilson lower bound of exponentially decayed and
+//! reputation weighted votes
+
+use crate::config::Config;
+use crate::db::{Karbar, Proxy, Settings, Submission, V2ray};
+use crate::error::AppErr;
+use crate::i18n::{Lang, t};
+use crate::state::KeyData;
+use crate::{Ctx, TB, utils};
+use std::time::Duration;
+use teloxide::prelude::*;
+use teloxide::types::ChatId;
+
+/// 95% confidence
+const Z: f64 = 1.96;
+/// pseudo votes every item starts with, so a few up votes move the score
+/// up from where an unvoted item is and never down
+const PRIOR_UP: f64 = 2.0;
+const PRIOR_DOWN: f64 = 2.0;
+
+/// score of an item without votes
+pub fn neutral() -> f64 {
+    Tally::default().score()
+}
+
+/// lower bound of the real ratio of `pos` in `n`
+pub fn wilson_lower(pos: f64, n: f64) -> f64 {
+    if n <= 0.0 {
+        return 0.0;
+    }
+    let p = (pos / n).clamp(0.0, 1.0);
+    let z2 = Z * Z;
+    let center = p + z2 / (2.0 * n);
+    let margin = Z * ((p * (1.0 - p) + z2 / (4.0 * n)) / n).sqrt();
+    ((center - margin) / (1.0 + z2 / n)).max(0.0)
+}
+
+/// weight left of a vote cast at `at`
+pub fn decay(at: i64, now: i64, half_life: i64) -> f64 {
+    if half_life <= 0 {
+        return 1.0;
+    }
+    0.5f64.powf((now - at).max(0) as f64 / half_life as f64)
+}
+
+#[derive(Debug, Default)]
+pub struct Tally {
+    pub up: f64,
+    pub total: f64,
+}
+
+impl Tally {
+    pub fn add(
+        &mut self, kind: i64, weight: f64, at: i64, now: i64, st: &Settings,
+    ) {
+        let w = weight.max(0.0) * decay(at, now, st.vote_half_life);
+        if kind > 0 {
+            self.up += w;
+        }
+        self.total += w;
+    }
+
+    /// wilson lower bound of the votes with the prior added
+    pub fn score(&self) -> f64 {
+        let up = self.up + PRIOR_UP;
+        wilson_lower(up, self.total + PRIOR_UP + PRIOR_DOWN)
+    }
+
+    /// confident enough that most voters found it broken
+    pub fn is_bad(&self, st: &Settings) -> bool {
+        if self.total < st.vote_min_weight as f64 {
+            return false;
+        }
+        let down = wilson_lower(self.total - self.up, self.total);
+        down * 100.0 >= st.vote_disable_pct as f64
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// outcome of a vote
+pub enum Cast {
+    Added,
+    Changed,
+    /// same as the current vote of the karbar
+    Same,
+    NotReceived,
+    /// the voting window after the delivery has passed
+    Expired,
+}
+
+impl Cast {
+    /// if the score of the item must be updated
+    pub fn counted(self) -> bool {
+        matches!(self, Self::Added | Self::Changed)
+    }
+
+    /// catalog key of the message for the karbar
+    pub fn key(self) -> &'static str {
+        match self {
+            Self::Added => "vote_ok",
+            Self::Changed => "vote_changed",
+            Self::Same => "vote_dup",
+            Self::NotReceived => "vote_not_received",
+            Self::Expired => "vote_expired",
+        }
+    }
+}
+
+/// vote weight of a karbar from 0.1 to 1, none when it can not vote.
+/// mixes account age, recent activity and how often its past votes
+/// agreed with the other voters
+pub async fn reputation(
+    ctx: &Ctx, karbar: &Karbar, now: i64,
+) -> Result<Option<f64>, AppErr> {
+    let age = now - karbar.created_at;
+    if karbar.banned || age < Config::VOTE_MIN_AGE {
+        return Ok(None);
+    }
+
+    let age = (age as f64 / (30.0 * 86400.0)).min(1.0);
+    let since = now - 30 * 86400;
+    let activity =
+        (karbar.deliveries(ctx, since).await? as f64 / 10.0).min(1.0);
+    let (agree, total) = karbar.vote_agreement(ctx).await?;
+    let agreement = (agree as f64 + 1.0) / (total as f64 + 2.0);
+
+    let rep = 0.3 * age + 0.2 * activity + 0.5 * agreement;
+    Ok(Some(rep.clamp(0.1, 1.0)))
+}
+
+#[derive(Debug, sqlx::FromRow)]
+/// light down votes on one item in the last day
+pub struct Cluster {
+    pub item: i64,
+    pub votes: i64,
+    /// creation time of the oldest and newest voter
+    pub first: i64,
+    pub last: i64,
+    /// comma separated voter tids
+    pub voters: String,
+}
+
+pub async fn notify_disabled(bot: &TB, item: String) {
+    let m = format!("{item}\n\nبه دلیل رای های منفی غیرفعال شد ❌");
+    let r = bot
+        .send_message(Config::get().dev, m)
+        .parse_mode(teloxide::types::ParseMode::Html)
+        .reply_markup(KeyData::main_menu(Lang::Fa))
+        .await;
+    if let Err(e) = r {
+        log::error!("send message to dev failed: {e:?}");
+    }
+}
+
+/// decay changes the scores without new votes, rescore every item
+pub async fn rescore(bot: TB, ctx: Ctx) {
+    loop {
+        let settings = Settings::get(&ctx.db).await;
+        let now = utils::now();
+        match Proxy::rescore_all(&ctx, &settings, now).await {
+            Ok(v) => {
+                for px in v {
+                    notify_disabled(&bot, px.to_string()).await;
+                }
+            }
+            Err(e) => log::error!("proxy rescore failed: {e:?}"),
+        }
+        match V2ray::rescore_all(&ctx, &settings, now).await {
+            Ok(v) => {
+                for v2 in v {
+                    notify_disabled(&bot, v2.to_string()).await;
+                }
+            }
+            Err(e) => log::error!("v2ray rescore failed: {e:?}"),
+        }
+        if let Err(e) = reward_submitters(&bot, &ctx, &settings).await {
+            log::error!("submitter reward failed: {e:?}");
+        }
+
+        tokio::time::sleep(Duration::from_secs(Config::RESCORE_EVERY)).await;
+    }
+}
+
+/// pay the submitters of proxies that got enough good votes
+async fn reward_submitters(
+    bot: &TB, ctx: &Ctx, st: &Settings,
+) -> Result<(), AppErr> {
+    let good = Config::SUBMIT_GOOD_VOTES;
+    for mut sub in Submission::good_unrewarded(ctx, good).await? {
+        sub.vote_rewarded = true;
+        sub.set(ctx).await?;
+        Karbar::add_points(ctx, sub.karbar, st.submit_vote_reward).await?;
+
+        let lang = Karbar::find_with_tid(ctx, sub.karbar)
+            .await
+            .map(|k| k.lang())
+            .unwrap_or_default();
+        let m = t!(lang, "submit_good_votes", points = st.submit_vote_reward);
+        let _ = bot.send_message(ChatId(sub.karbar), m).await;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wilson() {
+        assert_eq!(wilson_lower(0.0, 0.0), 0.0);
+        assert_eq!(wilson_lower(0.0, 5.0), 0.0);
+        let all = wilson_lower(10.0, 10.0);
+        assert!(all > 0.69 && all < 1.0, "{all}");
+        // the same ratio is more certain with more votes
+        assert!(wilson_lower(8.0, 10.0) < wilson_lower(80.0, 100.0));
+        assert!(wilson_lower(80.0, 100.0) < 0.8);
+        // a ratio above 1 is clamped
+        assert_eq!(wilson_lower(12.0, 10.0), all);
+    }
+
+    #[test]
+    fn decay_halves() {
+        assert_eq!(decay(0, 100, 0), 1.0);
+        assert_eq!(decay(100, 100, 50), 1.0);
+        assert_eq!(decay(200, 100, 50), 1.0);
+        assert_eq!(decay(0, 100, 100), 0.5);
+        assert_eq!(decay(0, 200, 100), 0.25);
+    }
+
+    #[test]
+    fn score_prior() {
+        let unvoted = Tally::default().score();
+        assert_eq!(unvoted, neutral());
+        assert!(unvoted > 0.0 && unvoted < 0.5);
+
+        // up votes of any weight never score below an unvoted item
+        let mut last = unvoted;
+        for up in [0.1, 0.5, 1.0, 2.0, 5.0, 20.0] {
+            let s = Tally { up, total: up }.score();
+            assert!(s > last, "{up}: {s} <= {last}");
+            last = s;
+        }
+
+        assert!(Tally { up: 0.0, total: 1.0 }.score() < unvoted);
+        assert!(Tally { up: 1.0, total: 4.0 }.score() < unvoted);
+
+        let mut st = Settings::default();
+        st.vote_half_life = 100;
+        let mut t = Tally::default();
+        t.add(1, 1.0, 0, 100, &st);
+        t.add(-1, 0.5, 100, 100, &st);
+        assert_eq!((t.up, t.total), (0.5, 1.0));
+    }
+}